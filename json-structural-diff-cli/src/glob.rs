@@ -0,0 +1,110 @@
+use std::path::Path;
+
+/// A glob pattern matched against paths relative to a root directory.
+///
+/// The supported syntax is:
+///
+/// - `?` matches any single character except `/`
+/// - `*` matches any sequence of characters except `/`
+/// - `**` matches any sequence of characters, `/` included, and `**/`
+///   matches zero or more directories
+///
+/// A pattern without any `/` is matched against the file name only,
+/// otherwise it is matched against the whole relative path.
+#[derive(Debug)]
+pub struct Glob {
+    pattern: Vec<char>,
+    match_path: bool,
+}
+
+impl Glob {
+    pub fn new(pattern: &str) -> Self {
+        Self {
+            pattern: pattern.chars().collect(),
+            match_path: pattern.contains('/'),
+        }
+    }
+
+    pub fn is_match(&self, relative_path: &Path) -> bool {
+        let text: Vec<char> = if self.match_path {
+            relative_path
+                .components()
+                .map(|component| component.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/")
+                .chars()
+                .collect()
+        } else {
+            relative_path
+                .file_name()
+                .map(|name| name.to_string_lossy().chars().collect())
+                .unwrap_or_default()
+        };
+        matches(&self.pattern, &text)
+    }
+
+    /// Matches the whole text, e.g. a key of an object.
+    pub fn is_match_str(&self, text: &str) -> bool {
+        matches(&self.pattern, &text.chars().collect::<Vec<_>>())
+    }
+}
+
+fn matches(pattern: &[char], text: &[char]) -> bool {
+    match pattern {
+        [] => text.is_empty(),
+        ['*', '*', '/', rest @ ..] => {
+            matches(rest, text)
+                || text
+                    .iter()
+                    .enumerate()
+                    .any(|(index, c)| *c == '/' && matches(rest, &text[index + 1..]))
+        }
+        ['*', '*', rest @ ..] => (0..=text.len()).any(|index| matches(rest, &text[index..])),
+        ['*', rest @ ..] => {
+            let segment_len = text.iter().position(|c| *c == '/').unwrap_or(text.len());
+            (0..=segment_len).any(|index| matches(rest, &text[index..]))
+        }
+        ['?', rest @ ..] => text
+            .split_first()
+            .is_some_and(|(c, text)| *c != '/' && matches(rest, text)),
+        [p, rest @ ..] => text
+            .split_first()
+            .is_some_and(|(c, text)| c == p && matches(rest, text)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::path::Path;
+
+    use super::Glob;
+
+    #[test]
+    fn test_glob() {
+        let glob = Glob::new("*.geojson");
+        assert!(glob.is_match(Path::new("map.geojson")));
+        assert!(glob.is_match(Path::new("maps/europe/map.geojson")));
+        assert!(!glob.is_match(Path::new("map.json")));
+
+        let glob = Glob::new("fixtures/**");
+        assert!(glob.is_match(Path::new("fixtures/a.json")));
+        assert!(glob.is_match(Path::new("fixtures/nested/a.json")));
+        assert!(!glob.is_match(Path::new("data/fixtures/a.json")));
+
+        let glob = Glob::new("**/fixtures/*.json");
+        assert!(glob.is_match(Path::new("fixtures/a.json")));
+        assert!(glob.is_match(Path::new("data/fixtures/a.json")));
+        assert!(!glob.is_match(Path::new("data/fixtures/nested/a.json")));
+
+        let glob = Glob::new("data/?.json");
+        assert!(glob.is_match(Path::new("data/a.json")));
+        assert!(!glob.is_match(Path::new("data/ab.json")));
+        assert!(!glob.is_match(Path::new("a.json")));
+
+        let glob = Glob::new("meta*");
+        assert!(glob.is_match_str("metadata"));
+        assert!(!glob.is_match_str("data"));
+        assert!(!glob.is_match_str("meta/data"));
+    }
+}
@@ -0,0 +1,130 @@
+use std::path::Path;
+
+use serde_json::Value;
+
+/// The format of an input file, detected from its extension.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    Json,
+    Yaml,
+    Toml,
+}
+
+impl Format {
+    /// Returns the format of the file at `path`, JSON unless its extension
+    /// is `yaml`, `yml` or `toml`.
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("yaml" | "yml") => Self::Yaml,
+            Some("toml") => Self::Toml,
+            _ => Self::Json,
+        }
+    }
+
+    /// Parses the contents of a file of this format into a JSON value.
+    pub fn parse(self, buffer: &[u8]) -> Result<Value, String> {
+        match self {
+            Self::Json => serde_json::from_slice(buffer).map_err(|err| err.to_string()),
+            Self::Yaml => parse_yaml(buffer),
+            Self::Toml => parse_toml(buffer),
+        }
+    }
+}
+
+#[cfg(feature = "yaml")]
+fn parse_yaml(buffer: &[u8]) -> Result<Value, String> {
+    let value: serde_yaml::Value = serde_yaml::from_slice(buffer).map_err(|err| err.to_string())?;
+    yaml_to_json(value)
+}
+
+#[cfg(not(feature = "yaml"))]
+fn parse_yaml(_buffer: &[u8]) -> Result<Value, String> {
+    Err("reading YAML files requires the `yaml` feature".to_owned())
+}
+
+/// Converts a YAML value into a JSON value, failing on the constructs
+/// which JSON cannot represent.
+#[cfg(feature = "yaml")]
+fn yaml_to_json(value: serde_yaml::Value) -> Result<Value, String> {
+    use serde_yaml::Value as Yaml;
+
+    Ok(match value {
+        Yaml::Null => Value::Null,
+        Yaml::Bool(boolean) => Value::Bool(boolean),
+        Yaml::Number(number) => {
+            if let Some(integer) = number.as_i64() {
+                Value::from(integer)
+            } else if let Some(integer) = number.as_u64() {
+                Value::from(integer)
+            } else {
+                number
+                    .as_f64()
+                    .and_then(serde_json::Number::from_f64)
+                    .map(Value::Number)
+                    .ok_or_else(|| format!("the YAML number {number} is not a JSON number"))?
+            }
+        }
+        Yaml::String(string) => Value::String(string),
+        Yaml::Sequence(sequence) => Value::Array(
+            sequence
+                .into_iter()
+                .map(yaml_to_json)
+                .collect::<Result<_, _>>()?,
+        ),
+        Yaml::Mapping(mapping) => Value::Object(
+            mapping
+                .into_iter()
+                .map(|(key, value)| match key {
+                    Yaml::String(key) => Ok((key, yaml_to_json(value)?)),
+                    key => Err(format!(
+                        "the YAML key {} is not a string, unlike JSON keys",
+                        serde_yaml::to_string(&key)
+                            .map_or_else(|_| format!("{key:?}"), |key| key.trim_end().to_owned())
+                    )),
+                })
+                .collect::<Result<_, _>>()?,
+        ),
+        Yaml::Tagged(tagged) => yaml_to_json(tagged.value)?,
+    })
+}
+
+#[cfg(feature = "toml")]
+fn parse_toml(buffer: &[u8]) -> Result<Value, String> {
+    let text = std::str::from_utf8(buffer).map_err(|err| err.to_string())?;
+    let value: toml::Value = toml::from_str(text).map_err(|err| err.to_string())?;
+    toml_to_json(value)
+}
+
+#[cfg(not(feature = "toml"))]
+fn parse_toml(_buffer: &[u8]) -> Result<Value, String> {
+    Err("reading TOML files requires the `toml` feature".to_owned())
+}
+
+/// Converts a TOML value into a JSON value, the dates and times becoming
+/// strings.
+#[cfg(feature = "toml")]
+fn toml_to_json(value: toml::Value) -> Result<Value, String> {
+    use toml::Value as Toml;
+
+    Ok(match value {
+        Toml::String(string) => Value::String(string),
+        Toml::Integer(integer) => Value::from(integer),
+        Toml::Float(float) => serde_json::Number::from_f64(float)
+            .map(Value::Number)
+            .ok_or_else(|| format!("the TOML float {float} is not a JSON number"))?,
+        Toml::Boolean(boolean) => Value::Bool(boolean),
+        Toml::Datetime(datetime) => Value::String(datetime.to_string()),
+        Toml::Array(array) => Value::Array(
+            array
+                .into_iter()
+                .map(toml_to_json)
+                .collect::<Result<_, _>>()?,
+        ),
+        Toml::Table(table) => Value::Object(
+            table
+                .into_iter()
+                .map(|(key, value)| Ok((key, toml_to_json(value)?)))
+                .collect::<Result<_, String>>()?,
+        ),
+    })
+}
@@ -1,56 +1,332 @@
 #[macro_use]
 extern crate clap;
 
+mod glob;
+mod input;
+mod side_by_side;
+
+use std::collections::BTreeSet;
+use std::fmt::{self, Write as _};
 use std::fs::File;
-use std::io::Write;
+use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
 use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 use clap::{App, Arg};
 use console::Term;
 use rayon::prelude::*;
 use walkdir::{DirEntry, WalkDir};
 
-use json_structural_diff::{colorize, JsonDiff};
+use serde_json::{Map, Value};
+
+use json_structural_diff::{
+    colorize_to_records_with, ContextOptions, DiffError, DiffLine, DiffOptions, JsonDiff,
+    JsonPointer, Theme,
+};
+
+use crate::glob::Glob;
+use crate::input::Format;
+use crate::side_by_side::{side_by_side, style};
 
+const STDIN_PATH: &str = "-";
+/// The path given by git for the missing side of an added or deleted file.
+const NULL_DEVICE_PATH: &str = "/dev/null";
+/// The width of the side-by-side view when stdout is not a terminal.
+const DEFAULT_WIDTH: usize = 80;
+
+#[allow(clippy::struct_excessive_bools)]
 struct Config {
     raw: bool,
+    compact: bool,
     only_keys: bool,
     color: bool,
+    /// The width of the side-by-side view, if enabled.
+    side_by_side: Option<usize>,
+    report_identical_files: bool,
+    /// The percentage of similarity from which the differences of two files
+    /// are not reported.
+    threshold: Option<f64>,
+    stat: bool,
+    include: Vec<Glob>,
+    exclude: Vec<Glob>,
+    /// The names or JSON Pointers of the keys not compared.
+    ignore_keys: Vec<String>,
+    /// The globs of the top-level keys compared, if not empty.
+    only_keys_matching: Vec<Glob>,
+}
+
+impl Config {
+    fn is_excluded(&self, relative_path: &Path) -> bool {
+        self.exclude.iter().any(|glob| glob.is_match(relative_path))
+    }
+
+    fn is_included(&self, relative_path: &Path) -> bool {
+        if self.include.is_empty() {
+            is_json(relative_path)
+        } else {
+            self.include.iter().any(|glob| glob.is_match(relative_path))
+        }
+    }
+
+    fn diff_options(&self) -> DiffOptions {
+        DiffOptions {
+            keys_only: self.only_keys,
+            ignore_keys: self.ignore_keys.clone(),
+            ..DiffOptions::default()
+        }
+    }
+
+    /// Ignores the top-level keys of the JSON files not matching any glob of
+    /// `only_keys_matching`.
+    fn restrict_keys(&self, options: &mut DiffOptions, jsons: [&Value; 2]) {
+        if self.only_keys_matching.is_empty() {
+            return;
+        }
+        let keys: BTreeSet<&String> = jsons
+            .into_iter()
+            .filter_map(Value::as_object)
+            .flat_map(|obj| obj.keys())
+            .filter(|key| {
+                !self
+                    .only_keys_matching
+                    .iter()
+                    .any(|glob| glob.is_match_str(key))
+            })
+            .collect();
+        options.ignore_keys.extend(keys.into_iter().map(|key| {
+            let mut pointer = JsonPointer::new();
+            pointer.push_key(key);
+            pointer.to_string()
+        }));
+    }
+}
+
+/// An error associated to the path which caused it.
+#[derive(Debug)]
+struct Error {
+    path: PathBuf,
+    reason: String,
+}
+
+impl Error {
+    fn new(path: &Path, reason: impl fmt::Display) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            reason: reason.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if is_stdin(&self.path) {
+            write!(f, "<stdin>: {}", self.reason)
+        } else {
+            write!(f, "{}: {}", self.path.display(), self.reason)
+        }
+    }
+}
+
+fn is_stdin(path: &Path) -> bool {
+    path.as_os_str() == STDIN_PATH
+}
+
+fn is_null_device(path: &Path) -> bool {
+    path.as_os_str() == NULL_DEVICE_PATH
+}
+
+fn read_input(path: &Path) -> Result<Vec<u8>, Error> {
+    if is_stdin(path) {
+        let mut buffer = Vec::new();
+        std::io::stdin()
+            .read_to_end(&mut buffer)
+            .map_err(|err| Error::new(path, err))?;
+        Ok(buffer)
+    } else {
+        std::fs::read(path).map_err(|err| Error::new(path, err))
+    }
+}
+
+fn parse_input(buffer: &[u8], path: &Path) -> Result<Value, Error> {
+    // The missing side of an added or deleted file is compared as `null`.
+    if is_null_device(path) {
+        return Ok(Value::Null);
+    }
+    Format::from_path(path)
+        .parse(buffer)
+        .map_err(|err| Error::new(path, err))
+}
+
+fn format_stats(json_diff: &JsonDiff) -> String {
+    // One line for each changed top-level key, then the total.
+    let stats_by_key = json_diff.stats_by_key();
+    let width = stats_by_key.keys().map(String::len).max().unwrap_or(0);
+    let mut output = String::new();
+    for (key, stats) in &stats_by_key {
+        let _ = writeln!(output, " {key:<width$} | {stats}");
+    }
+    let _ = writeln!(output, " {}", json_diff.stats());
+    output
 }
 
+/// Formats the lines of a JSON structural difference one below the other,
+/// as `colorize` does.
+fn format_lines(lines: &[DiffLine], theme: &Theme) -> String {
+    let mut output = String::new();
+    for line in lines {
+        // Writing into a `String` never fails.
+        let _ = writeln!(output, "{}", style(Some(line), theme).apply_to(line));
+    }
+    output
+}
+
+fn diff_error(path: &Path, err: DiffError) -> Error {
+    match err {
+        DiffError::Io(path, err) => Error::new(&path, err),
+        DiffError::Json(path, err) => Error::new(&path, err),
+        err => Error::new(path, err),
+    }
+}
+
+fn write_stdout(text: &str) -> Result<(), Error> {
+    Term::stdout()
+        .write_all(text.as_bytes())
+        .map_err(|err| Error::new(Path::new("<stdout>"), err))
+}
+
+/// Writes the difference into a file of `output_path`, or appends it to
+/// `stdout`, the text to be written to stdout.
+fn act_on_diff(
+    json_diff: JsonDiff,
+    output_filename: &Path,
+    output_path: Option<&PathBuf>,
+    cfg: &Config,
+    stdout: &mut String,
+) -> Result<bool, Error> {
+    let stats = cfg.stat.then(|| format_stats(&json_diff));
+    // Values may differ even without differences, e.g. comparing only keys.
+    let Some(result) = json_diff.into_diff() else {
+        return Ok(false);
+    };
+    let json_string = if let Some(stats) = stats {
+        stats
+    } else if cfg.raw {
+        let encoded = if cfg.compact {
+            serde_json::to_string(&result)
+        } else {
+            serde_json::to_string_pretty(&result)
+        };
+        encoded.map_err(|err| Error::new(output_filename, err))?
+    } else {
+        // A whole document replaced by a value of another type is described,
+        // since it is otherwise only shown as deleted and added.
+        let context_options = ContextOptions {
+            describe_root_type: true,
+            ..ContextOptions::default()
+        };
+        let lines = colorize_to_records_with(&result, &context_options);
+        let theme = if cfg.color {
+            Theme::default()
+        } else {
+            Theme::plain()
+        };
+        if let Some(width) = cfg.side_by_side {
+            side_by_side(&lines, width, &theme)
+        } else {
+            format_lines(&lines, &theme)
+        }
+    };
+    // The formatted differences already end with a newline, unlike the raw
+    // JSON encoding.
+    let json_string = if json_string.ends_with('\n') {
+        json_string
+    } else {
+        json_string + "\n"
+    };
+    if let Some(output_path) = output_path {
+        let output_file_path = output_path.join(output_filename);
+        File::create(&output_file_path)
+            .and_then(|mut output_file| output_file.write_all(json_string.as_bytes()))
+            .map_err(|err| Error::new(&output_file_path, err))?;
+    } else {
+        stdout.push_str(&json_string);
+    }
+    Ok(true)
+}
+
+/// The outcome of the comparison of two files.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Outcome {
+    Identical,
+    /// Different, but similar enough not to be reported.
+    Similar,
+    Different,
+}
+
+/// Writes the difference as `act_on_diff` does, or appends it to
+/// `combined`, the section of the single output file, if any.
 fn act_on_file(
     path1: &PathBuf,
     path2: &PathBuf,
     output_path: Option<&PathBuf>,
     cfg: &Config,
-) -> std::io::Result<()> {
-    let buffer1 = std::fs::read(path1).unwrap();
-    let buffer2 = std::fs::read(path2).unwrap();
-
-    if let (Ok(json1), Ok(json2)) = (
-        serde_json::from_slice(&buffer1),
-        serde_json::from_slice(&buffer2),
-    ) {
-        if json1 != json2 {
-            let json_diff = JsonDiff::diff(&json1, &json2, cfg.only_keys);
-            let result = json_diff.diff.unwrap();
-            let json_string = if cfg.raw {
-                serde_json::to_string_pretty(&result)?
-            } else {
-                colorize(&result, cfg.color)
-            };
-            if let Some(output_path) = output_path {
-                let output_filename = path1.file_name().unwrap().to_str().unwrap();
-                let mut output_file = File::create(output_path.join(output_filename))?;
-                writeln!(&mut output_file, "{json_string}")?;
-            } else {
-                let mut term = Term::stdout();
-                term.write_all(json_string.as_bytes())?;
-            }
+    stdout: &mut String,
+    combined: Option<&mut String>,
+) -> Result<Outcome, Error> {
+    let mut options = cfg.diff_options();
+    // The library only reads JSON files, and the keys to compare depend on
+    // both of them.
+    let is_json_file = |path: &Path| {
+        !is_stdin(path) && !is_null_device(path) && Format::from_path(path) == Format::Json
+    };
+    let json_diff =
+        if is_json_file(path1) && is_json_file(path2) && cfg.only_keys_matching.is_empty() {
+            JsonDiff::diff_files(path1, path2, &options)
+        } else {
+            let json1 = parse_input(&read_input(path1)?, path1)?;
+            let json2 = parse_input(&read_input(path2)?, path2)?;
+            cfg.restrict_keys(&mut options, [&json1, &json2]);
+            JsonDiff::try_diff_with_options(&json1, &json2, &options)
+        }
+        .map_err(|err| diff_error(path1, err))?;
+
+    // Files similar enough are neither reported as different nor as
+    // identical.
+    if let Some(threshold) = cfg.threshold {
+        if json_diff.diff.is_some() && json_diff.similarity() * 100. >= threshold {
+            return Ok(Outcome::Similar);
         }
     }
-    Ok(())
+
+    // Name the output file after the first input which is not stdin.
+    let named_path = if is_stdin(path1) { path2 } else { path1 };
+    let output_filename = named_path
+        .file_name()
+        .map(Path::new)
+        .ok_or_else(|| Error::new(named_path, "the path has no file name"))?;
+    let differences_found = if let Some(combined) = combined {
+        act_on_diff(json_diff, output_filename, None, cfg, combined)?
+    } else {
+        act_on_diff(json_diff, output_filename, output_path, cfg, stdout)?
+    };
+    if differences_found {
+        return Ok(Outcome::Different);
+    }
+    if cfg.report_identical_files {
+        let message = format!(
+            "Files {} and {} are identical",
+            path1.display(),
+            path2.display()
+        );
+        // The raw output is only made of JSON.
+        if cfg.raw {
+            eprintln!("{message}");
+        } else {
+            let _ = writeln!(stdout, "{message}");
+        }
+    }
+    Ok(Outcome::Identical)
 }
 
 fn is_hidden(entry: &DirEntry) -> bool {
@@ -60,65 +336,282 @@ fn is_hidden(entry: &DirEntry) -> bool {
         .is_some_and(|s| s.starts_with('.'))
 }
 
-fn explore(path1: &PathBuf, path2: &PathBuf, output_path: Option<&PathBuf>, cfg: &Config) {
-    WalkDir::new(path1)
+fn is_json(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|extension| extension == "json")
+}
+
+fn walk_error(root: &Path, err: &walkdir::Error) -> Error {
+    Error::new(err.path().unwrap_or(root), err)
+}
+
+fn relative_path<'a>(root: &Path, path: &'a Path) -> &'a Path {
+    path.strip_prefix(root).unwrap_or(path)
+}
+
+fn collect_files(root: &Path, cfg: &Config) -> Result<BTreeSet<PathBuf>, Error> {
+    let mut files = BTreeSet::new();
+    let walker = WalkDir::new(root)
         .into_iter()
-        .filter_entry(|e| !is_hidden(e))
-        .zip(
-            WalkDir::new(path2)
-                .into_iter()
-                .filter_entry(|e| !is_hidden(e)),
-        )
-        .par_bridge()
-        .for_each(|(entry1, entry2)| {
-            let entry1 = entry1.as_ref().unwrap();
-            let path1_file: PathBuf = entry1.path().to_path_buf();
-            let entry2 = entry2.as_ref().unwrap();
-            let path2_file: PathBuf = entry2.path().to_path_buf();
-            if path1_file.is_file()
-                && path2_file.is_file()
-                && path1_file.extension().unwrap() == "json"
-                && path2_file.extension().unwrap() == "json"
-            {
-                act_on_file(&path1_file, &path2_file, output_path, cfg).unwrap();
-            }
-        });
+        .filter_entry(|e| !is_hidden(e) && !cfg.is_excluded(relative_path(root, e.path())));
+    for entry in walker {
+        let entry = entry.map_err(|err| walk_error(root, &err))?;
+        let relative = relative_path(root, entry.path());
+        if entry.path().is_file() && cfg.is_included(relative) {
+            files.insert(relative.to_path_buf());
+        }
+    }
+    Ok(files)
+}
+
+/// A callback called with the number of files already processed, the total
+/// number of files and the path of the last one, relative to the roots.
+///
+/// It is called from the threads of the `rayon` pool.
+type Progress<'a> = &'a (dyn Fn(usize, usize, &Path) + Send + Sync);
+
+fn report_progress(processed: usize, total: usize, relative: &Path) {
+    eprintln!("[{processed}/{total}] {}", relative.display());
+}
+
+/// Writes the sections of the files into a single output file, in the order
+/// of their paths: a JSON object mapping each path to its raw difference,
+/// or the differences preceded by their paths.
+fn write_single_file(
+    single_file: &Path,
+    sections: Vec<(&PathBuf, String)>,
+    cfg: &Config,
+) -> Result<(), Error> {
+    let text = if cfg.raw && !cfg.stat {
+        let mut diffs = Map::new();
+        for (relative, section) in sections {
+            let diff = serde_json::from_str(&section).map_err(|err| Error::new(relative, err))?;
+            diffs.insert(relative.display().to_string(), diff);
+        }
+        let encoded = if cfg.compact {
+            serde_json::to_string(&diffs)
+        } else {
+            serde_json::to_string_pretty(&diffs)
+        };
+        encoded.map_err(|err| Error::new(single_file, err))? + "\n"
+    } else {
+        let mut text = String::new();
+        for (relative, section) in sections {
+            let _ = write!(text, "File {}:\n{section}", relative.display());
+        }
+        text
+    };
+    File::create(single_file)
+        .and_then(|mut file| file.write_all(text.as_bytes()))
+        .map_err(|err| Error::new(single_file, err))
+}
+
+fn explore(
+    path1: &Path,
+    path2: &Path,
+    output_path: Option<&PathBuf>,
+    single_file: Option<&Path>,
+    cfg: &Config,
+    progress: Progress<'_>,
+) -> Result<bool, Error> {
+    // Files are paired by their paths relative to the roots.
+    let files1 = collect_files(path1, cfg)?;
+    let files2 = collect_files(path2, cfg)?;
+
+    let files = files1.union(&files2).collect::<Vec<_>>();
+    let total = files.len();
+    let processed = AtomicUsize::new(0);
+    let results: Vec<Result<(bool, String, String), Error>> = files
+        .into_par_iter()
+        .map(|relative| {
+            let mut stdout = String::new();
+            let mut section = String::new();
+            let result = match (files1.contains(relative), files2.contains(relative)) {
+                (true, true) => act_on_file(
+                    &path1.join(relative),
+                    &path2.join(relative),
+                    output_path,
+                    cfg,
+                    &mut stdout,
+                    single_file.is_some().then_some(&mut section),
+                )
+                .map(|outcome| outcome == Outcome::Different),
+                (true, false) => {
+                    let _ = writeln!(
+                        stdout,
+                        "Only in {}: {}",
+                        path1.display(),
+                        relative.display()
+                    );
+                    Ok(true)
+                }
+                _ => {
+                    let _ = writeln!(
+                        stdout,
+                        "Only in {}: {}",
+                        path2.display(),
+                        relative.display()
+                    );
+                    Ok(true)
+                }
+            };
+            progress(
+                processed.fetch_add(1, Ordering::Relaxed) + 1,
+                total,
+                relative,
+            );
+            result.map(|differences_found| (differences_found, stdout, section))
+        })
+        .collect();
+
+    // The files are compared in any order, but their outputs are written in
+    // the order of their paths, so that the output is always the same.
+    let mut differences_found = false;
+    let mut sections = Vec::new();
+    for (relative, result) in files1.union(&files2).zip(results) {
+        let (found, stdout, section) = result?;
+        write_stdout(&stdout)?;
+        if !section.is_empty() {
+            sections.push((relative, section));
+        }
+        differences_found |= found;
+    }
+    if let Some(single_file) = single_file {
+        write_single_file(single_file, sections, cfg)?;
+    }
+    Ok(differences_found)
 }
 
 fn exist_or_exit(path: &Path, which_path: &str) {
-    if !(path.exists()) {
+    if !(is_stdin(path) || path.exists()) {
         eprintln!(
-            "The {} path `{}` is not correct",
-            which_path,
-            path.to_str().unwrap()
+            "error: {}",
+            Error::new(path, format!("the {which_path} path does not exist"))
         );
-        process::exit(1);
+        process::exit(2);
     }
 }
 
-fn main() {
-    let matches = App::new("json-diff")
+#[allow(clippy::too_many_lines)]
+fn build_app(authors: &str) -> App<'_, '_> {
+    App::new("json-diff")
         .version(crate_version!())
-        .author(&*env!("CARGO_PKG_AUTHORS").replace(':', "\n"))
+        .author(authors)
         .about("Find the differences between two input json files")
+        .after_help(
+            "EXIT STATUS:\n    \
+             0    No differences were found\n    \
+             1    Some differences were found\n    \
+             2    An error occurred",
+        )
         .arg(
             Arg::with_name("color")
                 .help("Colored output")
                 .short("c")
-                .long("--[no-]color"),
+                .long("color")
+                .overrides_with("no-color"),
+        )
+        .arg(
+            Arg::with_name("no-color")
+                .help("Uncolored output, the default [the last of --color and --no-color wins]")
+                .long("no-color")
+                .overrides_with("color"),
         )
         .arg(
             Arg::with_name("raw")
-                .help("Display raw JSON encoding of the diff")
+                .help("Display raw JSON encoding of the diff, null for identical files")
                 .short("j")
                 .long("raw-json"),
         )
+        .arg(
+            Arg::with_name("compact")
+                .help("Display the raw JSON encoding of the diff on a single line")
+                .long("compact"),
+        )
+        .arg(
+            Arg::with_name("side-by-side")
+                .help("Display the old and new json files side by side, in two columns")
+                .short("y")
+                .long("side-by-side"),
+        )
+        .arg(
+            Arg::with_name("width")
+                .help("Width of the side-by-side view [default: the terminal width]")
+                .long("width")
+                .takes_value(true)
+                .requires("side-by-side"),
+        )
         .arg(
             Arg::with_name("keys")
                 .help("Compare only the keys, ignore the differences in values")
                 .short("k")
                 .long("keys-only"),
         )
+        .arg(
+            Arg::with_name("ignore-key")
+                .help(
+                    "Do not compare the key, at any depth, or the value at the JSON Pointer \
+                     if it starts with /",
+                )
+                .long("ignore-key")
+                .alias("ignore-keys")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1),
+        )
+        .arg(
+            Arg::with_name("only-key")
+                .help("Compare only the top-level keys matching the glob")
+                .long("only-key")
+                .alias("only-keys-matching")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1),
+        )
+        .arg(
+            Arg::with_name("report-identical-files")
+                .help("Report when the two files are identical")
+                .short("s")
+                .long("report-identical-files"),
+        )
+        .arg(
+            Arg::with_name("threshold")
+                .help(
+                    "Report only the files whose similarity is below the percentage, \
+                     between 0 and 100",
+                )
+                .long("threshold")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("stat")
+                .help("Display a summary of the changes instead of the diff")
+                .long("stat"),
+        )
+        .arg(
+            Arg::with_name("progress")
+                .help("Report the progress of the comparison of directories on stderr")
+                .long("progress"),
+        )
+        .arg(
+            Arg::with_name("include")
+                .help(
+                    "Compare only the files matching the glob when exploring directories \
+                     [default: *.json]",
+                )
+                .long("include")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1),
+        )
+        .arg(
+            Arg::with_name("exclude")
+                .help("Skip the files and directories matching the glob when exploring directories")
+                .long("exclude")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1),
+        )
         .arg(
             Arg::with_name("output")
                 .help("Output directory")
@@ -126,22 +619,62 @@ fn main() {
                 .long("output")
                 .takes_value(true),
         )
+        .arg(
+            Arg::with_name("single-file")
+                .help(
+                    "Write the differences of the files of two directories into a single file, \
+                     a JSON object mapping their paths to their diffs with --raw-json",
+                )
+                .long("single-file")
+                .takes_value(true)
+                .conflicts_with("output"),
+        )
+        .arg(
+            Arg::with_name("git")
+                .help(
+                    "Compare the old and new files given by git to a diff driver, \
+                     exiting with 0 even if some differences were found",
+                )
+                .long("git")
+                .takes_value(true)
+                .number_of_values(7)
+                .value_names(&[
+                    "path", "old-file", "old-hex", "old-mode", "new-file", "new-hex", "new-mode",
+                ])
+                .conflicts_with_all(&["first-json", "second-json", "output", "single-file"]),
+        )
         .arg(
             Arg::with_name("first-json")
-                .help("Old json file")
-                .required(true)
+                .help("Old json file, or `-` to read it from stdin")
+                .required_unless("git")
                 .takes_value(true),
         )
         .arg(
             Arg::with_name("second-json")
-                .help("New json file")
-                .required(true)
+                .help("New json file, or `-` to read it from stdin")
+                .required_unless("git")
                 .takes_value(true),
         )
-        .get_matches();
+}
 
-    let path1 = PathBuf::from(matches.value_of("first-json").unwrap());
-    let path2 = PathBuf::from(matches.value_of("second-json").unwrap());
+#[allow(clippy::too_many_lines)]
+fn main() {
+    let authors = env!("CARGO_PKG_AUTHORS").replace(':', "\n");
+    let matches = build_app(&authors).get_matches();
+
+    // git gives the path of the file, then the path, hash and mode of its
+    // old and new versions.
+    let git = matches
+        .values_of("git")
+        .map(|values| values.map(PathBuf::from).collect::<Vec<_>>());
+    let (path1, path2) = if let Some(git) = &git {
+        (git[1].clone(), git[4].clone())
+    } else {
+        (
+            PathBuf::from(matches.value_of("first-json").unwrap()),
+            PathBuf::from(matches.value_of("second-json").unwrap()),
+        )
+    };
 
     let output_path = if let Some(path) = matches.value_of("output") {
         let path = PathBuf::from(path);
@@ -154,26 +687,131 @@ fn main() {
     exist_or_exit(&path1, "first");
     exist_or_exit(&path2, "second");
 
-    let color = if output_path.is_none() {
+    if is_stdin(&path1) && is_stdin(&path2) {
+        eprintln!("error: only one of the two paths can be read from stdin");
+        process::exit(2);
+    }
+
+    let single_file = matches.value_of("single-file").map(PathBuf::from);
+    if single_file.is_some() && !(path1.is_dir() && path2.is_dir()) {
+        eprintln!("error: --single-file only applies to two directories");
+        process::exit(2);
+    }
+
+    let color = if output_path.is_none() && single_file.is_none() && git.is_none() {
         matches.is_present("color")
     } else {
         false
     };
-    let raw = matches.is_present("raw");
+    let compact = matches.is_present("compact");
+    let raw = compact || matches.is_present("raw");
     let only_keys = matches.is_present("keys");
+    let side_by_side = matches.is_present("side-by-side").then(|| {
+        if let Some(width) = matches.value_of("width") {
+            width.parse().unwrap_or_else(|_| {
+                eprintln!("error: the width {width} is not a number of columns");
+                process::exit(2);
+            })
+        } else {
+            Term::stdout()
+                .size_checked()
+                .map_or(DEFAULT_WIDTH, |(_, columns)| usize::from(columns))
+        }
+    });
+    let threshold = matches.value_of("threshold").map(|threshold| {
+        threshold
+            .parse()
+            .ok()
+            .filter(|threshold| (0. ..=100.).contains(threshold))
+            .unwrap_or_else(|| {
+                eprintln!("error: the threshold {threshold} is not a percentage");
+                process::exit(2);
+            })
+    });
+    let globs = |name| {
+        matches
+            .values_of(name)
+            .map(|values| values.map(Glob::new).collect())
+            .unwrap_or_default()
+    };
 
     let cfg = Config {
         raw,
+        compact,
         only_keys,
         color,
+        side_by_side,
+        report_identical_files: matches.is_present("report-identical-files"),
+        threshold,
+        stat: matches.is_present("stat"),
+        include: globs("include"),
+        exclude: globs("exclude"),
+        ignore_keys: matches
+            .values_of("ignore-key")
+            .map(|values| values.map(String::from).collect())
+            .unwrap_or_default(),
+        only_keys_matching: globs("only-key"),
     };
 
-    if path1.is_dir() && path2.is_dir() {
-        explore(&path1, &path2, output_path.as_ref(), &cfg);
+    let result = if path1.is_dir() && path2.is_dir() {
+        let progress: Progress<'_> = if matches.is_present("progress") {
+            &report_progress
+        } else {
+            &|_, _, _| {}
+        };
+        explore(
+            &path1,
+            &path2,
+            output_path.as_ref(),
+            single_file.as_deref(),
+            &cfg,
+            progress,
+        )
     } else if (path1.is_dir() && !path2.is_dir()) || (!path1.is_dir() && path2.is_dir()) {
-        eprintln!("Both paths should be a directory or a file",);
-        process::exit(1);
+        if is_stdin(&path1) || is_stdin(&path2) {
+            eprintln!("error: stdin cannot be compared with a directory");
+        } else {
+            eprintln!("error: both paths should be a directory or a file");
+        }
+        process::exit(2);
     } else {
-        act_on_file(&path1, &path2, output_path.as_ref(), &cfg).unwrap();
+        let mut stdout = String::new();
+        act_on_file(
+            &path1,
+            &path2,
+            output_path.as_ref(),
+            &cfg,
+            &mut stdout,
+            None,
+        )
+        .and_then(|outcome| {
+            // The raw diff of identical files is `null`, so that the
+            // output is always JSON, even without differences.
+            if outcome == Outcome::Identical
+                && cfg.raw
+                && !cfg.stat
+                && output_path.is_none()
+                && stdout.is_empty()
+            {
+                stdout.push_str("null\n");
+            }
+            let differences_found = outcome == Outcome::Different;
+            if let (true, Some(git)) = (differences_found, &git) {
+                let path = git[0].display();
+                stdout.insert_str(0, &format!("diff --git a/{path} b/{path}\n"));
+            }
+            write_stdout(&stdout).map(|()| differences_found)
+        })
+    };
+
+    match result {
+        Ok(false) => {}
+        // git stops at the first diff driver exiting with an error.
+        Ok(true) if git.is_some() => {}
+        Ok(true) => process::exit(1),
+        Err(err) => {
+            eprintln!("error: {err}");
+            process::exit(2);
+        }
     }
 }
@@ -0,0 +1,102 @@
+use std::fmt::Write as _;
+
+use json_structural_diff::{DiffLine, DiffOp, Style, Theme};
+
+/// The width of the gutter between the two columns, marker included.
+const GUTTER_WIDTH: usize = 3;
+
+/// A row of the side-by-side view, made of a line of the old JSON file on
+/// the left and a line of the new one on the right.
+struct Row<'a> {
+    left: Option<&'a DiffLine>,
+    right: Option<&'a DiffLine>,
+    marker: char,
+}
+
+impl<'a> Row<'a> {
+    fn new(left: Option<&'a DiffLine>, right: Option<&'a DiffLine>) -> Self {
+        let marker = match (left, right) {
+            (Some(line), Some(_)) if line.op == DiffOp::Delete || line.op == DiffOp::Move => '|',
+            (Some(_), Some(_)) => ' ',
+            (Some(_), None) => '<',
+            _ => '>',
+        };
+        Self {
+            left,
+            right,
+            marker,
+        }
+    }
+}
+
+/// Pairs the deleted lines with the added lines which follow them, as
+/// `diff -y` does, while the other lines appear in both columns.
+fn rows(lines: &[DiffLine]) -> Vec<Row<'_>> {
+    let mut rows = Vec::new();
+    let mut position = 0;
+    while position < lines.len() {
+        let run = |start: usize, op: DiffOp| {
+            lines[start..]
+                .iter()
+                .take_while(|line| line.op == op)
+                .count()
+        };
+        let deleted = run(position, DiffOp::Delete);
+        let added = run(position + deleted, DiffOp::Add);
+        if deleted + added == 0 {
+            let line = &lines[position];
+            rows.push(Row::new(Some(line), Some(line)));
+            position += 1;
+            continue;
+        }
+        let (deleted, added) = lines[position..position + deleted + added].split_at(deleted);
+        for index in 0..deleted.len().max(added.len()) {
+            rows.push(Row::new(deleted.get(index), added.get(index)));
+        }
+        position += deleted.len() + added.len();
+    }
+    rows
+}
+
+/// Returns the indented text of a line, truncated or padded to `width`
+/// characters.
+fn cell(line: Option<&DiffLine>, width: usize) -> String {
+    let text = line.map_or_else(String::new, |line| {
+        format!("{:indent$}{}", "", line.text, indent = line.indent)
+    });
+    let text: String = text.chars().take(width).collect();
+    format!("{text:<width$}")
+}
+
+/// Returns the style of a line, or of the missing side of a row.
+pub fn style<'a>(line: Option<&DiffLine>, theme: &'a Theme) -> &'a Style {
+    match line.map(|line| line.op) {
+        Some(DiffOp::Add) => &theme.added,
+        Some(DiffOp::Delete) => &theme.deleted,
+        Some(DiffOp::Change) => &theme.changed,
+        Some(DiffOp::Context | DiffOp::Move) | None => &theme.context,
+    }
+}
+
+/// Formats the lines of a JSON structural difference in two columns of
+/// `width` characters overall, the old JSON file on the left and the new
+/// one on the right.
+///
+/// The marker between the columns is `|` for a changed line, `<` for a
+/// line only present on the left and `>` for a line only present on the
+/// right.
+pub fn side_by_side(lines: &[DiffLine], width: usize, theme: &Theme) -> String {
+    let column_width = (width.saturating_sub(GUTTER_WIDTH) / 2).max(1);
+    let mut output = String::new();
+    for row in rows(lines) {
+        let line = format!(
+            "{} {} {}",
+            style(row.left, theme).apply_to(cell(row.left, column_width)),
+            row.marker,
+            style(row.right, theme).apply_to(cell(row.right, column_width).trim_end())
+        );
+        // Writing into a `String` never fails.
+        let _ = writeln!(output, "{}", line.trim_end());
+    }
+    output
+}
@@ -0,0 +1,862 @@
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+
+fn data_path(filename: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("..")
+        .join("data")
+        .join(filename)
+}
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!(
+        "json-structural-diff-cli-{name}-{}",
+        std::process::id()
+    ));
+    if dir.exists() {
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn run(args: &[&str], stdin: Option<&str>) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_json-structural-diff-cli"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    let mut child_stdin = child.stdin.take().unwrap();
+    if let Some(stdin) = stdin {
+        // The process may exit before reading its input.
+        let _ = child_stdin.write_all(stdin.as_bytes());
+    }
+    drop(child_stdin);
+
+    child.wait_with_output().unwrap()
+}
+
+#[test]
+fn test_stdin() {
+    let b_json = data_path("b.json");
+    let b_json = b_json.to_str().unwrap();
+
+    let output = run(
+        &["-", b_json],
+        Some(&std::fs::read_to_string(data_path("a.json")).unwrap()),
+    );
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        std::fs::read_to_string(data_path("result.jsdiff"))
+            .unwrap()
+            .replace("\r\n", "\n")
+    );
+
+    let output = run(&["-", b_json], Some(r#"{"foo": 42}"#));
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8(output.stdout)
+        .unwrap()
+        .contains("+  boz: ["));
+
+    let output = run(&["-", "-"], Some("{}"));
+    assert_eq!(output.status.code(), Some(2));
+
+    let data_dir = data_path("");
+    let output = run(&["-", data_dir.to_str().unwrap()], Some("{}"));
+    assert_eq!(output.status.code(), Some(2));
+}
+
+#[test]
+fn test_output_dir() {
+    let dir = temp_dir("output");
+    let a_json = data_path("a.json");
+    let b_json = data_path("b.json");
+    let (a_json, b_json) = (a_json.to_str().unwrap(), b_json.to_str().unwrap());
+
+    // The written differences end with a single newline.
+    let output = run(&["-o", dir.to_str().unwrap(), a_json, b_json], None);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        std::fs::read_to_string(dir.join("a.json")).unwrap(),
+        std::fs::read_to_string(data_path("result.jsdiff"))
+            .unwrap()
+            .replace("\r\n", "\n")
+    );
+
+    let output = run(&["-j", "-o", dir.to_str().unwrap(), a_json, b_json], None);
+    assert_eq!(output.status.code(), Some(1));
+    let written = std::fs::read_to_string(dir.join("a.json")).unwrap();
+    assert!(written.ends_with("}\n"));
+    assert!(!written.ends_with("\n\n"));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_exit_status() {
+    let a_json = data_path("a.json");
+    let a_json = a_json.to_str().unwrap();
+    let b_json = data_path("b.json");
+    let b_json = b_json.to_str().unwrap();
+
+    let output = run(&[a_json, a_json], None);
+    assert_eq!(output.status.code(), Some(0));
+    assert!(output.stdout.is_empty());
+
+    let output = run(&[a_json, b_json], None);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(!output.stdout.is_empty());
+
+    let output = run(&["-", b_json], Some("{ not json"));
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .starts_with("error: <stdin>: "));
+
+    let output = run(&[a_json, "missing.json"], None);
+    assert_eq!(output.status.code(), Some(2));
+}
+
+#[test]
+fn test_report_identical_files() {
+    let a_json = data_path("a.json");
+    let a_json = a_json.to_str().unwrap();
+    let b_json = data_path("b.json");
+    let b_json = b_json.to_str().unwrap();
+
+    let output = run(&["--report-identical-files", a_json, a_json], None);
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        format!("Files {a_json} and {a_json} are identical\n")
+    );
+
+    let output = run(&["-s", a_json, b_json], None);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(!String::from_utf8(output.stdout)
+        .unwrap()
+        .contains("are identical"));
+
+    // Files whose values differ are identical when comparing only keys.
+    let dir = temp_dir("report-identical");
+    let (first, second) = (dir.join("first.json"), dir.join("second.json"));
+    std::fs::write(&first, r#"{"foo": 1}"#).unwrap();
+    std::fs::write(&second, r#"{"foo": 2}"#).unwrap();
+    let (first, second) = (first.to_str().unwrap(), second.to_str().unwrap());
+    let output = run(&["-s", "-k", first, second], None);
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        format!("Files {first} and {second} are identical\n")
+    );
+    let output = run(&[first, second], None);
+    assert_eq!(output.status.code(), Some(1));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_threshold() {
+    let dir = temp_dir("threshold");
+    let (first, second) = (dir.join("first.json"), dir.join("second.json"));
+    let keys = |last: u32| {
+        let mut obj: serde_json::Map<_, _> =
+            (0..9).map(|key| (key.to_string(), 0.into())).collect();
+        obj.insert("9".to_owned(), last.into());
+        serde_json::Value::Object(obj).to_string()
+    };
+    std::fs::write(&first, keys(0)).unwrap();
+    std::fs::write(&second, keys(1)).unwrap();
+    let (first, second) = (first.to_str().unwrap(), second.to_str().unwrap());
+
+    // Nearly identical files are similar enough for a low threshold.
+    let output = run(&["--threshold", "50", "-s", first, second], None);
+    assert_eq!(output.status.code(), Some(0));
+    assert!(output.stdout.is_empty());
+
+    let output = run(&["--threshold", "99.9", first, second], None);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8(output.stdout)
+        .unwrap()
+        .contains("-  9: 0"));
+
+    for threshold in ["-1", "101", "high"] {
+        let output = run(&[&format!("--threshold={threshold}"), first, second], None);
+        assert_eq!(output.status.code(), Some(2));
+        assert_eq!(
+            String::from_utf8(output.stderr).unwrap(),
+            format!("error: the threshold {threshold} is not a percentage\n")
+        );
+    }
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_root_type_change() {
+    let dir = temp_dir("root-type-change");
+    let write = |name: &str, contents: &str| {
+        let path = dir.join(name);
+        std::fs::write(&path, contents).unwrap();
+        path.to_str().unwrap().to_owned()
+    };
+    let array = write("array.json", "[1, 2]");
+    let object = write("object.json", r#"{"a": 1}"#);
+    let scalar = write("scalar.json", "3");
+
+    let output = run(&[&array, &object], None);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        " (the whole document is replaced, its type changed from an array to an object)\n\
+         -[\n-  1\n-  2\n-]\n+{\n+  a: 1\n+}\n"
+    );
+
+    let output = run(&[&object, &scalar], None);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8(output.stdout).unwrap().starts_with(
+        " (the whole document is replaced, its type changed from an object to a number)\n"
+    ));
+
+    // The raw difference is left as is, and values of the same type are
+    // compared as usual.
+    let output = run(&["--raw-json", "--compact", &object, &scalar], None);
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "{\"__new\":3,\"__old\":{\"a\":1}}\n"
+    );
+    let other = write("other.json", "4");
+    let output = run(&[&scalar, &other], None);
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "-3\n+4\n");
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_stat() {
+    let a_json = data_path("a.json");
+    let a_json = a_json.to_str().unwrap();
+    let b_json = data_path("b.json");
+    let b_json = b_json.to_str().unwrap();
+
+    let output = run(&["--stat", a_json, b_json], None);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        " boz   | 2 items inserted, 2 items deleted\n \
+         fubar | 1 value changed\n \
+         1 value changed, 2 items inserted, 2 items deleted\n"
+    );
+
+    let output = run(&["--stat", a_json, a_json], None);
+    assert_eq!(output.status.code(), Some(0));
+    assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn test_compact() {
+    let a_json = data_path("a.json");
+    let a_json = a_json.to_str().unwrap();
+    let b_json = data_path("b.json");
+    let b_json = b_json.to_str().unwrap();
+
+    let output = run(&["--compact", "--raw-json", a_json, b_json], None);
+    assert_eq!(output.status.code(), Some(1));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(stdout.lines().count(), 1);
+    assert!(stdout.ends_with('\n'));
+
+    let pretty = String::from_utf8(run(&["--raw-json", a_json, b_json], None).stdout).unwrap();
+    assert!(pretty.lines().count() > 1);
+    assert_eq!(
+        serde_json::from_str::<serde_json::Value>(&stdout).unwrap(),
+        serde_json::from_str::<serde_json::Value>(&pretty).unwrap()
+    );
+}
+
+#[test]
+fn test_raw_identical_files() {
+    let a_json = data_path("a.json");
+    let a_json = a_json.to_str().unwrap();
+
+    // Identical files are the raw diff `null`, instead of no output.
+    for args in [&["--raw-json"][..], &["--compact"], &["-j", "-k"]] {
+        let output = run(&[args, &[a_json, a_json]].concat(), None);
+        assert_eq!(output.status.code(), Some(0));
+        assert_eq!(String::from_utf8(output.stdout).unwrap(), "null\n");
+    }
+    // Without the raw encoding, there is still no output.
+    let output = run(&[a_json, a_json], None);
+    assert_eq!(output.status.code(), Some(0));
+    assert!(output.stdout.is_empty());
+
+    // The identical files are reported on stderr, so that stdout is JSON.
+    let output = run(&["-j", "--report-identical-files", a_json, a_json], None);
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "null\n");
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        format!("Files {a_json} and {a_json} are identical\n")
+    );
+
+    // Files similar enough are not identical, so there is no output.
+    let b_json = data_path("b.json");
+    let b_json = b_json.to_str().unwrap();
+    let output = run(&["-j", "--threshold", "0", a_json, b_json], None);
+    assert_eq!(output.status.code(), Some(0));
+    assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn test_color_flags() {
+    let a_json = data_path("a.json");
+    let a_json = a_json.to_str().unwrap();
+    let b_json = data_path("b.json");
+    let b_json = b_json.to_str().unwrap();
+    let expected = std::fs::read_to_string(data_path("result.jsdiff"))
+        .unwrap()
+        .replace("\r\n", "\n");
+
+    for args in [
+        &["--no-color"][..],
+        &["--color", "--no-color"],
+        &["-c", "--no-color"],
+    ] {
+        let output = run(&[args, &[a_json, b_json]].concat(), None);
+        assert_eq!(output.status.code(), Some(1));
+        assert_eq!(String::from_utf8(output.stdout).unwrap(), expected);
+    }
+
+    let output = run(&["--no-color", "--color", a_json, b_json], None);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(output.stderr.is_empty());
+}
+
+#[test]
+fn test_errors() {
+    let dir = temp_dir("errors");
+    let malformed = dir.join("malformed.json");
+    std::fs::write(&malformed, r#"{"foo": "#).unwrap();
+    let a_json = data_path("a.json");
+
+    let output = run(
+        &[malformed.to_str().unwrap(), a_json.to_str().unwrap()],
+        None,
+    );
+    assert_eq!(output.status.code(), Some(2));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.starts_with(&format!("error: {}: ", malformed.display())));
+    assert!(!stderr.contains("panicked"));
+
+    let output = run(&[a_json.to_str().unwrap(), "missing.json"], None);
+    assert_eq!(output.status.code(), Some(2));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.starts_with("error: missing.json: "));
+
+    // Files without an extension are skipped when exploring directories.
+    let dir1 = dir.join("first");
+    let dir2 = dir.join("second");
+    std::fs::create_dir_all(&dir1).unwrap();
+    std::fs::create_dir_all(&dir2).unwrap();
+    std::fs::write(dir1.join("README"), "foo").unwrap();
+    std::fs::write(dir2.join("README"), "bar").unwrap();
+    let output = run(&[dir1.to_str().unwrap(), dir2.to_str().unwrap()], None);
+    assert_eq!(output.status.code(), Some(0));
+    assert!(!String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("panicked"));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_include_exclude() {
+    let dir = temp_dir("include-exclude");
+    for (side, value) in [("first", 1), ("second", 2)] {
+        let root = dir.join(side);
+        std::fs::create_dir_all(root.join("fixtures")).unwrap();
+        std::fs::write(root.join("map.geojson"), format!(r#"{{"map": {value}}}"#)).unwrap();
+        std::fs::write(root.join("doc.json"), format!(r#"{{"doc": {value}}}"#)).unwrap();
+        std::fs::write(
+            root.join("fixtures").join("fixture.json"),
+            format!(r#"{{"fixture": {value}}}"#),
+        )
+        .unwrap();
+    }
+    let first = dir.join("first");
+    let second = dir.join("second");
+    let (first, second) = (first.to_str().unwrap(), second.to_str().unwrap());
+
+    let output = run(&[first, second], None);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("doc: 1"));
+    assert!(stdout.contains("fixture: 1"));
+    assert!(!stdout.contains("map: 1"));
+
+    let output = run(
+        &[
+            "--include",
+            "*.geojson",
+            "--include",
+            "*.json",
+            "--exclude",
+            "fixtures",
+            first,
+            second,
+        ],
+        None,
+    );
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("doc: 1"));
+    assert!(stdout.contains("map: 1"));
+    assert!(!stdout.contains("fixture: 1"));
+
+    let output = run(&["--include", "*.geojson", first, second], None);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(!stdout.contains("doc: 1"));
+    assert!(stdout.contains("map: 1"));
+    assert!(!stdout.contains("fixture: 1"));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_explore_pairs_by_path() {
+    let dir = temp_dir("explore");
+    let first = dir.join("a");
+    let second = dir.join("b");
+    std::fs::create_dir_all(first.join("nested")).unwrap();
+    std::fs::create_dir_all(second.join("nested")).unwrap();
+    for root in [&first, &second] {
+        std::fs::write(root.join("b.json"), r#"{"b": 1}"#).unwrap();
+        std::fs::write(root.join("nested").join("c.json"), r#"{"c": 1}"#).unwrap();
+    }
+    std::fs::write(first.join("a.json"), r#"{"a": 1}"#).unwrap();
+    std::fs::write(second.join("d.json"), r#"{"d": 1}"#).unwrap();
+
+    let output = run(&[first.to_str().unwrap(), second.to_str().unwrap()], None);
+    assert_eq!(output.status.code(), Some(1));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let mut lines: Vec<&str> = stdout.lines().collect();
+    lines.sort_unstable();
+    assert_eq!(
+        lines,
+        [
+            format!("Only in {}: a.json", first.display()),
+            format!("Only in {}: d.json", second.display()),
+        ]
+    );
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_explore_is_deterministic() {
+    let dir = temp_dir("deterministic");
+    let first = dir.join("a");
+    let second = dir.join("b");
+    for root in [&first, &second] {
+        std::fs::create_dir_all(root.join("nested")).unwrap();
+    }
+    for index in 0..16 {
+        std::fs::write(first.join(format!("{index:02}.json")), r#"{"a": 1}"#).unwrap();
+        std::fs::write(
+            second.join(format!("{index:02}.json")),
+            format!(r#"{{"a": {index}}}"#),
+        )
+        .unwrap();
+    }
+    std::fs::write(first.join("nested").join("c.json"), r#"{"c": 1}"#).unwrap();
+    let (first, second) = (first.to_str().unwrap(), second.to_str().unwrap());
+
+    let output = run(&[first, second], None);
+    assert_eq!(output.status.code(), Some(1));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(run(&[first, second], None).stdout, stdout.as_bytes());
+
+    // The outputs follow the order of the paths.
+    let nested = Path::new("nested").join("c.json");
+    let expected: String = (0..16)
+        .filter(|&index| index != 1)
+        .map(|index| format!(" {{\n-  a: 1\n+  a: {index}\n }}\n"))
+        .chain([format!("Only in {first}: {}\n", nested.display())])
+        .collect();
+    assert_eq!(stdout, expected);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_single_file() {
+    let dir = temp_dir("single-file");
+    let first = dir.join("a");
+    let second = dir.join("b");
+    for root in [&first, &second] {
+        std::fs::create_dir_all(root.join("nested")).unwrap();
+        std::fs::write(root.join("same.json"), r#"{"s": 1}"#).unwrap();
+    }
+    std::fs::write(first.join("x.json"), r#"{"x": 1}"#).unwrap();
+    std::fs::write(second.join("x.json"), r#"{"x": 2}"#).unwrap();
+    std::fs::write(first.join("nested").join("y.json"), "[1]").unwrap();
+    std::fs::write(second.join("nested").join("y.json"), "[1, 2]").unwrap();
+    std::fs::write(first.join("z.json"), "{}").unwrap();
+    let single_file = dir.join("diff.json");
+    let (first, second, single) = (
+        first.to_str().unwrap(),
+        second.to_str().unwrap(),
+        single_file.to_str().unwrap(),
+    );
+
+    // The files only in one directory are still reported on stdout.
+    let output = run(&["-j", "--single-file", single, first, second], None);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        format!("Only in {first}: z.json\n")
+    );
+    let combined: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&single_file).unwrap()).unwrap();
+    let nested = Path::new("nested").join("y.json");
+    let mut expected = serde_json::Map::new();
+    expected.insert(
+        nested.display().to_string(),
+        serde_json::json!([[" ", 1], ["+", 2]]),
+    );
+    expected.insert(
+        "x.json".to_owned(),
+        serde_json::json!({"x": {"__old": 1, "__new": 2}}),
+    );
+    assert_eq!(combined, serde_json::Value::Object(expected));
+
+    // Without the raw encoding, each difference follows its path.
+    let output = run(&["--single-file", single, first, second], None);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        std::fs::read_to_string(&single_file).unwrap(),
+        format!(
+            "File {}:\n [\n   1\n+  2\n ]\nFile x.json:\n {{\n-  x: 1\n+  x: 2\n }}\n",
+            nested.display()
+        )
+    );
+
+    let output = run(&["--single-file", single, "-", first], Some("{}"));
+    assert_eq!(output.status.code(), Some(2));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_ignore_keys() {
+    let dir = temp_dir("ignore-keys");
+    let first = dir.join("first.json");
+    let second = dir.join("second.json");
+    std::fs::write(
+        &first,
+        r#"{"id": 1, "timestamp": "2024-01-01", "meta": {"timestamp": 1, "a": 1}, "b": 1}"#,
+    )
+    .unwrap();
+    std::fs::write(
+        &second,
+        r#"{"id": 2, "timestamp": "2024-06-01", "meta": {"timestamp": 2, "a": 1}, "c": 1}"#,
+    )
+    .unwrap();
+    let (first, second) = (first.to_str().unwrap(), second.to_str().unwrap());
+
+    // The key is ignored at any depth.
+    let output = run(
+        &[
+            "-j",
+            "--compact",
+            "--ignore-key",
+            "timestamp",
+            first,
+            second,
+        ],
+        None,
+    );
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "{\"b__deleted\":1,\"c__added\":1,\"id\":{\"__new\":2,\"__old\":1}}\n"
+    );
+
+    // The JSON Pointers only ignore the value at their path.
+    let output = run(
+        &[
+            "-j",
+            "--compact",
+            "--ignore-key",
+            "/timestamp",
+            "--ignore-key",
+            "id",
+            first,
+            second,
+        ],
+        None,
+    );
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "{\"b__deleted\":1,\"c__added\":1,\"meta\":{\"timestamp\":{\"__new\":2,\"__old\":1}}}\n"
+    );
+
+    // Only the matching top-level keys are compared, and keys-only ignores
+    // the values.
+    let output = run(
+        &[
+            "-j",
+            "--compact",
+            "--only-key",
+            "?",
+            "--keys-only",
+            first,
+            second,
+        ],
+        None,
+    );
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "{\"b__deleted\":1,\"c__added\":1}\n"
+    );
+    let output = run(
+        &[
+            "--only-key",
+            "meta",
+            "--ignore-key",
+            "timestamp",
+            first,
+            second,
+        ],
+        None,
+    );
+    assert_eq!(output.status.code(), Some(0));
+    assert!(output.stdout.is_empty());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_git() {
+    let dir = temp_dir("git");
+    let old = dir.join("old_a.json");
+    let new = dir.join("new_a.json");
+    std::fs::write(&old, r#"{"foo": 1, "bar": true}"#).unwrap();
+    std::fs::write(&new, r#"{"foo": 2, "bar": true}"#).unwrap();
+    let (old, new) = (old.to_str().unwrap(), new.to_str().unwrap());
+    let hex = "0123456789abcdef0123456789abcdef01234567";
+
+    // The differences are reported without colors, and git expects 0.
+    let output = run(
+        &[
+            "--git",
+            "data/a.json",
+            old,
+            hex,
+            "100644",
+            new,
+            hex,
+            "100644",
+        ],
+        None,
+    );
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "diff --git a/data/a.json b/data/a.json\n {\n-  foo: 1\n+  foo: 2\n }\n"
+    );
+
+    let output = run(
+        &[
+            "--git",
+            "data/a.json",
+            old,
+            hex,
+            "100644",
+            old,
+            hex,
+            "100755",
+        ],
+        None,
+    );
+    assert_eq!(output.status.code(), Some(0));
+    assert!(output.stdout.is_empty());
+
+    // An added file is compared with `null`.
+    #[cfg(unix)]
+    {
+        let output = run(
+            &[
+                "--git",
+                "data/a.json",
+                "/dev/null",
+                ".",
+                ".",
+                new,
+                hex,
+                "100644",
+            ],
+            None,
+        );
+        assert_eq!(output.status.code(), Some(0));
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        assert!(stdout.starts_with("diff --git a/data/a.json b/data/a.json\n"));
+        assert!(stdout.ends_with("-null\n+{\n+  bar: true\n+  foo: 2\n+}\n"));
+    }
+
+    let output = run(&["--git", "data/a.json", old, hex, "100644"], None);
+    assert_eq!(output.status.code(), Some(1));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_progress() {
+    let dir = temp_dir("progress");
+    let first = dir.join("a");
+    let second = dir.join("b");
+    std::fs::create_dir_all(first.join("nested")).unwrap();
+    std::fs::create_dir_all(&second).unwrap();
+    for root in [&first, &second] {
+        std::fs::write(root.join("a.json"), r#"{"a": 1}"#).unwrap();
+        std::fs::write(root.join("b.json"), r#"{"b": 1}"#).unwrap();
+    }
+    std::fs::write(first.join("nested").join("c.json"), r#"{"c": 1}"#).unwrap();
+    let (first, second) = (first.to_str().unwrap(), second.to_str().unwrap());
+
+    let output = run(&["--progress", first, second], None);
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    let mut counts: Vec<&str> = stderr
+        .lines()
+        .map(|line| line.split_once(' ').unwrap().0)
+        .collect();
+    counts.sort_unstable();
+    assert_eq!(counts, ["[1/3]", "[2/3]", "[3/3]"]);
+    let mut paths: Vec<&str> = stderr
+        .lines()
+        .map(|line| line.split_once(' ').unwrap().1)
+        .collect();
+    paths.sort_unstable();
+    let nested = Path::new("nested").join("c.json");
+    assert_eq!(paths, ["a.json", "b.json", nested.to_str().unwrap()]);
+
+    let output = run(&[first, second], None);
+    assert!(output.stderr.is_empty());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_side_by_side() {
+    let dir = temp_dir("side-by-side");
+    let old_json = dir.join("old.json");
+    let new_json = dir.join("new.json");
+    std::fs::write(&old_json, r#"{"name": "foo", "size": 1, "tags": ["a"]}"#).unwrap();
+    std::fs::write(&new_json, r#"{"name": "bar", "size": 1, "color": "red"}"#).unwrap();
+    let (old_json, new_json) = (old_json.to_str().unwrap(), new_json.to_str().unwrap());
+
+    let output = run(
+        &["--side-by-side", "--width", "43", old_json, new_json],
+        None,
+    );
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        concat!(
+            "{                      {\n",
+            "                     >   color: \"red\"\n",
+            "  name: \"foo\"        |   name: \"bar\"\n",
+            "  tags: [            <\n",
+            "    \"a\"              <\n",
+            "  ]                  <\n",
+            "}                      }\n",
+        )
+    );
+
+    // Long lines are truncated to the width of their column.
+    let output = run(&["-y", "--width", "23", old_json, new_json], None);
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap().lines().nth(2),
+        Some("  name: \"f |   name: \"b")
+    );
+
+    let output = run(&["-y", "--width", "wide", old_json, new_json], None);
+    assert_eq!(output.status.code(), Some(2));
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_yaml() {
+    let dir = temp_dir("yaml");
+    let old_yaml = dir.join("old.yaml");
+    let new_yaml = dir.join("new.yml");
+    let new_json = dir.join("new.json");
+    let keys_yaml = dir.join("keys.yaml");
+    std::fs::write(&old_yaml, "name: foo\nports:\n  - 80\n  - 443\n").unwrap();
+    std::fs::write(&new_yaml, "name: bar\nports: [80, 443]\n").unwrap();
+    std::fs::write(&new_json, r#"{"name": "foo", "ports": [80, 443]}"#).unwrap();
+    std::fs::write(&keys_yaml, "1: one\n").unwrap();
+    let paths = [&old_yaml, &new_yaml, &new_json, &keys_yaml].map(|path| path.to_str().unwrap());
+    let [old_yaml, new_yaml, new_json, keys_yaml] = paths;
+
+    let output = run(&["--raw-json", old_yaml, new_yaml], None);
+    if cfg!(feature = "yaml") {
+        assert_eq!(output.status.code(), Some(1));
+        assert_eq!(
+            serde_json::from_slice::<serde_json::Value>(&output.stdout).unwrap(),
+            serde_json::json!({"name": {"__old": "foo", "__new": "bar"}})
+        );
+
+        // A YAML file is compared with a JSON file by value.
+        let output = run(&[old_yaml, new_json], None);
+        assert_eq!(output.status.code(), Some(0));
+
+        let output = run(&[keys_yaml, new_json], None);
+        assert_eq!(output.status.code(), Some(2));
+        assert!(String::from_utf8(output.stderr)
+            .unwrap()
+            .contains("the YAML key 1 is not a string"));
+    } else {
+        assert_eq!(output.status.code(), Some(2));
+        assert!(String::from_utf8(output.stderr)
+            .unwrap()
+            .contains("reading YAML files requires the `yaml` feature"));
+    }
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_toml() {
+    let dir = temp_dir("toml");
+    let old_toml = dir.join("old.toml");
+    let new_json = dir.join("new.json");
+    std::fs::write(
+        &old_toml,
+        "name = \"foo\"\ncreated = 2024-01-01\n\n[server]\nport = 80\n",
+    )
+    .unwrap();
+    std::fs::write(
+        &new_json,
+        r#"{"name": "foo", "created": "2024-01-01", "server": {"port": 8080}}"#,
+    )
+    .unwrap();
+    let (old_toml, new_json) = (old_toml.to_str().unwrap(), new_json.to_str().unwrap());
+
+    let output = run(&["--raw-json", old_toml, new_json], None);
+    if cfg!(feature = "toml") {
+        assert_eq!(output.status.code(), Some(1));
+        assert_eq!(
+            serde_json::from_slice::<serde_json::Value>(&output.stdout).unwrap(),
+            serde_json::json!({"server": {"port": {"__old": 80, "__new": 8080}}})
+        );
+    } else {
+        assert_eq!(output.status.code(), Some(2));
+    }
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,247 @@
+use alloc::collections::{BTreeMap, VecDeque};
+use alloc::format;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use serde_json::Value;
+
+use crate::diff::{JsonDiff, ScalarizedArrays};
+use crate::error::DiffError;
+use crate::options::DiffOptions;
+use crate::unordered::UnorderedPairs;
+
+/// An item of two arrays, matched or not with an item of the other array,
+/// see [`JsonDiff::match_arrays`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum ArrayMatch {
+    /// An item of the first array matched with an item of the second one.
+    Matched {
+        /// Its index in the first array.
+        from_index: usize,
+        /// The index of its match in the second array.
+        to_index: usize,
+        /// The JSON structural difference of the two items, or `None` if
+        /// they are equal.
+        change: Option<Value>,
+    },
+    /// An item only present in the first array.
+    Deleted {
+        /// Its index in the first array.
+        index: usize,
+        /// The deleted item.
+        value: Value,
+    },
+    /// An item only present in the second array.
+    Added {
+        /// Its index in the second array.
+        index: usize,
+        /// The added item.
+        value: Value,
+    },
+}
+
+impl JsonDiff {
+    /// Matches the items of two arrays as their difference does, and
+    /// returns every item of the first array, matched with an item of the
+    /// second one or deleted, then the added items of the second array.
+    ///
+    /// Unlike the difference, the matched items keep their indices even if
+    /// their order has changed, e.g. an object edited and moved from the
+    /// index `2` to the index `0` is matched with `from_index: 2` and
+    /// `to_index: 0`. The equal scalars are matched in order.
+    ///
+    /// Returns `None` if the JSON files are not both arrays.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DiffError::TooDeep`] if a JSON file is nested deeper than
+    /// [`DiffOptions::max_nesting`].
+    pub fn match_arrays(
+        json1: &Value,
+        json2: &Value,
+        options: &DiffOptions,
+    ) -> Result<Option<Vec<ArrayMatch>>, DiffError> {
+        options.check_nesting(json1)?;
+        options.check_nesting(json2)?;
+        let (json1, json2) = (options.normalize(json1), options.normalize(json2));
+        let (Value::Array(array1), Value::Array(array2)) = (&*json1, &*json2) else {
+            return Ok(None);
+        };
+
+        let pairs = Self::array_pairs(array1, array2, options, "")?;
+
+        let mut matched1 = vec![None; array1.len()];
+        let mut matched2 = vec![false; array2.len()];
+        for (index1, index2) in pairs {
+            matched1[index1] = Some(index2);
+            matched2[index2] = true;
+        }
+
+        let mut result = Vec::with_capacity(array1.len() + array2.len());
+        for (index1, (item1, matched)) in array1.iter().zip(matched1).enumerate() {
+            result.push(if let Some(index2) = matched {
+                let json_diff =
+                    Self::diff_with_score(item1, &array2[index2], options, &format!("/{index1}"))?;
+                ArrayMatch::Matched {
+                    from_index: index1,
+                    to_index: index2,
+                    change: json_diff.diff,
+                }
+            } else {
+                ArrayMatch::Deleted {
+                    index: index1,
+                    value: item1.clone(),
+                }
+            });
+        }
+        result.extend(
+            array2
+                .iter()
+                .enumerate()
+                .filter(|(index2, _)| !matched2[*index2])
+                .map(|(index2, item2)| ArrayMatch::Added {
+                    index: index2,
+                    value: item2.clone(),
+                }),
+        );
+        Ok(Some(result))
+    }
+
+    /// Pairs the indices of the items of two arrays matched by their
+    /// difference, in any order.
+    pub(crate) fn array_pairs(
+        array1: &[Value],
+        array2: &[Value],
+        options: &DiffOptions,
+        path: &str,
+    ) -> Result<Vec<(usize, usize)>, DiffError> {
+        Ok(if options.is_beyond_fuzzy_array_len(array1, array2) {
+            (0..array1.len().min(array2.len()))
+                .map(|index| (index, index))
+                .collect()
+        } else if options.is_unordered(path) {
+            let UnorderedPairs { matches, .. } =
+                Self::pair_unordered(array1, array2, options, path)?;
+            matches
+                .into_iter()
+                .enumerate()
+                .filter_map(|(index1, found)| Some((index1, found?.0)))
+                .collect()
+        } else {
+            Self::scalarized_pairs(&Self::scalarize_arrays(array1, array2, options, path)?)
+        })
+    }
+
+    /// Pairs the indices of the items sharing a key, the items sharing the
+    /// same scalar key being paired in order.
+    fn scalarized_pairs(scalarized: &ScalarizedArrays<'_>) -> Vec<(usize, usize)> {
+        let mut indices1: BTreeMap<&str, VecDeque<usize>> = BTreeMap::new();
+        for (index1, key) in scalarized.seq1.iter().enumerate() {
+            indices1.entry(key).or_default().push_back(index1);
+        }
+        scalarized
+            .seq2
+            .iter()
+            .enumerate()
+            .filter_map(|(index2, key)| {
+                let index1 = indices1.get_mut(key.as_str())?.pop_front()?;
+                Some((index1, index2))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use alloc::vec;
+
+    use super::ArrayMatch;
+    use crate::diff::JsonDiff;
+    use crate::options::DiffOptions;
+
+    #[test]
+    fn test_match_arrays() {
+        let json1 = json!([
+            {"id": 1, "name": "foo", "size": 1},
+            "bar",
+            {"id": 2, "name": "baz", "size": 2},
+            "bar",
+        ]);
+        let json2 = json!([
+            {"id": 2, "name": "baz", "size": 3},
+            "bar",
+            {"id": 1, "name": "foo", "size": 1},
+            "qux",
+        ]);
+
+        let matches = JsonDiff::match_arrays(&json1, &json2, &DiffOptions::default())
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            matches,
+            [
+                ArrayMatch::Matched {
+                    from_index: 0,
+                    to_index: 2,
+                    change: None,
+                },
+                ArrayMatch::Matched {
+                    from_index: 1,
+                    to_index: 1,
+                    change: None,
+                },
+                ArrayMatch::Matched {
+                    from_index: 2,
+                    to_index: 0,
+                    change: Some(json!({"size": {"__old": 2, "__new": 3}})),
+                },
+                ArrayMatch::Deleted {
+                    index: 3,
+                    value: json!("bar"),
+                },
+                ArrayMatch::Added {
+                    index: 3,
+                    value: json!("qux"),
+                },
+            ]
+        );
+
+        // The pairs of unordered arrays are the same.
+        let options = DiffOptions {
+            array_unordered: true,
+            ..DiffOptions::default()
+        };
+        assert_eq!(
+            JsonDiff::match_arrays(&json1, &json2, &options)
+                .unwrap()
+                .unwrap(),
+            matches
+        );
+
+        // Long arrays are matched by index.
+        let options = DiffOptions {
+            max_fuzzy_array_len: Some(0),
+            ..DiffOptions::default()
+        };
+        assert_eq!(
+            JsonDiff::match_arrays(&json!([1, 2]), &json!([2]), &options).unwrap(),
+            Some(vec![
+                ArrayMatch::Matched {
+                    from_index: 0,
+                    to_index: 0,
+                    change: Some(json!({"__old": 1, "__new": 2})),
+                },
+                ArrayMatch::Deleted {
+                    index: 1,
+                    value: json!(2),
+                },
+            ])
+        );
+
+        assert_eq!(
+            JsonDiff::match_arrays(&json!({}), &json!([]), &DiffOptions::default()).unwrap(),
+            None
+        );
+    }
+}
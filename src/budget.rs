@@ -0,0 +1,110 @@
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use serde_json::Value;
+
+use crate::filter::DiffFilter;
+use crate::flatten::ChangeKind;
+use crate::markers::DiffMarkers;
+
+/// The number and the kinds of changes which can still be reported while
+/// finding a JSON structural difference, shared by the whole recursion.
+pub(crate) struct ChangeBudget {
+    max_changes: Option<usize>,
+    filter: DiffFilter,
+    changes: AtomicUsize,
+    truncated: AtomicBool,
+    /// Whether the values are compared beyond [`DiffOptions::max_depth`],
+    /// to score the values reported as a whole.
+    ///
+    /// [`DiffOptions::max_depth`]: crate::DiffOptions::max_depth
+    unbounded: bool,
+}
+
+impl ChangeBudget {
+    pub(crate) fn new(max_changes: Option<usize>, filter: DiffFilter) -> Self {
+        Self {
+            max_changes,
+            filter,
+            changes: AtomicUsize::new(0),
+            truncated: AtomicBool::new(false),
+            unbounded: false,
+        }
+    }
+
+    /// A budget which is never exhausted, for the differences only computed
+    /// to score a match.
+    pub(crate) fn unlimited() -> Self {
+        Self::new(None, DiffFilter::All)
+    }
+
+    /// An unlimited budget, for the differences only computed to score the
+    /// values beyond [`DiffOptions::max_depth`], which are compared at any
+    /// depth.
+    ///
+    /// [`DiffOptions::max_depth`]: crate::DiffOptions::max_depth
+    pub(crate) fn unbounded() -> Self {
+        Self {
+            unbounded: true,
+            ..Self::unlimited()
+        }
+    }
+
+    /// Returns `true` if the values are compared at any depth.
+    pub(crate) fn is_unbounded(&self) -> bool {
+        self.unbounded
+    }
+
+    /// Returns `true` if the changes of the given kind are reported.
+    pub(crate) fn keeps(&self, kind: ChangeKind) -> bool {
+        self.filter.keeps(kind)
+    }
+
+    /// Returns the changes of `diff` which are reported, for the differences
+    /// found before being reported, or `None` if no change is left.
+    pub(crate) fn filter(&self, diff: Value, markers: &DiffMarkers) -> Option<Value> {
+        self.filter.filter(diff, markers)
+    }
+
+    /// Spends one change of the given kind, returning `false` if it is left
+    /// out by the filter, in which case `filtered` is set, or if it exceeds
+    /// the budget.
+    pub(crate) fn spend_on(&self, kind: ChangeKind, filtered: &mut bool) -> bool {
+        if self.keeps(kind) {
+            self.spend(1)
+        } else {
+            *filtered = true;
+            false
+        }
+    }
+
+    /// Spends `count` changes, returning `false` if they exceed the budget,
+    /// in which case the difference is truncated.
+    pub(crate) fn spend(&self, count: usize) -> bool {
+        let Some(max_changes) = self.max_changes else {
+            return true;
+        };
+        let spent = self.changes.fetch_add(count, Ordering::Relaxed);
+        if spent.saturating_add(count) <= max_changes {
+            true
+        } else {
+            self.changes.fetch_sub(count, Ordering::Relaxed);
+            self.truncated.store(true, Ordering::Relaxed);
+            false
+        }
+    }
+
+    /// Returns `true` if no change can be reported anymore.
+    pub(crate) fn is_exhausted(&self) -> bool {
+        self.max_changes
+            .is_some_and(|max_changes| self.changes.load(Ordering::Relaxed) >= max_changes)
+    }
+
+    /// Records that some changes have not been reported.
+    pub(crate) fn truncate(&self) {
+        self.truncated.store(true, Ordering::Relaxed);
+    }
+
+    pub(crate) fn is_truncated(&self) -> bool {
+        self.truncated.load(Ordering::Relaxed)
+    }
+}
@@ -1,69 +1,490 @@
-use regex::Regex;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+
 use serde_json::Value;
 
-fn subcolorize<F>(key: Option<&str>, diff: &Value, output: &mut F, color: &str, indent: &str)
-where
-    F: FnMut(&str, &str),
-{
-    let prefix = if let Some(key) = key {
-        format!("{key}: ")
-    } else {
-        String::new()
+use crate::markers::{DiffMarkers, MarkedKey};
+
+/// The kind of a line of a JSON structural difference.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DiffOp {
+    /// An added line, prefixed by `+`.
+    Add,
+    /// A deleted line, prefixed by `-`.
+    Delete,
+    /// An unchanged context line, prefixed by a space.
+    Context,
+    /// A line of an array item changed in place, or of a string changed
+    /// character by character, prefixed by a space.
+    Change,
+    /// An array item moved to another index, or a renamed key, prefixed by
+    /// `>`.
+    Move,
+}
+
+impl DiffOp {
+    /// Returns the character which prefixes the lines of this kind.
+    #[must_use]
+    pub fn symbol(self) -> char {
+        match self {
+            Self::Add => '+',
+            Self::Delete => '-',
+            Self::Context | Self::Change => ' ',
+            Self::Move => '>',
+        }
+    }
+}
+
+/// A line of a JSON structural difference.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DiffLine {
+    /// The kind of the line.
+    pub op: DiffOp,
+    /// The number of spaces which indent the text, or the number of tabs
+    /// with [`Indent::Tab`].
+    pub indent: usize,
+    /// The text of the line, without any indentation.
+    pub text: String,
+}
+
+impl DiffLine {
+    fn new(op: DiffOp, indent: usize, text: impl Into<String>) -> Self {
+        Self {
+            op,
+            indent,
+            text: text.into(),
+        }
+    }
+}
+
+impl fmt::Display for DiffLine {
+    /// Formats the line as the prefix of its kind followed by the
+    /// indented text.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}{:indent$}{}",
+            self.op.symbol(),
+            "",
+            self.text,
+            indent = self.indent
+        )
+    }
+}
+
+// Receives the lines of a formatted JSON structural difference, which are
+// only counted, without being formatted, beyond `ContextOptions::max_lines`.
+struct Lines<'a> {
+    output: &'a mut dyn FnMut(DiffLine),
+    remaining: Option<usize>,
+    skipped: usize,
+}
+
+impl<'a> Lines<'a> {
+    fn new(output: &'a mut dyn FnMut(DiffLine), options: &ContextOptions) -> Self {
+        Self {
+            output,
+            remaining: options.max_lines,
+            skipped: 0,
+        }
+    }
+
+    fn push<S: Into<String>>(&mut self, op: DiffOp, indent: usize, text: impl FnOnce() -> S) {
+        match &mut self.remaining {
+            Some(0) => self.skipped += 1,
+            Some(remaining) => {
+                *remaining -= 1;
+                (self.output)(DiffLine::new(op, indent, text()));
+            }
+            None => (self.output)(DiffLine::new(op, indent, text())),
+        }
+    }
+}
+
+// Formats a value of one of the JSON files, whose keys are not markers.
+fn colorize_value(
+    key: Option<&str>,
+    value: &Value,
+    output: &mut Lines<'_>,
+    op: DiffOp,
+    indent: usize,
+    options: &ContextOptions,
+) {
+    let prefix = || key.map_or_else(String::new, |key| format!("{key}: "));
+    let subindent = indent + options.indent.width();
+
+    match value {
+        Value::Object(obj) => {
+            output.push(op, indent, || prefix() + "{");
+            for (subkey, subvalue) in obj {
+                colorize_value(Some(subkey), subvalue, output, op, subindent, options);
+            }
+            output.push(op, indent, || "}");
+        }
+        Value::Array(array) => {
+            output.push(op, indent, || prefix() + "[");
+            for subvalue in array {
+                colorize_value(None, subvalue, output, op, subindent, options);
+            }
+            output.push(op, indent, || "]");
+        }
+        _ => output.push(op, indent, || prefix() + &options.format(value)),
+    }
+}
+
+/// The unit of indentation of the nested values of a formatted JSON
+/// structural difference.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Indent {
+    /// A number of spaces.
+    Spaces(usize),
+    /// A tab.
+    Tab,
+}
+
+impl Default for Indent {
+    /// Two spaces.
+    fn default() -> Self {
+        Self::Spaces(2)
+    }
+}
+
+impl Indent {
+    /// Returns the width of the unit, i.e. how much [`DiffLine::indent`]
+    /// grows for each nested value.
+    fn width(self) -> usize {
+        match self {
+            Self::Spaces(spaces) => spaces,
+            Self::Tab => 1,
+        }
+    }
+
+    // Formats a line as `DiffLine` does, with this unit of indentation.
+    fn format(self, line: &DiffLine) -> String {
+        match self {
+            Self::Spaces(_) => line.to_string(),
+            Self::Tab => format!(
+                "{}{}{}",
+                line.op.symbol(),
+                "\t".repeat(line.indent),
+                line.text
+            ),
+        }
+    }
+}
+
+// Formats the difference of two strings as a single string, where the
+// deleted characters are wrapped in `[-` and `-]` and the added ones in
+// `{+` and `+}`, as `wdiff` does.
+fn colorize_text(segments: &[Value]) -> String {
+    let mut text = String::from('"');
+    for segment in segments {
+        let (Some(op), Some(content)) = (
+            segment.get(0).and_then(Value::as_str),
+            segment.get(1).and_then(Value::as_str),
+        ) else {
+            continue;
+        };
+        // The characters are escaped as in a JSON string, without quotes.
+        let escaped = Value::from(content).to_string();
+        let escaped = &escaped[1..escaped.len() - 1];
+        let (start, end) = match op {
+            "-" => ("[-", "-]"),
+            "+" => ("{+", "+}"),
+            _ => ("", ""),
+        };
+        text.push_str(start);
+        text.push_str(escaped);
+        text.push_str(end);
+    }
+    text.push('"');
+    text
+}
+
+/// Options to customize how the unchanged items of the arrays of a JSON
+/// structural difference are shown, how its nested values are indented,
+/// and how its scalar values are formatted.
+#[derive(Clone, Debug, Default)]
+pub struct ContextOptions {
+    /// Number of unchanged items shown before and after each changed item
+    /// of an array.
+    ///
+    /// If `Some(context)`: the other unchanged items are collapsed, and each
+    /// run of them is shown as a single `...` line. A run between two
+    /// changes is then collapsed when it is longer than `2 * context`, while
+    /// a run at the start or the end of an array is collapsed when it is
+    /// longer than `context`.
+    /// If `None`: every unchanged item is shown.
+    pub context: Option<usize>,
+    /// The unit of indentation of the nested values.
+    ///
+    /// Defaults to two spaces.
+    pub indent: Indent,
+    /// Formats the scalar values, e.g. to truncate or redact the long
+    /// strings.
+    ///
+    /// If `None`: the scalar values are formatted as JSON.
+    pub format_leaf: Option<fn(&Value) -> String>,
+    /// Maximum number of lines formatted.
+    ///
+    /// If `Some(max_lines)`: the lines beyond are only counted, and replaced
+    /// by a single `... (M more lines)` line.
+    /// If `None`: every line is formatted.
+    pub max_lines: Option<usize>,
+    /// The markers of the changes of the JSON structural difference, i.e.
+    /// [`DiffOptions::markers`].
+    ///
+    /// Defaults to [`DiffMarkers::default`].
+    ///
+    /// [`DiffOptions::markers`]: crate::DiffOptions::markers
+    pub markers: DiffMarkers,
+    /// Introduce a root object or array replaced by a value of another
+    /// type, e.g. an array by an object, by a line describing it.
+    ///
+    /// Defaults to `false`.
+    pub describe_root_type: bool,
+}
+
+// The formatters are compared by their address.
+impl PartialEq for ContextOptions {
+    fn eq(&self, other: &Self) -> bool {
+        self.context == other.context
+            && self.indent == other.indent
+            && self.max_lines == other.max_lines
+            && self.markers == other.markers
+            && self.describe_root_type == other.describe_root_type
+            && match (self.format_leaf, other.format_leaf) {
+                (Some(format_leaf), Some(other)) => format_leaf as usize == other as usize,
+                (format_leaf, other) => format_leaf.is_none() && other.is_none(),
+            }
+    }
+}
+
+impl Eq for ContextOptions {}
+
+impl ContextOptions {
+    fn format(&self, value: &Value) -> String {
+        self.format_leaf
+            .map_or_else(|| value.to_string(), |format_leaf| format_leaf(value))
+    }
+}
+
+/// Options to customize how the lines of a formatted JSON structural
+/// difference are joined.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LineOptions {
+    /// Separator of the lines.
+    ///
+    /// Defaults to `"\n"`.
+    pub line_ending: &'static str,
+    /// Terminate the last line with [`LineOptions::line_ending`] too.
+    ///
+    /// Defaults to `true`.
+    pub trailing_newline: bool,
+}
+
+impl Default for LineOptions {
+    fn default() -> Self {
+        Self {
+            line_ending: "\n",
+            trailing_newline: true,
+        }
+    }
+}
+
+impl LineOptions {
+    pub(crate) fn join(&self, lines: &[String]) -> String {
+        let mut output = lines.join(self.line_ending);
+        if self.trailing_newline {
+            output.push_str(self.line_ending);
+        }
+        output
+    }
+}
+
+// Returns which entries of an array difference are shown, i.e. the changes
+// and the unchanged items close enough to a change.
+fn visible_entries(entries: &[Value], options: &ContextOptions) -> Vec<bool> {
+    let Some(context) = options.context else {
+        return vec![true; entries.len()];
     };
-    let subindent = &format!("{indent}  ");
+    let mut visible = vec![false; entries.len()];
+    let mut mark_close_to_change = |indices: &mut dyn Iterator<Item = usize>| {
+        let mut distance = None;
+        for index in indices {
+            match entries[index].get(0).and_then(Value::as_str) {
+                Some(" ") => distance = distance.map(|distance| distance + 1),
+                _ => distance = Some(0),
+            }
+            // The unchanged items without a value are always collapsed.
+            if entries[index].get(1).is_some() || distance == Some(0) {
+                visible[index] |= distance.is_some_and(|distance| distance <= context);
+            }
+        }
+    };
+    mark_close_to_change(&mut (0..entries.len()));
+    mark_close_to_change(&mut (0..entries.len()).rev());
+    visible
+}
+
+fn colorize_entries(
+    entries: &[Value],
+    output: &mut Lines<'_>,
+    indent: usize,
+    options: &ContextOptions,
+) {
+    let mut collapsed = false;
+    for (entry, visible) in entries.iter().zip(visible_entries(entries, options)) {
+        let Value::Array(subitem) = entry else {
+            continue;
+        };
+        if !visible {
+            if !collapsed {
+                output.push(DiffOp::Context, indent, || "...");
+            }
+            collapsed = true;
+            continue;
+        }
+        collapsed = false;
+
+        match (subitem.first().and_then(Value::as_str), &subitem[..]) {
+            (Some(" "), [_]) => output.push(DiffOp::Context, indent, || "..."),
+            (Some(">"), [_, from, to, ..]) => {
+                output.push(DiffOp::Move, indent, || format!("{from} -> {to}"));
+            }
+            (Some(" "), [_, subvalue, ..]) => {
+                colorize_value(None, subvalue, output, DiffOp::Context, indent, options);
+            }
+            (Some("~"), [_, subvalue, ..]) => {
+                subcolorize(None, subvalue, output, DiffOp::Change, indent, options);
+            }
+            (Some("-"), [_, subvalue, ..]) => {
+                colorize_value(None, subvalue, output, DiffOp::Delete, indent, options);
+            }
+            (Some("+"), [_, subvalue, ..]) => {
+                colorize_value(None, subvalue, output, DiffOp::Add, indent, options);
+            }
+            // The malformed entries, e.g. of a difference wrapped by
+            // `JsonDiff::from_diff_value`, are shown as they are.
+            _ => colorize_value(None, entry, output, DiffOp::Context, indent, options),
+        }
+    }
+}
+
+// Formats a renamed key, whose value may have changed too.
+#[allow(clippy::too_many_arguments)]
+fn colorize_renamed(
+    old_key: &str,
+    new_key: &str,
+    diff: &Value,
+    output: &mut Lines<'_>,
+    op: DiffOp,
+    indent: usize,
+    options: &ContextOptions,
+) {
+    let markers = &options.markers;
+    if diff.is_null() {
+        output.push(DiffOp::Move, indent, || format!("{old_key} -> {new_key}"));
+    } else if let Some((old, new)) = diff.as_object().and_then(|obj| markers.as_change(obj)) {
+        colorize_value(Some(old_key), old, output, DiffOp::Delete, indent, options);
+        colorize_value(Some(new_key), new, output, DiffOp::Add, indent, options);
+    } else {
+        subcolorize(
+            Some(&format!("{old_key} -> {new_key}")),
+            diff,
+            output,
+            op,
+            indent,
+            options,
+        );
+    }
+}
+
+fn subcolorize(
+    key: Option<&str>,
+    diff: &Value,
+    output: &mut Lines<'_>,
+    op: DiffOp,
+    indent: usize,
+    options: &ContextOptions,
+) {
+    let markers = &options.markers;
+    let prefix = || key.map_or_else(String::new, |key| format!("{key}: "));
+    let width = options.indent.width();
+    let subindent = indent + width;
 
     match diff {
         Value::Object(obj) => {
-            if obj.len() == 2 && obj.contains_key("__old") && obj.contains_key("__new") {
-                let old = obj.get("__old").unwrap();
-                let new = obj.get("__new").unwrap();
-                subcolorize(key, old, output, "-", indent);
-                subcolorize(key, new, output, "+", indent);
+            if let Some((old, new)) = markers.as_change(obj) {
+                colorize_value(key, old, output, DiffOp::Delete, indent, options);
+                colorize_value(key, new, output, DiffOp::Add, indent, options);
+            } else if let Some(embedded) = markers.as_embedded(obj) {
+                let key = key.map_or_else(
+                    || String::from("(embedded JSON)"),
+                    |key| format!("{key} (embedded JSON)"),
+                );
+                subcolorize(Some(&key), embedded, output, op, indent, options);
+            } else if let Some(segments) = markers.as_text(obj) {
+                output.push(DiffOp::Change, indent, || {
+                    prefix() + &colorize_text(segments)
+                });
             } else {
-                output(color, &format!("{indent}{prefix}{{"));
-                let re_delete = Regex::new(r"^(.*)__deleted$").unwrap();
-                let re_added = Regex::new(r"^(.*)__added$").unwrap();
+                output.push(op, indent, || prefix() + "{");
                 for (subkey, subvalue) in obj {
-                    if let Some(caps) = re_delete.captures(subkey) {
-                        subcolorize(
-                            Some(caps.get(1).unwrap().as_str()),
+                    match markers.parse_key(subkey) {
+                        MarkedKey::Deleted(deleted_key) => colorize_value(
+                            Some(deleted_key),
                             subvalue,
                             output,
-                            "-",
+                            DiffOp::Delete,
                             subindent,
-                        );
-                        continue;
-                    }
-                    if let Some(caps) = re_added.captures(subkey) {
-                        subcolorize(
-                            Some(caps.get(1).unwrap().as_str()),
+                            options,
+                        ),
+                        MarkedKey::Added(added_key) => colorize_value(
+                            Some(added_key),
+                            subvalue,
+                            output,
+                            DiffOp::Add,
+                            subindent,
+                            options,
+                        ),
+                        MarkedKey::Unchanged(unchanged_key) => colorize_value(
+                            Some(unchanged_key),
                             subvalue,
                             output,
-                            "+",
+                            DiffOp::Context,
                             subindent,
-                        );
-                        continue;
+                            options,
+                        ),
+                        MarkedKey::Shared(shared_key) => {
+                            subcolorize(Some(shared_key), subvalue, output, op, subindent, options);
+                        }
+                        MarkedKey::Renamed(old_key, new_key) => colorize_renamed(
+                            old_key, new_key, subvalue, output, op, subindent, options,
+                        ),
                     }
-                    subcolorize(Some(subkey), subvalue, output, color, subindent);
                 }
-                output(color, &format!("{indent}}}"));
+                output.push(op, indent, || "}");
             }
         }
         Value::Array(array) => {
-            output(color, &format!("{indent}{prefix}["));
+            output.push(op, indent, || prefix() + "[");
 
             let mut looks_like_diff = true;
             for item in array {
                 looks_like_diff = if let Value::Array(arr) = item {
-                    if !(arr.len() == 2
-                        || (arr.len() == 1
-                            && (arr[0].is_string() && arr[0].as_str().unwrap() == " ")))
-                    {
-                        false
-                    } else if let Value::String(str1) = &arr[0] {
-                        str1.len() == 1 && ([" ", "-", "+", "~"].contains(&str1.as_str()))
-                    } else {
-                        false
+                    // Changes may be followed by the indices of their items,
+                    // and by their match score.
+                    let arr = markers.without_score(arr);
+                    let are_indices = |values: &[Value]| values.iter().all(Value::is_u64);
+                    match (arr.first().and_then(Value::as_str), arr.len()) {
+                        (Some(" "), 1 | 2) | (Some("-" | "+" | "~"), 2) => true,
+                        (Some("-" | "+"), 3) | (Some("~"), 4) => are_indices(&arr[2..]),
+                        (Some(">"), 3) => are_indices(&arr[1..]),
+                        _ => false,
                     }
                 } else {
                     false
@@ -71,49 +492,230 @@ where
             }
 
             if looks_like_diff {
-                for item in array {
-                    if let Value::Array(subitem) = item {
-                        let op = subitem[0].as_str().unwrap();
-                        let subvalue = &subitem.get(1);
-                        if op == " " && subvalue.is_none() {
-                            output(" ", &format!("{subindent}..."));
-                        } else {
-                            assert!(([" ", "-", "+", "~"].contains(&op)), "Unexpected op '{op}'");
-                            let subvalue = subvalue.unwrap();
-                            let color = if op == "~" { " " } else { op };
-                            subcolorize(None, subvalue, output, color, subindent);
-                        }
-                    }
-                }
+                colorize_entries(array, output, subindent, options);
             } else {
                 for subvalue in array {
-                    subcolorize(None, subvalue, output, color, subindent);
+                    subcolorize(None, subvalue, output, op, subindent, options);
                 }
             }
 
-            output(color, &format!("{indent}]"));
+            output.push(op, indent, || "]");
         }
-        _ => output(color, &(indent.to_owned() + &prefix + &diff.to_string())),
+        _ => output.push(op, indent, || prefix() + &options.format(diff)),
     }
 }
 
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "a boolean",
+        Value::Number(_) => "a number",
+        Value::String(_) => "a string",
+        Value::Array(_) => "an array",
+        Value::Object(_) => "an object",
+    }
+}
+
+// Formats the whole JSON structural difference. A root object or array
+// replaced by a value of another type may be introduced by a line
+// describing it, since it is otherwise only shown as the deletion of the
+// first JSON file and the addition of the second one.
+fn colorize_root(diff: &Value, output: &mut Lines<'_>, options: &ContextOptions) {
+    let markers = &options.markers;
+    let change = diff
+        .as_object()
+        .filter(|_| options.describe_root_type)
+        .and_then(|obj| markers.as_change(obj));
+    if let Some((old, new)) = change {
+        let is_container = |value: &Value| value.is_object() || value.is_array();
+        let (old_type, new_type) = (type_name(old), type_name(new));
+        if old_type != new_type && (is_container(old) || is_container(new)) {
+            output.push(DiffOp::Context, 0, || {
+                format!(
+                    "(the whole document is replaced, its type changed from {old_type} \
+                     to {new_type})"
+                )
+            });
+        }
+    }
+    subcolorize(None, diff, output, DiffOp::Context, 0, options);
+}
+
+/// Returns the lines of the JSON structural difference as records, so
+/// that they can be rendered without parsing their prefix.
+///
+/// If `None`, there is no JSON structural difference to be formatted.
+#[must_use]
+#[allow(clippy::module_name_repetitions)]
+pub fn colorize_to_records(diff: &Value) -> Vec<DiffLine> {
+    colorize_to_records_with(diff, &ContextOptions::default())
+}
+
+/// Returns the lines of the JSON structural difference as records, showing
+/// the unchanged array items and indenting the nested values according to
+/// the given options.
+#[must_use]
+#[allow(clippy::module_name_repetitions)]
+pub fn colorize_to_records_with(diff: &Value, options: &ContextOptions) -> Vec<DiffLine> {
+    let mut lines: Vec<DiffLine> = Vec::new();
+    let mut output = |line| lines.push(line);
+    let mut output = Lines::new(&mut output, options);
+    colorize_root(diff, &mut output, options);
+    let more_lines = output.skipped;
+    if more_lines > 0 {
+        let noun = if more_lines == 1 { "line" } else { "lines" };
+        lines.push(DiffLine::new(
+            DiffOp::Context,
+            0,
+            format!("... ({more_lines} more {noun})"),
+        ));
+    }
+    lines
+}
+
+/// Writes the JSON structural difference formatted as text into `out`,
+/// each line followed by a newline, without collecting its lines first.
+///
+/// # Errors
+///
+/// Returns the first error of `out`, after which nothing else is written.
+#[allow(clippy::module_name_repetitions)]
+pub fn colorize_into<W: fmt::Write>(diff: &Value, out: &mut W) -> fmt::Result {
+    colorize_into_with(diff, out, &ContextOptions::default())
+}
+
+/// Writes the JSON structural difference formatted according to the given
+/// options into `out`, as [`colorize_into`] does.
+pub(crate) fn colorize_into_with<W: fmt::Write>(
+    diff: &Value,
+    out: &mut W,
+    options: &ContextOptions,
+) -> fmt::Result {
+    let mut result = Ok(());
+    let mut output = |line| {
+        if result.is_ok() {
+            result = writeln!(out, "{line}");
+        }
+    };
+    colorize_root(diff, &mut Lines::new(&mut output, options), options);
+    result
+}
+
 /// Returns the JSON structural difference formatted as a `Vec<String>`.
 ///
 /// If `None`, there is no JSON structural difference to be formatted.
 #[must_use]
 #[allow(clippy::module_name_repetitions)]
 pub fn colorize_to_array(diff: &Value) -> Vec<String> {
-    let mut output: Vec<String> = Vec::new();
+    colorize_to_records(diff)
+        .iter()
+        .map(ToString::to_string)
+        .collect()
+}
 
-    let mut output_func = |color: &str, line: &str| {
-        output.push(format!("{color}{line}"));
-    };
+/// Returns the JSON structural difference formatted as a `Vec<String>`,
+/// showing the unchanged array items and indenting the nested values
+/// according to the given options.
+#[must_use]
+#[allow(clippy::module_name_repetitions)]
+pub fn colorize_to_array_with(diff: &Value, options: &ContextOptions) -> Vec<String> {
+    colorize_to_records_with(diff, options)
+        .iter()
+        .map(|line| options.indent.format(line))
+        .collect()
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
 
-    subcolorize(None, diff, &mut output_func, " ", "");
+/// Returns the JSON structural difference formatted as an HTML `<pre>` block.
+///
+/// Each line is wrapped in a `<span>` element whose class depends on the
+/// kind of line, so that the output can be styled with CSS:
+///
+/// - `diff-add`: added lines
+/// - `diff-del`: deleted lines
+/// - `diff-ctx`: unchanged context lines, including the lines of array items
+///   changed in place
+///
+/// The JSON content is HTML-escaped.
+#[must_use]
+pub fn colorize_to_html(diff: &Value) -> String {
+    let mut output = String::from("<pre>");
 
+    for line in colorize_to_records(diff) {
+        let class = match line.op {
+            DiffOp::Add => "diff-add",
+            DiffOp::Delete => "diff-del",
+            DiffOp::Context | DiffOp::Change | DiffOp::Move => "diff-ctx",
+        };
+        output.push_str("<span class=\"");
+        output.push_str(class);
+        output.push_str("\">");
+        output.push_str(&escape_html(&line.to_string()));
+        output.push_str("</span>\n");
+    }
+
+    output.push_str("</pre>");
     output
 }
 
+/// Styles used to colorize the lines of a JSON structural difference.
+#[cfg(feature = "colorize")]
+#[derive(Clone, Debug)]
+pub struct Theme {
+    /// Style of the added lines.
+    pub added: console::Style,
+    /// Style of the deleted lines.
+    pub deleted: console::Style,
+    /// Style of the unchanged context lines.
+    pub context: console::Style,
+    /// Style of the lines of array items changed in place.
+    pub changed: console::Style,
+}
+
+#[cfg(feature = "colorize")]
+impl Default for Theme {
+    /// Added lines in green, deleted lines in red, lines of changed
+    /// array items in yellow and context lines without any style.
+    fn default() -> Self {
+        use console::Style;
+
+        Self {
+            added: Style::new().green(),
+            deleted: Style::new().red(),
+            context: Style::new(),
+            changed: Style::new().yellow(),
+        }
+    }
+}
+
+#[cfg(feature = "colorize")]
+impl Theme {
+    /// Creates a theme which does not apply any style.
+    #[must_use]
+    pub fn plain() -> Self {
+        use console::Style;
+
+        Self {
+            added: Style::new(),
+            deleted: Style::new(),
+            context: Style::new(),
+            changed: Style::new(),
+        }
+    }
+}
+
 /// Returns the JSON structural difference formatted as a `String`.
 ///
 /// If `None`, there is no JSON structural difference to be formatted.
@@ -121,33 +723,102 @@ pub fn colorize_to_array(diff: &Value) -> Vec<String> {
 #[must_use]
 #[allow(clippy::module_name_repetitions)]
 pub fn colorize(diff: &Value, is_color: bool) -> String {
-    use console::Style;
+    let theme = if is_color {
+        Theme::default()
+    } else {
+        Theme::plain()
+    };
+    colorize_with(diff, &theme)
+}
 
-    let mut output: Vec<String> = Vec::new();
+/// Returns the JSON structural difference formatted as a `String`
+/// and colorized according to the given theme.
+#[cfg(feature = "colorize")]
+#[must_use]
+pub fn colorize_with(diff: &Value, theme: &Theme) -> String {
+    use core::fmt::Write;
 
-    let mut output_func = |color: &str, line: &str| {
-        let color_line = format!("{color}{line}");
-        let str_output = if is_color {
-            match color {
-                "+" => format!("{}", Style::new().green().apply_to(color_line)),
-                "-" => format!("{}", Style::new().red().apply_to(color_line)),
-                _ => color_line,
-            }
-        } else {
-            color_line
-        };
-        output.push(str_output + "\n");
-    };
+    let mut output = String::new();
 
-    subcolorize(None, diff, &mut output_func, " ", "");
+    for line in colorize_to_records(diff) {
+        let style = match line.op {
+            DiffOp::Add => &theme.added,
+            DiffOp::Delete => &theme.deleted,
+            DiffOp::Context | DiffOp::Move => &theme.context,
+            DiffOp::Change => &theme.changed,
+        };
+        // Writing into a `String` never fails.
+        let _ = writeln!(output, "{}", style.apply_to(line));
+    }
 
-    output.join("")
+    output
 }
 
 #[cfg(test)]
 mod tests {
 
-    use super::colorize_to_array;
+    use alloc::borrow::ToOwned;
+    use alloc::format;
+    use alloc::string::{String, ToString};
+    use core::fmt;
+    use core::sync::atomic::{AtomicUsize, Ordering};
+
+    use serde_json::Value;
+
+    use super::{
+        colorize_into, colorize_to_array, colorize_to_array_with, colorize_to_html,
+        colorize_to_records, colorize_to_records_with, ContextOptions, DiffLine, DiffOp, Indent,
+    };
+
+    #[test]
+    fn test_colorize_into() {
+        struct Full;
+        impl fmt::Write for Full {
+            fn write_str(&mut self, _text: &str) -> fmt::Result {
+                Err(fmt::Error)
+            }
+        }
+
+        let diff = json!({
+            "foo": {"__old": 42, "__new": 10},
+            "bar__added": [1, {"baz": true}],
+            "qux": [[" ", 1], ["-", 2], ["~", {"a": {"__old": 1, "__new": 2}}]]
+        });
+        let mut output = String::new();
+        colorize_into(&diff, &mut output).unwrap();
+        assert_eq!(output, colorize_to_array(&diff).join("\n") + "\n");
+
+        assert_eq!(colorize_into(&diff, &mut Full), Err(fmt::Error));
+    }
+
+    #[test]
+    fn test_colorize_malformed_entries() {
+        // Only the last entry is checked to tell an array difference apart,
+        // so the malformed entries before it are shown as they are.
+        let diff = json!([["?", 1], [">", 0], [2], ["-"], [" ", 3]]);
+        assert_eq!(
+            colorize_to_array(&diff),
+            &[
+                " [",
+                "   [",
+                "     \"?\"",
+                "     1",
+                "   ]",
+                "   [",
+                "     \">\"",
+                "     0",
+                "   ]",
+                "   [",
+                "     2",
+                "   ]",
+                "   [",
+                "     \"-\"",
+                "   ]",
+                "   3",
+                " ]"
+            ]
+        );
+    }
 
     #[test]
     fn test_colorize_to_array() {
@@ -162,6 +833,12 @@ mod tests {
             &["-42", "+10"]
         );
 
+        // The sides of a change are found whatever their order.
+        assert_eq!(
+            colorize_to_array(&json!({"__new": 10, "__old": 42})),
+            &["-42", "+10"]
+        );
+
         assert_eq!(
             colorize_to_array(&json!({"__old": false, "__new": null })),
             &["-false", "+null"]
@@ -177,6 +854,21 @@ mod tests {
             &[" {", "+  foo: 42", " }"]
         );
 
+        assert_eq!(
+            colorize_to_array(&json!([['-', 20, 1], ['~', {"foo__added": 42}, 2, 1]])),
+            &[" [", "-  20", "   {", "+    foo: 42", "   }", " ]"]
+        );
+
+        assert_eq!(
+            colorize_to_array(&json!([['>', 2, 0], [' ', 1]])),
+            &[" [", ">  2 -> 0", "   1", " ]"]
+        );
+
+        assert_eq!(
+            colorize_to_array(&json!({"foo__added__deleted": 42, "foo__added_bar": 10 })),
+            &[" {", "-  foo__added: 42", "   foo__added_bar: 10", " }"]
+        );
+
         assert_eq!(
             colorize_to_array(&json!({ "foo__added": null })),
             &[" {", "+  foo: null", " }"]
@@ -221,6 +913,380 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_colorize_root_type() {
+        let options = ContextOptions {
+            describe_root_type: true,
+            ..ContextOptions::default()
+        };
+        // A root replaced by a value of another type is deleted and added
+        // as a whole, and described only if requested.
+        let diff = json!({"__old": [1, 2], "__new": {"a": 1}});
+        assert_eq!(
+            colorize_to_array(&diff),
+            &["-[", "-  1", "-  2", "-]", "+{", "+  a: 1", "+}"]
+        );
+        assert_eq!(
+            colorize_to_array_with(&diff, &options),
+            &[
+                " (the whole document is replaced, its type changed from an array to an object)",
+                "-[",
+                "-  1",
+                "-  2",
+                "-]",
+                "+{",
+                "+  a: 1",
+                "+}"
+            ]
+        );
+        let diff = json!({"__old": {"a": 1}, "__new": 3});
+        assert_eq!(colorize_to_array(&diff), &["-{", "-  a: 1", "-}", "+3"]);
+        assert_eq!(
+            colorize_to_array_with(&diff, &options),
+            &[
+                " (the whole document is replaced, its type changed from an object to a number)",
+                "-{",
+                "-  a: 1",
+                "-}",
+                "+3"
+            ]
+        );
+
+        // Scalars and nested values replaced by a value of another type are
+        // never described.
+        assert_eq!(
+            colorize_to_array_with(&json!({"__old": 42, "__new": "a"}), &options),
+            &["-42", "+\"a\""]
+        );
+        assert_eq!(
+            colorize_to_array_with(&json!({"foo": {"__old": [1], "__new": 1}}), &options),
+            &[" {", "-  foo: [", "-    1", "-  ]", "+  foo: 1", " }"]
+        );
+
+        // The description is a context line.
+        assert_eq!(
+            colorize_to_records_with(&json!({"__old": [], "__new": "foo"}), &options),
+            &[
+                DiffLine::new(
+                    DiffOp::Context,
+                    0,
+                    "(the whole document is replaced, its type changed from an array to a string)"
+                ),
+                DiffLine::new(DiffOp::Delete, 0, "["),
+                DiffLine::new(DiffOp::Delete, 0, "]"),
+                DiffLine::new(DiffOp::Add, 0, "\"foo\""),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_colorize_to_array_with() {
+        let diff = json!([
+            [' ', 1],
+            [' ', 2],
+            [' ', 3],
+            ['-', 4],
+            ['+', 5],
+            [' ', 6],
+            [' ', 7],
+            [' ', 8],
+            [' ', 9],
+            [' ', 10],
+            ['~', {"a": {"__old": 1, "__new": 2}}],
+            [' ', 11]
+        ]);
+        let with_context = |diff: &serde_json::Value, context| {
+            colorize_to_array_with(
+                diff,
+                &ContextOptions {
+                    context,
+                    ..ContextOptions::default()
+                },
+            )
+        };
+
+        assert_eq!(with_context(&diff, None), colorize_to_array(&diff));
+        // Every run is short enough.
+        assert_eq!(with_context(&diff, Some(3)), colorize_to_array(&diff));
+        assert_eq!(
+            with_context(&diff, Some(2)),
+            &[
+                " [",
+                "   ...",
+                "   2",
+                "   3",
+                "-  4",
+                "+  5",
+                "   6",
+                "   7",
+                "   ...",
+                "   9",
+                "   10",
+                "   {",
+                "-    a: 1",
+                "+    a: 2",
+                "   }",
+                "   11",
+                " ]"
+            ]
+        );
+        assert_eq!(
+            with_context(&diff, Some(1)),
+            &[
+                " [",
+                "   ...",
+                "   3",
+                "-  4",
+                "+  5",
+                "   6",
+                "   ...",
+                "   10",
+                "   {",
+                "-    a: 1",
+                "+    a: 2",
+                "   }",
+                "   11",
+                " ]"
+            ]
+        );
+        assert_eq!(
+            with_context(&diff, Some(0)),
+            &[
+                " [",
+                "   ...",
+                "-  4",
+                "+  5",
+                "   ...",
+                "   {",
+                "-    a: 1",
+                "+    a: 2",
+                "   }",
+                "   ...",
+                " ]"
+            ]
+        );
+
+        // The unchanged items without a value are merged with the collapsed
+        // ones, and nested arrays are collapsed too.
+        let diff = json!({"foo": [[' '], [' ', 1], [' ', 2], ['+', 3], [' ']]});
+        assert_eq!(
+            with_context(&diff, Some(1)),
+            &[
+                " {",
+                "   foo: [",
+                "     ...",
+                "     2",
+                "+    3",
+                "     ...",
+                "   ]",
+                " }"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_colorize_format_leaf() {
+        fn truncate(value: &Value) -> String {
+            match value.as_str() {
+                Some(text) if text.len() > 8 => format!("<{} characters string>", text.len()),
+                _ => value.to_string(),
+            }
+        }
+
+        let diff = json!({
+            "blob": {"__old": "aGVsbG8gd29ybGQ=", "__new": "Ym9uam91cg=="},
+            "name__added": "short",
+            "items": [["-", {"data": "a long string"}], ["+", 2]],
+        });
+        let options = ContextOptions {
+            format_leaf: Some(truncate),
+            ..ContextOptions::default()
+        };
+        assert_eq!(
+            colorize_to_array_with(&diff, &options),
+            &[
+                " {",
+                "-  blob: <16 characters string>",
+                "+  blob: <12 characters string>",
+                "   items: [",
+                "-    {",
+                "-      data: <13 characters string>",
+                "-    }",
+                "+    2",
+                "   ]",
+                "+  name: \"short\"",
+                " }",
+            ]
+        );
+
+        // By default, the values are formatted as JSON.
+        assert_eq!(
+            colorize_to_array_with(&diff, &ContextOptions::default())[1],
+            "-  blob: \"aGVsbG8gd29ybGQ=\""
+        );
+        assert_ne!(options, ContextOptions::default());
+        assert_eq!(options, options);
+    }
+
+    #[test]
+    fn test_colorize_max_lines() {
+        static FORMATTED: AtomicUsize = AtomicUsize::new(0);
+
+        let diff = Value::Array(
+            (0..1000)
+                .map(|index| json!(["~", {"id": {"__old": index, "__new": index + 1}}]))
+                .collect(),
+        );
+        let with_max_lines = |max_lines| {
+            colorize_to_array_with(
+                &diff,
+                &ContextOptions {
+                    max_lines,
+                    ..ContextOptions::default()
+                },
+            )
+        };
+
+        // Each item takes 4 lines, within the brackets of the array.
+        assert_eq!(
+            with_max_lines(Some(5)),
+            &[
+                " [",
+                "   {",
+                "-    id: 0",
+                "+    id: 1",
+                "   }",
+                " ... (3997 more lines)",
+            ]
+        );
+        assert_eq!(with_max_lines(Some(4001)).len(), 4002);
+        assert_eq!(with_max_lines(Some(4001))[4001], " ... (1 more line)");
+        assert_eq!(with_max_lines(Some(4002)), with_max_lines(None));
+        assert_eq!(with_max_lines(Some(0)), &[" ... (4002 more lines)"]);
+
+        // The lines beyond are counted without being formatted.
+        let lines = colorize_to_array_with(
+            &diff,
+            &ContextOptions {
+                max_lines: Some(5),
+                format_leaf: Some(|value| {
+                    FORMATTED.fetch_add(1, Ordering::Relaxed);
+                    value.to_string()
+                }),
+                ..ContextOptions::default()
+            },
+        );
+        assert_eq!(lines.len(), 6);
+        assert_eq!(FORMATTED.load(Ordering::Relaxed), 2);
+    }
+
+    #[test]
+    fn test_colorize_indent() {
+        let diff = json!({"foo": {"bar": {"__old": 1, "__new": 2}, "baz__added": [true]}});
+        let with_indent = |indent| {
+            colorize_to_array_with(
+                &diff,
+                &ContextOptions {
+                    indent,
+                    ..ContextOptions::default()
+                },
+            )
+        };
+
+        assert_eq!(with_indent(Indent::default()), colorize_to_array(&diff));
+        assert_eq!(
+            with_indent(Indent::Spaces(4)),
+            &[
+                " {",
+                "     foo: {",
+                "-        bar: 1",
+                "+        bar: 2",
+                "+        baz: [",
+                "+            true",
+                "+        ]",
+                "     }",
+                " }"
+            ]
+        );
+        assert_eq!(
+            with_indent(Indent::Tab),
+            &[
+                " {",
+                " \tfoo: {",
+                "-\t\tbar: 1",
+                "+\t\tbar: 2",
+                "+\t\tbaz: [",
+                "+\t\t\ttrue",
+                "+\t\t]",
+                " \t}",
+                " }"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_colorize_to_records() {
+        let line = |op, indent, text: &str| DiffLine {
+            op,
+            indent,
+            text: text.to_owned(),
+        };
+
+        assert_eq!(
+            colorize_to_records(&json!([[" "], ["~", {"foo__added": 42}], [" "]])),
+            &[
+                line(DiffOp::Context, 0, "["),
+                line(DiffOp::Context, 2, "..."),
+                line(DiffOp::Change, 2, "{"),
+                line(DiffOp::Add, 4, "foo: 42"),
+                line(DiffOp::Change, 2, "}"),
+                line(DiffOp::Context, 2, "..."),
+                line(DiffOp::Context, 0, "]"),
+            ]
+        );
+
+        assert_eq!(
+            colorize_to_records(&json!({"foo": {"__old": 42, "__new": 10}})),
+            &[
+                line(DiffOp::Context, 0, "{"),
+                line(DiffOp::Delete, 2, "foo: 42"),
+                line(DiffOp::Add, 2, "foo: 10"),
+                line(DiffOp::Context, 0, "}"),
+            ]
+        );
+        assert_eq!(line(DiffOp::Delete, 2, "foo: 42").to_string(), "-  foo: 42");
+    }
+
+    #[test]
+    fn test_colorize_to_html() {
+        assert_eq!(
+            colorize_to_html(&json!({"foo": {"__old": 42, "__new": 10 } })),
+            "<pre><span class=\"diff-ctx\"> {</span>\n\
+             <span class=\"diff-del\">-  foo: 42</span>\n\
+             <span class=\"diff-add\">+  foo: 10</span>\n\
+             <span class=\"diff-ctx\"> }</span>\n</pre>"
+        );
+
+        assert_eq!(
+            colorize_to_html(&json!({"foo": {"__old": "<a> & b", "__new": "c"} })),
+            "<pre><span class=\"diff-ctx\"> {</span>\n\
+             <span class=\"diff-del\">-  foo: \"&lt;a&gt; &amp; b\"</span>\n\
+             <span class=\"diff-add\">+  foo: \"c\"</span>\n\
+             <span class=\"diff-ctx\"> }</span>\n</pre>"
+        );
+
+        assert_eq!(
+            colorize_to_html(&json!([[" "], ["~", {"foo__added": 42}], [" "]])),
+            "<pre><span class=\"diff-ctx\"> [</span>\n\
+             <span class=\"diff-ctx\">   ...</span>\n\
+             <span class=\"diff-ctx\">   {</span>\n\
+             <span class=\"diff-add\">+    foo: 42</span>\n\
+             <span class=\"diff-ctx\">   }</span>\n\
+             <span class=\"diff-ctx\">   ...</span>\n\
+             <span class=\"diff-ctx\"> ]</span>\n</pre>"
+        );
+    }
+
     #[test]
     #[cfg(feature = "colorize")]
     fn test_colorize_no_colors() {
@@ -230,4 +1296,54 @@ mod tests {
             " {\n-  foo: 42\n+  foo: 10\n }\n"
         );
     }
+
+    #[test]
+    #[cfg(feature = "colorize")]
+    fn test_colorize_with_theme() {
+        use console::Style;
+
+        use super::{colorize_with, Theme};
+
+        let theme = Theme {
+            added: Style::new().blue().bold().force_styling(true),
+            deleted: Style::new().yellow().dim().force_styling(true),
+            context: Style::new(),
+            changed: Style::new().cyan().force_styling(true),
+        };
+        assert_eq!(
+            colorize_with(&json!({"foo": {"__old": 42, "__new": 10 } }), &theme),
+            concat!(
+                " {\n",
+                "\u{1b}[33m\u{1b}[2m-  foo: 42\u{1b}[0m\n",
+                "\u{1b}[34m\u{1b}[1m+  foo: 10\u{1b}[0m\n",
+                " }\n"
+            )
+        );
+
+        assert_eq!(
+            colorize_with(
+                &json!({"foo": {"__old": 42, "__new": 10 } }),
+                &Theme::plain()
+            ),
+            " {\n-  foo: 42\n+  foo: 10\n }\n"
+        );
+
+        // The lines of array items changed in place are distinct from context.
+        let diff = json!([[" ", 1], ["~", {"foo__added": 42}]]);
+        assert_eq!(
+            colorize_with(&diff, &theme),
+            concat!(
+                " [\n",
+                "   1\n",
+                "\u{1b}[36m   {\u{1b}[0m\n",
+                "\u{1b}[34m\u{1b}[1m+    foo: 42\u{1b}[0m\n",
+                "\u{1b}[36m   }\u{1b}[0m\n",
+                " ]\n"
+            )
+        );
+        assert_eq!(
+            colorize_with(&diff, &Theme::plain()),
+            colorize_to_array(&diff).join("\n") + "\n"
+        );
+    }
 }
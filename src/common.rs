@@ -0,0 +1,175 @@
+use alloc::format;
+use alloc::vec::Vec;
+
+use serde_json::{Map, Value};
+
+use crate::diff::JsonDiff;
+use crate::error::DiffError;
+use crate::options::DiffOptions;
+
+impl JsonDiff {
+    /// Finds the structure common to two JSON files, the inverse of their
+    /// difference, using the given options.
+    ///
+    /// Two objects have in common their shared keys, along with what their
+    /// values have in common, e.g. `{"a": 1, "b": 2}` and `{"a": 1, "b": 3,
+    /// "c": 4}` have `{"a": 1}` in common. Two arrays have in common their
+    /// items matched as their difference matches them, in the order of the
+    /// first array, along with what these items have in common. Two equal
+    /// scalars have the scalar of the first JSON file in common.
+    ///
+    /// Returns `None` if the JSON files have nothing in common, i.e. their
+    /// types differ or they are different scalars. Two objects, or two
+    /// arrays, always have a structure in common, even if it is empty.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DiffError::TooDeep`] if a JSON file is nested deeper than
+    /// [`DiffOptions::max_nesting`].
+    pub fn common(
+        json1: &Value,
+        json2: &Value,
+        options: &DiffOptions,
+    ) -> Result<Option<Value>, DiffError> {
+        options.check_nesting(json1)?;
+        options.check_nesting(json2)?;
+        let (json1, json2) = (options.normalize(json1), options.normalize(json2));
+        Self::common_value(&json1, &json2, options, "")
+    }
+
+    fn common_value(
+        json1: &Value,
+        json2: &Value,
+        options: &DiffOptions,
+        path: &str,
+    ) -> Result<Option<Value>, DiffError> {
+        match (json1, json2) {
+            (Value::Object(obj1), Value::Object(obj2)) => {
+                let mut common = Map::new();
+                for (key, value1) in obj1 {
+                    let key_path = Self::child_path(path, key);
+                    if options.is_ignored(key, &key_path) {
+                        continue;
+                    }
+                    let Some(value2) = Self::counterpart(obj2, key, value1, options) else {
+                        continue;
+                    };
+                    if let Some(value) = Self::common_value(value1, value2, options, &key_path)? {
+                        common.insert(key.clone(), value);
+                    }
+                }
+                Ok(Some(Value::Object(common)))
+            }
+            (Value::Array(array1), Value::Array(array2)) => {
+                let mut pairs = Self::array_pairs(array1, array2, options, path)?;
+                pairs.sort_unstable();
+                let mut common = Vec::with_capacity(pairs.len());
+                for (index1, index2) in pairs {
+                    let item_path = format!("{path}/{index1}");
+                    if let Some(item) =
+                        Self::common_value(&array1[index1], &array2[index2], options, &item_path)?
+                    {
+                        common.push(item);
+                    }
+                }
+                Ok(Some(Value::Array(common)))
+            }
+            (Value::Object(_) | Value::Array(_), _) | (_, Value::Object(_) | Value::Array(_)) => {
+                Ok(None)
+            }
+            _ => Ok(Self::diff_with_score(json1, json2, options, path)?
+                .diff
+                .is_none()
+                .then(|| json1.clone())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use alloc::vec;
+
+    use crate::diff::JsonDiff;
+    use crate::options::DiffOptions;
+
+    #[test]
+    fn test_common() {
+        let json1 = json!({
+            "id": 1,
+            "name": "foo",
+            "tags": ["a", "b", "c"],
+            "meta": {"version": 1, "author": "bar", "draft": true},
+            "size": 10,
+        });
+        let json2 = json!({
+            "id": 1,
+            "name": "baz",
+            "tags": ["b", "c", "d"],
+            "meta": {"version": 2, "author": "bar"},
+            "extra": null,
+        });
+
+        let options = DiffOptions::default();
+        assert_eq!(
+            JsonDiff::common(&json1, &json2, &options).unwrap(),
+            Some(json!({
+                "id": 1,
+                "tags": ["b", "c"],
+                "meta": {"author": "bar"},
+            }))
+        );
+        assert_eq!(
+            JsonDiff::common(&json1, &json1, &options).unwrap(),
+            Some(json1.clone())
+        );
+
+        // The matched objects of arrays keep what they have in common, in
+        // the order of the first array.
+        assert_eq!(
+            JsonDiff::common(
+                &json!([{"id": 1, "x": 1}, 5, {"id": 2, "x": 2}]),
+                &json!([{"id": 2, "x": 3}, {"id": 1, "x": 1}]),
+                &options
+            )
+            .unwrap(),
+            Some(json!([{"id": 1, "x": 1}, {"id": 2}]))
+        );
+
+        // Containers always have a structure in common, unlike different
+        // scalars and values of different types.
+        assert_eq!(
+            JsonDiff::common(&json!({"a": 1}), &json!({"b": 1}), &options).unwrap(),
+            Some(json!({}))
+        );
+        assert_eq!(
+            JsonDiff::common(&json!({"a": [1]}), &json!({"a": {}}), &options).unwrap(),
+            Some(json!({}))
+        );
+        assert_eq!(
+            JsonDiff::common(&json!(1), &json!(2), &options).unwrap(),
+            None
+        );
+        assert_eq!(
+            JsonDiff::common(&json!([1]), &json!({}), &options).unwrap(),
+            None
+        );
+
+        // The values are compared with the options, and the ignored keys are
+        // left out.
+        let options = DiffOptions {
+            epsilon: Some(0.5),
+            ignore_keys: vec!["id".into()],
+            ..DiffOptions::default()
+        };
+        assert_eq!(
+            JsonDiff::common(
+                &json!({"id": 1, "x": 1.0, "y": 1}),
+                &json!({"id": 1, "x": 1.2, "y": 2}),
+                &options
+            )
+            .unwrap(),
+            Some(json!({"x": 1.0}))
+        );
+    }
+}
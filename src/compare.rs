@@ -0,0 +1,177 @@
+use core::fmt;
+
+use serde_json::Value;
+
+/// Decides whether two scalars of the JSON files are equal, e.g. two
+/// timestamps denoting the same instant.
+///
+/// It is implemented for every `Fn(&Value, &Value) -> Option<bool>`
+/// closure.
+pub trait ScalarComparator: Send + Sync {
+    /// Returns whether `value1` and `value2` are equal, or `None` to defer
+    /// to the next comparator, then to the default comparison.
+    fn equal(&self, value1: &Value, value2: &Value) -> Option<bool>;
+}
+
+impl<F: Fn(&Value, &Value) -> Option<bool> + Send + Sync> ScalarComparator for F {
+    fn equal(&self, value1: &Value, value2: &Value) -> Option<bool> {
+        self(value1, value2)
+    }
+}
+
+impl fmt::Debug for dyn ScalarComparator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ScalarComparator")
+    }
+}
+
+/// Compares the strings holding RFC 3339 timestamps as instants, e.g.
+/// `"2024-01-01T00:00:00Z"` is equal to `"2024-01-01T01:00:00+01:00"`.
+///
+/// The other values, including the strings which are not timestamps, are
+/// compared as usual.
+#[cfg(feature = "chrono")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DateTimes;
+
+#[cfg(feature = "chrono")]
+impl ScalarComparator for DateTimes {
+    fn equal(&self, value1: &Value, value2: &Value) -> Option<bool> {
+        let parse = |value: &Value| chrono::DateTime::parse_from_rfc3339(value.as_str()?).ok();
+        Some(parse(value1)? == parse(value2)?)
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+
+    use std::sync::Arc;
+
+    use serde_json::Value;
+
+    use super::ScalarComparator;
+    use crate::diff::JsonDiff;
+    use crate::options::DiffOptions;
+
+    // Versions are equal up to their trailing zero components.
+    struct Versions;
+
+    fn trim_version(version: &str) -> &str {
+        let mut version = version;
+        while let Some(trimmed) = version.strip_suffix(".0") {
+            version = trimmed;
+        }
+        version
+    }
+
+    impl ScalarComparator for Versions {
+        fn equal(&self, value1: &Value, value2: &Value) -> Option<bool> {
+            Some(trim_version(value1.as_str()?) == trim_version(value2.as_str()?))
+        }
+    }
+
+    #[test]
+    fn test_scalar_comparators() {
+        let options = DiffOptions {
+            scalar_comparators: vec![Arc::new(Versions)],
+            ..DiffOptions::default()
+        };
+        assert_eq!(
+            JsonDiff::diff_with_options(
+                &json!({"version": "1.0.0", "deps": ["1.0", "2.1"]}),
+                &json!({"version": "1.0", "deps": ["1.0.0", "2.1.0"]}),
+                &options
+            )
+            .diff,
+            None
+        );
+        assert_eq!(
+            JsonDiff::diff_with_options(
+                &json!({"version": "1.0.0", "count": 1}),
+                &json!({"version": "1.1", "count": 2}),
+                &options
+            )
+            .diff,
+            Some(json!({
+                "version": {"__old": "1.0.0", "__new": "1.1"},
+                "count": {"__old": 1, "__new": 2}
+            }))
+        );
+        assert!(JsonDiff::diff(&json!("1.0.0"), &json!("1.0"), false)
+            .diff
+            .is_some());
+
+        // Every comparator is consulted until one decides, and closures
+        // are comparators too.
+        let options = DiffOptions {
+            scalar_comparators: vec![
+                Arc::new(|value1: &Value, value2: &Value| {
+                    (value1.is_number() && value2.is_number()).then_some(true)
+                }),
+                Arc::new(|value1: &Value, value2: &Value| {
+                    Some(value1.as_str()?.eq_ignore_ascii_case(value2.as_str()?))
+                }),
+            ],
+            ..DiffOptions::default()
+        };
+        assert_eq!(
+            JsonDiff::diff_with_options(
+                &json!({"n": 1, "s": "a", "t": "x"}),
+                &json!({"n": 2, "s": "A", "t": "y"}),
+                &options
+            )
+            .diff,
+            Some(json!({"t": {"__old": "x", "__new": "y"}}))
+        );
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn test_date_times() {
+        let options = DiffOptions {
+            scalar_comparators: vec![Arc::new(super::DateTimes)],
+            ..DiffOptions::default()
+        };
+
+        assert_eq!(
+            JsonDiff::diff_with_options(
+                &json!({
+                    "created": "2024-01-01T00:00:00Z",
+                    "times": ["2024-01-01T12:00:00.5Z"]
+                }),
+                &json!({
+                    "created": "2024-01-01T00:00:00+00:00",
+                    "times": ["2024-01-01T13:00:00.500+01:00"]
+                }),
+                &options
+            )
+            .diff,
+            None
+        );
+        assert_eq!(
+            JsonDiff::diff_with_options(
+                &json!({"created": "2024-01-01T00:00:00Z"}),
+                &json!({"created": "2024-01-01T00:00:01Z"}),
+                &options
+            )
+            .diff,
+            Some(json!({
+                "created": {"__old": "2024-01-01T00:00:00Z", "__new": "2024-01-01T00:00:01Z"}
+            }))
+        );
+
+        // The strings which are not timestamps are compared as usual.
+        assert_eq!(
+            JsonDiff::diff_with_options(
+                &json!({"name": "foo", "created": "2024-01-01"}),
+                &json!({"name": "bar", "created": "2024-01-01T00:00:00Z"}),
+                &options
+            )
+            .diff,
+            Some(json!({
+                "name": {"__old": "foo", "__new": "bar"},
+                "created": {"__old": "2024-01-01", "__new": "2024-01-01T00:00:00Z"}
+            }))
+        );
+    }
+}
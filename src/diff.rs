@@ -1,36 +1,146 @@
-use difflib::sequencematcher::SequenceMatcher;
-use serde_json::{Map, Value};
+use alloc::borrow::Cow;
+use alloc::collections::btree_map::Entry;
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use core::mem;
+#[cfg(feature = "std")]
+use std::path::Path;
 
-use crate::colorize::colorize_to_array;
+use serde_json::{Map, Number, Value};
+
+use crate::budget::ChangeBudget;
+use crate::colorize::{colorize_into_with, colorize_to_array, ContextOptions, LineOptions};
+use crate::error::DiffError;
+use crate::flatten::ChangeKind;
+use crate::markers::DiffMarkers;
+use crate::options::DiffOptions;
+use crate::parallel::map_in_order;
+use crate::pointer::{self, JsonPointer};
+use crate::sequence::SequenceMatcher;
 
 /// Auxiliary structure to encapsulate data about the structural difference
 /// of two JSON files.
 #[allow(clippy::module_name_repetitions)]
+#[derive(Clone, Debug)]
 pub struct JsonDiff {
     /// Quantifies the difference between two JSON files.
     ///
     /// If `0.`: the two JSON files are entirely different one from the other.
     /// If `100.`: the two JSON files are identical.
+    ///
+    /// Identical objects score `100.` for each of their keys, so the score
+    /// is not bounded above. Use [`JsonDiff::similarity`] to get a value
+    /// normalized between `0.` and `1.`.
     pub score: f64,
     /// The JSON structural difference of two JSON files.
     ///
+    /// The keys of objects are sorted, unless the `preserve_order` feature
+    /// of `serde_json` is enabled. In that case, the deleted and changed
+    /// keys follow the order of the first JSON file, then the added keys
+    /// follow the order of the second one, unless
+    /// [`DiffOptions::sort_keys`] is set.
+    ///
     /// If `None`: the two JSON files are identical.
     pub diff: Option<Value>,
+    /// Whether [`DiffOptions::max_changes`] has been exceeded, so that some
+    /// changes are missing from the JSON structural difference.
+    pub truncated: bool,
+    pub(crate) similarity: f64,
+    // Whether some changes are left out by `DiffOptions::filter`, so that
+    // the values differ even without any change reported.
+    pub(crate) filtered: bool,
+    // The markers of the changes, unless they are the default ones.
+    pub(crate) markers: Option<DiffMarkers>,
+}
+
+/// Two arrays mapped to sequences of keys, so that they can be compared
+/// with a sequence matcher.
+///
+/// Scalars are keyed by their value, while every other item is keyed by
+/// a proxy shared with its best fuzzy match on the other side. The items
+/// are borrowed from the arrays, never copied.
+///
+/// Items sharing a key may differ, e.g. numbers equal within an epsilon,
+/// so the items are always found by their index in the arrays.
+pub(crate) struct ScalarizedArrays<'a> {
+    pub(crate) seq1: Vec<String>,
+    pub(crate) seq2: Vec<String>,
+    pub(crate) array1: &'a [Value],
+    pub(crate) array2: &'a [Value],
+    pub(crate) originals1: BTreeMap<String, &'a Value>,
+    pub(crate) originals2: BTreeMap<String, &'a Value>,
+    /// The scores of the items of the second array matched with an item of
+    /// the first one, keyed by their shared proxy.
+    pub(crate) match_scores: BTreeMap<String, f64>,
+}
+
+/// The old and new names of a renamed key, along with the difference of
+/// their values.
+type RenamedKey<'a> = (&'a str, &'a str, Option<Value>);
+
+/// An object of the first array which the items of the second one may be
+/// matched with.
+struct Candidate<'a> {
+    key: String,
+    /// Its index in the first array.
+    index: usize,
+    /// The position of its key among the keys of all the candidates, by
+    /// which the ties between the best matches are broken.
+    rank: usize,
+    value: &'a Value,
+}
+
+/// The objects of the first array, in the order of the array.
+type Candidates<'a> = [Candidate<'a>];
+
+/// An item of the first array paired with an item of the second one, along
+/// with the positions of their entries, when detecting the moved items.
+struct MovePair {
+    old_index: usize,
+    new_index: usize,
+    deleted: Option<usize>,
+    added: usize,
 }
 
+#[derive(Debug)]
 struct BestMatch {
     score: f64,
     key: String,
     index_distance: usize,
 }
 
-impl BestMatch {
-    fn new(score: f64, key: String, index_distance: usize) -> Self {
-        Self {
-            score,
-            key,
-            index_distance,
+impl fmt::Display for JsonDiff {
+    /// Formats the JSON structural difference as `diff_string` does, or as
+    /// an empty string if the two JSON files are identical.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(diff) = &self.diff {
+            let options = ContextOptions {
+                markers: self.markers().into_owned(),
+                ..ContextOptions::default()
+            };
+            colorize_into_with(diff, f, &options)?;
         }
+        Ok(())
+    }
+}
+
+impl PartialEq for JsonDiff {
+    /// Compares all the fields, the scores and the similarities exactly,
+    /// except that `NaN` is equal to itself, so that the differences wrapped
+    /// by [`JsonDiff::from_diff_value`] can be compared too.
+    #[allow(clippy::float_cmp)]
+    fn eq(&self, other: &Self) -> bool {
+        let floats_equal =
+            |float1: f64, float2: f64| float1 == float2 || (float1.is_nan() && float2.is_nan());
+        floats_equal(self.score, other.score)
+            && floats_equal(self.similarity, other.similarity)
+            && self.diff == other.diff
+            && self.truncated == other.truncated
+            && self.markers() == other.markers()
     }
 }
 
@@ -38,289 +148,1114 @@ impl JsonDiff {
     /// Finds the JSON structural difference of two JSON files.
     #[must_use]
     pub fn diff(json1: &Value, json2: &Value, keys_only: bool) -> Self {
-        Self::diff_with_score(json1, json2, keys_only)
+        Self::diff_with_options(json1, json2, &DiffOptions::new(keys_only))
+    }
+
+    /// Finds the JSON structural difference of two JSON files using
+    /// the given options.
+    ///
+    /// If a JSON file is nested deeper than [`DiffOptions::max_nesting`],
+    /// the JSON files are not compared, and the first one is reported as
+    /// replaced as a whole by the second one, unless they are equal.
+    /// Use [`JsonDiff::try_diff_with_options`] to get an error instead.
+    #[must_use]
+    pub fn diff_with_options(json1: &Value, json2: &Value, options: &DiffOptions) -> Self {
+        Self::try_diff_with_options(json1, json2, options)
+            .unwrap_or_else(|_| Self::replaced(json1, json2, options))
+    }
+
+    /// The difference of two JSON files which are not compared, since one
+    /// of them is nested deeper than [`DiffOptions::max_nesting`].
+    pub(crate) fn replaced(json1: &Value, json2: &Value, options: &DiffOptions) -> Self {
+        let json_diff = if json1 == json2 {
+            Self::identical(100.)
+        } else {
+            Self {
+                score: 0.,
+                diff: Some(options.markers.change(json1, json2)),
+                truncated: false,
+                filtered: false,
+                similarity: 0.,
+                markers: None,
+            }
+        };
+        json_diff.with_markers(options.markers.clone())
+    }
+
+    /// Finds the JSON structural difference of two JSON files.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DiffError::TooDeep`] if a JSON file is nested deeper than
+    /// the default [`DiffOptions::max_nesting`].
+    pub fn try_diff(json1: &Value, json2: &Value, keys_only: bool) -> Result<Self, DiffError> {
+        Self::try_diff_with_options(json1, json2, &DiffOptions::new(keys_only))
+    }
+
+    /// Finds the JSON structural difference of two JSON files using
+    /// the given options.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DiffError::TooDeep`] if a JSON file is nested deeper than
+    /// [`DiffOptions::max_nesting`].
+    pub fn try_diff_with_options(
+        json1: &Value,
+        json2: &Value,
+        options: &DiffOptions,
+    ) -> Result<Self, DiffError> {
+        options.check_nesting(json1)?;
+        options.check_nesting(json2)?;
+        let (json1, json2) = (options.normalize(json1), options.normalize(json2));
+        let budget = ChangeBudget::new(options.max_changes, options.filter);
+        let json_diff = Self::diff_with_budget(&json1, &json2, options, "", &budget)?;
+        Ok(json_diff.finish(&budget, options))
+    }
+
+    /// Finds the JSON structural difference of the values at the given JSON
+    /// Pointer in two JSON files, e.g. `/spec/containers`, using the given
+    /// options.
+    ///
+    /// The rest of the JSON files is not compared. The JSON Pointers of
+    /// [`DiffOptions::ignore_keys`] and [`DiffOptions::max_depth`] remain
+    /// relative to their roots.
+    ///
+    /// Returns `None` if the JSON Pointer resolves in neither JSON file.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DiffError::PointerNotFound`] if the JSON Pointer only
+    /// resolves in one of the JSON files, [`DiffError::TooDeep`] if a JSON
+    /// file is nested deeper than [`DiffOptions::max_nesting`].
+    pub fn diff_at(
+        json1: &Value,
+        json2: &Value,
+        pointer: &JsonPointer,
+        options: &DiffOptions,
+    ) -> Result<Option<Self>, DiffError> {
+        options.check_nesting(json1)?;
+        options.check_nesting(json2)?;
+        let (json1, json2) = (options.normalize(json1), options.normalize(json2));
+        let path = pointer.to_string();
+        match (json1.pointer(&path), json2.pointer(&path)) {
+            (Some(json1), Some(json2)) => {
+                let budget = ChangeBudget::new(options.max_changes, options.filter);
+                let json_diff = Self::diff_with_budget(json1, json2, options, &path, &budget)?;
+                Ok(Some(json_diff.finish(&budget, options)))
+            }
+            (None, None) => Ok(None),
+            (json1, _) => Err(DiffError::PointerNotFound {
+                pointer: pointer.clone(),
+                in_first: json1.is_none(),
+            }),
+        }
+    }
+
+    /// Reads and parses the JSON files at the given paths, then finds their
+    /// JSON structural difference using the given options.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`DiffError`] if a file cannot be read or parsed, or if it
+    /// is nested deeper than [`DiffOptions::max_nesting`].
+    #[cfg(feature = "std")]
+    pub fn diff_files(
+        path1: impl AsRef<Path>,
+        path2: impl AsRef<Path>,
+        options: &DiffOptions,
+    ) -> Result<Self, DiffError> {
+        let (_, json1) = Self::read_json_file(path1.as_ref())?;
+        let (_, json2) = Self::read_json_file(path2.as_ref())?;
+        Self::try_diff_with_options(&json1, &json2, options)
+    }
+
+    /// Returns the text of the JSON file at the given path and its value.
+    #[cfg(feature = "std")]
+    pub(crate) fn read_json_file(path: &Path) -> Result<(String, Value), DiffError> {
+        let text =
+            std::fs::read_to_string(path).map_err(|err| DiffError::Io(path.to_path_buf(), err))?;
+        let value =
+            serde_json::from_str(&text).map_err(|err| DiffError::Json(path.to_path_buf(), err))?;
+        Ok((text, value))
     }
 
     /// Finds the JSON structural difference of two JSON files and
     /// returns it as a formatted string.
     #[must_use]
     pub fn diff_string(json1: &Value, json2: &Value, keys_only: bool) -> Option<String> {
-        let Self { score: _, diff } = Self::diff(json1, json2, keys_only);
-        diff.map(|value| colorize_to_array(&value).join("\n") + "\n")
+        let json_diff = Self::diff(json1, json2, keys_only);
+        json_diff.has_changes().then(|| json_diff.to_string())
+    }
+
+    /// Finds the JSON structural difference of two JSON files and
+    /// returns its score along with its formatted string, see
+    /// [`JsonDiff::score`] and [`JsonDiff::diff_string`].
+    #[must_use]
+    pub fn diff_string_with_score(
+        json1: &Value,
+        json2: &Value,
+        keys_only: bool,
+    ) -> (f64, Option<String>) {
+        let json_diff = Self::diff(json1, json2, keys_only);
+        (
+            json_diff.score,
+            json_diff.has_changes().then(|| json_diff.to_string()),
+        )
+    }
+
+    /// Finds the JSON structural difference of two JSON files and
+    /// returns it as a formatted string, whose lines are joined according
+    /// to the given options.
+    #[must_use]
+    pub fn diff_string_with(
+        json1: &Value,
+        json2: &Value,
+        keys_only: bool,
+        options: &LineOptions,
+    ) -> Option<String> {
+        Self::diff_lines(json1, json2, keys_only).map(|lines| options.join(&lines))
+    }
+
+    /// Finds the JSON structural difference of two JSON files and
+    /// returns the lines of its formatted string, without line endings.
+    #[must_use]
+    pub fn diff_lines(json1: &Value, json2: &Value, keys_only: bool) -> Option<Vec<String>> {
+        Self::diff(json1, json2, keys_only)
+            .into_diff()
+            .map(|value| colorize_to_array(&value))
+    }
+
+    /// Finds the JSON structural difference of two JSON files and
+    /// returns it as a formatted string, colorized with ANSI escape
+    /// sequences if `is_color` is `true`.
+    #[cfg(feature = "colorize")]
+    #[must_use]
+    pub fn diff_string_colored(
+        json1: &Value,
+        json2: &Value,
+        keys_only: bool,
+        is_color: bool,
+    ) -> Option<String> {
+        Self::diff(json1, json2, keys_only)
+            .into_diff()
+            .map(|value| crate::colorize::colorize(&value, is_color))
+    }
+
+    /// Returns `true` if the two JSON files are different.
+    #[must_use]
+    pub fn has_changes(&self) -> bool {
+        self.diff.is_some()
+    }
+
+    /// Returns the JSON structural difference, consuming `self`.
+    ///
+    /// If `None`: the two JSON files are identical.
+    #[must_use]
+    pub fn into_diff(self) -> Option<Value> {
+        self.diff
+    }
+
+    /// Wraps a JSON structural difference found earlier, e.g. deserialized
+    /// from a file, so that it can be rendered and converted again.
+    ///
+    /// The JSON files are not known, so the score and the similarity are
+    /// `NaN`. A `null` difference means that they are identical.
+    #[must_use]
+    pub fn from_diff_value(diff: Value) -> Self {
+        Self {
+            score: f64::NAN,
+            diff: (!diff.is_null()).then_some(diff),
+            truncated: false,
+            filtered: false,
+            similarity: f64::NAN,
+            markers: None,
+        }
+    }
+
+    /// Sets the markers of the changes of the JSON structural difference,
+    /// e.g. of a difference found with custom [`DiffOptions::markers`] then
+    /// wrapped by [`JsonDiff::from_diff_value`].
+    ///
+    /// The markers are kept by the differences found with
+    /// [`JsonDiff::diff_with_options`], and used to format, parse and
+    /// convert them.
+    #[must_use]
+    pub fn with_markers(mut self, markers: DiffMarkers) -> Self {
+        self.markers = (markers != DiffMarkers::default()).then_some(markers);
+        self
+    }
+
+    /// Returns the markers of the changes of the JSON structural difference.
+    pub(crate) fn markers(&self) -> Cow<'_, DiffMarkers> {
+        self.markers
+            .as_ref()
+            .map_or_else(|| Cow::Owned(DiffMarkers::default()), Cow::Borrowed)
+    }
+
+    /// Returns a measure of how similar the two JSON files are, normalized
+    /// between `0.` and `1.`.
+    ///
+    /// If `0.`: the two JSON files are entirely different one from the other.
+    /// If `1.`: the two JSON files are identical.
+    ///
+    /// Two scalars are either identical or entirely different. The
+    /// similarity of two objects is the sum of the similarities of their
+    /// shared keys divided by the number of their distinct keys, while the
+    /// similarity of two arrays is the sum of the similarities of their
+    /// matched items divided by the number of matched, added and deleted
+    /// items.
+    #[must_use]
+    pub fn similarity(&self) -> f64 {
+        self.similarity
+    }
+
+    /// Completes a JSON structural difference found from the roots.
+    fn finish(mut self, budget: &ChangeBudget, options: &DiffOptions) -> Self {
+        self.truncated = budget.is_truncated();
+        self = self.with_markers(options.markers.clone());
+        if options.sort_keys {
+            if let Some(diff) = &mut self.diff {
+                Self::sort_keys(diff);
+            }
+        }
+        self
+    }
+
+    /// Sorts the keys of every object of `value`, whatever the order kept by
+    /// `Map`.
+    fn sort_keys(value: &mut Value) {
+        match value {
+            Value::Object(obj) => {
+                let mut entries: Vec<(String, Value)> = mem::take(obj).into_iter().collect();
+                entries.sort_by(|(key1, _), (key2, _)| key1.cmp(key2));
+                for (key, mut value) in entries {
+                    Self::sort_keys(&mut value);
+                    obj.insert(key, value);
+                }
+            }
+            Value::Array(items) => items.iter_mut().for_each(Self::sort_keys),
+            _ => {}
+        }
+    }
+
+    pub(crate) fn identical(score: f64) -> Self {
+        Self {
+            score,
+            diff: None,
+            truncated: false,
+            filtered: false,
+            similarity: 1.,
+            markers: None,
+        }
+    }
+
+    /// The difference of two values whose changes are all left out by
+    /// [`DiffOptions::filter`].
+    pub(crate) fn filtered(score: f64, similarity: f64) -> Self {
+        Self {
+            score,
+            diff: None,
+            truncated: false,
+            similarity,
+            filtered: true,
+            markers: None,
+        }
     }
 
-    fn object_diff(obj1: &Map<String, Value>, obj2: &Map<String, Value>, keys_only: bool) -> Self {
+    pub(crate) fn child_path(path: &str, key: &str) -> String {
+        format!("{path}/{}", pointer::escape(key))
+    }
+
+    #[allow(clippy::too_many_lines)]
+    fn object_diff(
+        obj1: &Map<String, Value>,
+        obj2: &Map<String, Value>,
+        options: &DiffOptions,
+        path: &str,
+        budget: &ChangeBudget,
+    ) -> Result<Self, DiffError> {
         let mut result = Map::new();
         let mut score = 0.;
+        let mut compared_keys = 0;
+        let mut added_keys = 0;
+        let mut similarity = 0.;
+        let mut deleted = Vec::new();
+        let mut added = Vec::new();
+        let mut unchanged = Vec::new();
+        let mut filtered = false;
 
-        for (key, value1) in obj1 {
-            if !obj2.contains_key(key) {
-                let key_deleted = format!("{key}__deleted");
-                result.insert(key_deleted, value1.clone());
-                score -= 30.;
+        // The deleted and changed keys follow the order of the first object,
+        // then the added keys follow the order of the second one. This is
+        // only visible when `Map` preserves the insertion order.
+        let shared_keys: Vec<(&String, &Value, String)> = obj1
+            .iter()
+            .map(|(key, value1)| (key, value1, Self::child_path(path, key)))
+            .filter(|(key, value1, key_path)| {
+                !options.is_ignored(key, key_path)
+                    && (!options.values_only
+                        || Self::counterpart(obj2, key, value1, options).is_some())
+            })
+            .collect();
+        // The values are compared in order, possibly in parallel, then their
+        // scores are accumulated in order, as floating-point sums depend on it.
+        // The changes kept within a budget would depend on the scheduling.
+        let parallel = options.parallel && options.max_changes.is_none();
+        let subdiffs = map_in_order(&shared_keys, parallel, |(key, value1, key_path)| {
+            Self::counterpart(obj2, key, value1, options)
+                .map(|value2| Self::diff_with_budget(value1, value2, options, key_path, budget))
+                .transpose()
+        });
+        for ((key, value1, _), subdiff) in shared_keys.iter().zip(subdiffs) {
+            compared_keys += 1;
+            if let Some(Self {
+                score: subscore,
+                diff: change,
+                similarity: subsimilarity,
+                filtered: subfiltered,
+                ..
+            }) = subdiff?
+            {
+                score += options.key_match_bonus;
+                if let Some(change) = change {
+                    result.insert(options.markers.shared_key(key).into_owned(), change);
+                } else if subfiltered {
+                    filtered = true;
+                } else if options.show_context_keys {
+                    unchanged.push((key, *value1));
+                }
+                score += (subscore / options.value_score_divisor)
+                    .clamp(options.value_score_min, options.value_score_max);
+                similarity += subsimilarity;
+            } else {
+                // The keys left out by the filter may still be renamed.
+                let kept = budget.keeps(ChangeKind::Removed);
+                if !kept || budget.spend(1) {
+                    if kept {
+                        result.insert(options.markers.deleted_key(key), (*value1).clone());
+                    } else {
+                        filtered = true;
+                    }
+                    deleted.push((key.as_str(), *value1));
+                }
+                score -= options.key_missing_penalty;
             }
         }
 
         for (key, value2) in obj2 {
-            if !obj1.contains_key(key) {
-                let key_added = format!("{key}__added");
-                result.insert(key_added, value2.clone());
-                score -= 30.;
+            if Self::matching_key(obj1, key, options).is_some()
+                || options.is_ignored(key, &Self::child_path(path, key))
+            {
+                continue;
+            }
+            if options.values_only && Self::counterpart(obj1, key, value2, options).is_none() {
+                continue;
+            }
+            if Self::counterpart(obj1, key, value2, options).is_some() {
+                // A value equal to a missing key counts as an identical
+                // shared key, as it does in the first object.
+                compared_keys += 1;
+                score += options.key_match_bonus
+                    + (100. / options.value_score_divisor)
+                        .clamp(options.value_score_min, options.value_score_max);
+                similarity += 1.;
+            } else {
+                let kept = budget.keeps(ChangeKind::Added);
+                if !kept || budget.spend(1) {
+                    if kept {
+                        result.insert(options.markers.added_key(key), value2.clone());
+                    } else {
+                        filtered = true;
+                    }
+                    added.push((key.as_str(), value2));
+                }
+                score -= options.key_missing_penalty;
+                added_keys += 1;
             }
         }
 
-        for (key, value1) in obj1 {
-            if let Some(value2) = obj2.get(key) {
-                score += 20.;
-                let Self {
-                    score: subscore,
-                    diff: change,
-                } = Self::diff_with_score(value1, value2, keys_only);
-                if let Some(change) = change {
-                    result.insert(key.clone(), change);
+        if options.detect_renames {
+            for (old_key, new_key, change) in Self::renamed_keys(&deleted, &added, options, path)? {
+                result.remove(&options.markers.deleted_key(old_key));
+                result.remove(&options.markers.added_key(new_key));
+                // The renamed keys are already spent for, unless both are
+                // left out by the filter.
+                let spent = budget.keeps(ChangeKind::Removed) || budget.keeps(ChangeKind::Added);
+                if budget.keeps(ChangeKind::Renamed) && (spent || budget.spend(1)) {
+                    result.insert(
+                        options.markers.renamed_key(old_key, new_key),
+                        change.unwrap_or(Value::Null),
+                    );
                 }
-                score += (subscore / 5.).clamp(-10., 20.);
             }
         }
 
-        if result.is_empty() {
-            #[allow(clippy::cast_precision_loss)]
-            Self {
-                score: 100. * (obj1.len() as f64).max(0.5),
-                diff: None,
-            }
+        // Shared keys count as much as they are similar, while added and
+        // deleted keys do not count at all.
+        let similarity = similarity / f64::from(compared_keys + added_keys);
+        if result.is_empty() && filtered {
+            Ok(Self::filtered(score.max(0.), similarity))
+        } else if result.is_empty() {
+            Ok(Self::identical(100. * f64::from(compared_keys).max(0.5)))
         } else {
+            // The unchanged keys are only shown along with some changes.
+            for (key, value1) in unchanged {
+                result.insert(options.markers.unchanged_key(key), value1.clone());
+            }
             let output = json!(result);
-            Self {
+            Ok(Self {
                 score: score.max(0.),
                 diff: Some(output),
+                truncated: false,
+                similarity,
+                filtered: false,
+                markers: None,
+            })
+        }
+    }
+
+    /// Returns the value associated to `key`, or to a key equal to `key`
+    /// when ignoring the case if requested.
+    pub(crate) fn matching_key<'a>(
+        obj: &'a Map<String, Value>,
+        key: &str,
+        options: &DiffOptions,
+    ) -> Option<&'a Value> {
+        obj.get(key).or_else(|| {
+            if options.case_insensitive_keys {
+                let key = key.to_lowercase();
+                obj.iter()
+                    .find_map(|(other, value)| (other.to_lowercase() == key).then_some(value))
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Pairs the deleted keys with the added keys having an equal value,
+    /// then with those having the most similar object or array, along with
+    /// the difference of their values.
+    fn renamed_keys<'a>(
+        deleted: &[(&'a str, &Value)],
+        added: &[(&'a str, &Value)],
+        options: &DiffOptions,
+        path: &str,
+    ) -> Result<Vec<RenamedKey<'a>>, DiffError> {
+        let mut renames = Vec::new();
+        let mut paired_deleted = vec![false; deleted.len()];
+        let mut paired_added = vec![false; added.len()];
+        let can_rename = |old_key, new_key| options.markers.can_rename(old_key, new_key);
+
+        for (index1, (old_key, value1)) in deleted.iter().enumerate() {
+            let key_path = Self::child_path(path, old_key);
+            for (index2, (new_key, value2)) in added.iter().enumerate() {
+                if !paired_added[index2]
+                    && can_rename(old_key, new_key)
+                    && Self::diff_with_score(value1, value2, options, &key_path)?
+                        .diff
+                        .is_none()
+                {
+                    paired_deleted[index1] = true;
+                    paired_added[index2] = true;
+                    renames.push((*old_key, *new_key, None));
+                    break;
+                }
             }
         }
+
+        for (index1, (old_key, value1)) in deleted.iter().enumerate() {
+            if paired_deleted[index1] {
+                continue;
+            }
+            let key_path = Self::child_path(path, old_key);
+            let mut best: Option<(usize, Self)> = None;
+            for (index2, (new_key, value2)) in added.iter().enumerate() {
+                if paired_added[index2]
+                    || !can_rename(old_key, new_key)
+                    || !Self::are_same_containers(value1, value2)
+                {
+                    continue;
+                }
+                let json_diff = Self::diff_with_score(value1, value2, options, &key_path)?;
+                if json_diff.score > options.array_match_threshold
+                    && best
+                        .as_ref()
+                        .map_or(true, |(_, best)| json_diff.score > best.score)
+                {
+                    best = Some((index2, json_diff));
+                }
+            }
+            if let Some((index2, json_diff)) = best {
+                paired_added[index2] = true;
+                renames.push((*old_key, added[index2].0, json_diff.diff));
+            }
+        }
+
+        Ok(renames)
+    }
+
+    /// Returns the value of `obj` compared with `value`, the value of `key`
+    /// in the other object. A missing key counts as `value` itself if
+    /// `value` is `null` and [`DiffOptions::null_equals_missing`] is set, or
+    /// if `value` is an empty array or object and
+    /// [`DiffOptions::empty_equals_missing`] is set.
+    pub(crate) fn counterpart<'a>(
+        obj: &'a Map<String, Value>,
+        key: &str,
+        value: &'a Value,
+        options: &DiffOptions,
+    ) -> Option<&'a Value> {
+        Self::matching_key(obj, key, options).or_else(|| {
+            let is_missing = match value {
+                Value::Null => options.null_equals_missing,
+                Value::Array(array) => options.empty_equals_missing && array.is_empty(),
+                Value::Object(obj) => options.empty_equals_missing && obj.is_empty(),
+                _ => false,
+            };
+            is_missing.then_some(value)
+        })
     }
 
+    /// Returns `true` if both values have the same JSON type.
     fn check_type(item1: &Value, item2: &Value) -> bool {
-        item1.is_null() == item2.is_null()
-            || item1.is_boolean() == item2.is_boolean()
-            || item1.is_number() == item2.is_number()
-            || item1.is_string() == item2.is_string()
-            || item1.is_array() == item2.is_array()
-            || item1.is_object() == item2.is_object()
+        mem::discriminant(item1) == mem::discriminant(item2)
     }
 
-    fn find_matching_object(
+    /// Returns the score of `item` against every object of
+    /// `fuzzy_originals`, in order.
+    ///
+    /// The items are compared at the path of the object, which is the one
+    /// their difference is reported at once they are matched.
+    fn match_scores(
         item: &Value,
+        fuzzy_originals: &Candidates<'_>,
+        options: &DiffOptions,
+        path: &str,
+    ) -> Result<Vec<Option<f64>>, DiffError> {
+        map_in_order(
+            fuzzy_originals,
+            options.parallel,
+            |Candidate {
+                 index,
+                 value: candidate,
+                 ..
+             }| {
+                if Self::check_type(item, candidate) {
+                    let Self {
+                        score, similarity, ..
+                    } = Self::diff_with_score(
+                        item,
+                        candidate,
+                        options,
+                        &format!("{path}/{index}"),
+                    )?;
+                    // The score of arrays grows with their length, unlike the
+                    // percentage of their similarity.
+                    Ok(Some(if item.is_array() {
+                        similarity * 100.
+                    } else {
+                        score
+                    }))
+                } else {
+                    Ok(None)
+                }
+            },
+        )
+        .into_iter()
+        .collect()
+    }
+
+    /// Returns the best match of the item at `index` among the objects of
+    /// `fuzzy_originals`, given its scores against them.
+    ///
+    /// Among the objects with the best score, the one whose rank is the
+    /// closest after `index` is matched.
+    fn find_matching_object(
+        scores: &[Option<f64>],
         index: usize,
-        fuzzy_originals: &Map<String, Value>,
+        fuzzy_originals: &Candidates<'_>,
     ) -> Option<BestMatch> {
         let mut best_match: Option<BestMatch> = None;
 
-        for (match_index, (key, candidate)) in fuzzy_originals.into_iter().enumerate() {
-            if key != "__next" {
-                let index_distance = (match_index).wrapping_sub(index);
-                if Self::check_type(item, candidate) {
-                    let Self { score, diff: _ } = Self::diff(item, candidate, false);
-                    if best_match.as_ref().map_or(true, |v| score > v.score)
-                        || (best_match
-                            .as_ref()
-                            .map_or(true, |v| (score - v.score).abs() < f64::EPSILON)
-                            && best_match
-                                .as_ref()
-                                .map_or(true, |v| index_distance < v.index_distance))
-                    {
-                        best_match = Some(BestMatch::new(score, key.clone(), index_distance));
-                    }
-                }
+        for (candidate, score) in fuzzy_originals.iter().zip(scores) {
+            let Some(score) = *score else {
+                continue;
+            };
+            let index_distance = candidate.rank.wrapping_sub(index);
+            if best_match.as_ref().map_or(true, |v| score > v.score)
+                || best_match.as_ref().map_or(true, |v| {
+                    (score - v.score).abs() < f64::EPSILON && index_distance < v.index_distance
+                })
+            {
+                best_match = Some(BestMatch {
+                    score,
+                    key: candidate.key.clone(),
+                    index_distance,
+                });
             }
         }
 
         best_match
     }
 
-    fn scalarize(
-        array: &[Value],
-        scalar_values: &mut Map<String, Value>,
-        originals: &mut Map<String, Value>,
-        fuzzy_originals: Option<&Map<String, Value>>,
-    ) -> Vec<String> {
+    fn find_matching_scalar(
+        item: &Value,
+        fuzzy_scalars: &BTreeMap<String, &Value>,
+        options: &DiffOptions,
+    ) -> Option<String> {
+        if options.epsilon.is_none()
+            && !options.numbers_by_value
+            && !options.case_insensitive
+            && !options.coerce_numeric_strings
+            && options.scalar_comparators.is_empty()
+        {
+            return None;
+        }
+        fuzzy_scalars.iter().find_map(|(key, candidate)| {
+            Self::scalars_equal(item, candidate, options).then(|| key.clone())
+        })
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn scalarize<'a>(
+        array: &'a [Value],
+        scalar_values: &mut BTreeMap<String, &'a Value>,
+        originals: &mut BTreeMap<String, &'a Value>,
+        next_proxy: &mut usize,
+        match_scores: &mut BTreeMap<String, f64>,
+        fuzzy_originals: Option<&Candidates<'a>>,
+        fuzzy_scalars: Option<&BTreeMap<String, &'a Value>>,
+        options: &DiffOptions,
+        path: &str,
+    ) -> Result<Vec<String>, DiffError> {
         let mut output_array: Vec<String> = Vec::new();
+        // Equal items have the same scores, so they are computed once.
+        let mut scores_by_item: BTreeMap<String, Vec<Option<f64>>> = BTreeMap::new();
         for (index, item) in array.iter().enumerate() {
-            let mut value = if let Value::Object(_) = item {
+            let is_fuzzy = match item {
+                Value::Object(_) => true,
+                Value::Array(_) => options.fuzzy_match_arrays,
+                _ => false,
+            };
+            let mut value = if is_fuzzy {
                 None
             } else {
-                let key = item.to_string();
-                scalar_values.insert(key.clone(), item.clone());
+                let key = fuzzy_scalars
+                    .and_then(|fuzzy_scalars| {
+                        Self::find_matching_scalar(item, fuzzy_scalars, options)
+                    })
+                    .unwrap_or_else(|| item.to_string());
+                scalar_values.insert(key.clone(), item);
                 Some(key)
             };
 
-            if let Some(fuzzy_originals) = fuzzy_originals {
-                if let Some(best_match) = Self::find_matching_object(item, index, fuzzy_originals) {
-                    if best_match.score > 40. && !originals.contains_key(&best_match.key) {
-                        originals.insert(best_match.key.clone(), item.to_owned());
+            let identity = options.array_identity(item);
+            if let (Some(fuzzy_originals), Some(identity)) = (fuzzy_originals, &identity) {
+                // An identified object is only matched with an object of the
+                // same identity, whatever their score.
+                value = fuzzy_originals
+                    .iter()
+                    .find(|candidate| {
+                        !originals.contains_key(&candidate.key)
+                            && options.array_identity(candidate.value).as_ref() == Some(identity)
+                    })
+                    .map(|candidate| candidate.key.clone());
+                if let Some(key) = &value {
+                    originals.insert(key.clone(), item);
+                }
+            } else if let (Some(fuzzy_originals), true) = (fuzzy_originals, options.strict_arrays) {
+                // Without fuzzy matching, an item is only matched with the
+                // first equal item which is not already matched.
+                for candidate in fuzzy_originals {
+                    if !originals.contains_key(&candidate.key)
+                        && Self::check_type(item, candidate.value)
+                        && Self::diff_with_score(
+                            candidate.value,
+                            item,
+                            options,
+                            &format!("{path}/{}", candidate.index),
+                        )?
+                        .diff
+                        .is_none()
+                    {
+                        originals.insert(candidate.key.clone(), item);
+                        value = Some(candidate.key.clone());
+                        break;
+                    }
+                }
+            } else if let Some(fuzzy_originals) = fuzzy_originals {
+                let scores = match scores_by_item.entry(item.to_string()) {
+                    Entry::Occupied(entry) => entry.into_mut(),
+                    Entry::Vacant(entry) => {
+                        entry.insert(Self::match_scores(item, fuzzy_originals, options, path)?)
+                    }
+                };
+                if let Some(best_match) = Self::find_matching_object(scores, index, fuzzy_originals)
+                {
+                    // An object is matched at most once.
+                    if best_match.score > options.array_match_threshold
+                        && !originals.contains_key(&best_match.key)
+                    {
+                        originals.insert(best_match.key.clone(), item);
+                        match_scores.insert(best_match.key.clone(), best_match.score);
                         value = Some(best_match.key);
                     }
                 }
             }
 
-            if value.is_none() {
-                let original = originals.get_mut("__next").unwrap();
-                let proxy = "__$!SCALAR".to_owned() + &(original).to_string();
-
-                *original = json!(original.as_u64().unwrap() + 1);
-                originals.insert(proxy.clone(), item.to_owned());
-                value = Some(proxy);
-            }
+            // The keys of scalars are their JSON text, which never starts
+            // with `_`, so they cannot collide with the proxies.
+            let final_value = if let Some(value) = value {
+                value
+            } else {
+                let proxy = format!("__$!SCALAR{next_proxy}");
+                *next_proxy += 1;
+                originals.insert(proxy.clone(), item);
+                proxy
+            };
 
-            let final_value = value.unwrap();
             output_array.push(final_value);
         }
-        output_array
+        Ok(output_array)
     }
 
-    fn is_scalarized(key: &str, originals: &Map<String, Value>) -> bool {
+    fn is_scalarized(key: &str, originals: &BTreeMap<String, &Value>) -> bool {
         originals.contains_key(key)
     }
 
-    fn get_scalar(key: &str, scalar_values: &Map<String, Value>) -> Value {
-        scalar_values.get(key).unwrap().clone()
+    pub(crate) fn scalarize_arrays<'a>(
+        array1: &'a [Value],
+        array2: &'a [Value],
+        options: &DiffOptions,
+        path: &str,
+    ) -> Result<ScalarizedArrays<'a>, DiffError> {
+        // Fuzzy matching always compares values, even in keys-only mode.
+        let match_options = if options.ignores_values() {
+            Cow::Owned(DiffOptions {
+                keys_only: false,
+                types_only: false,
+                ..options.clone()
+            })
+        } else {
+            Cow::Borrowed(options)
+        };
+
+        // The proxies are numbered across both arrays.
+        let mut next_proxy = 1;
+
+        let mut originals1 = BTreeMap::new();
+        let mut scalar_values1 = BTreeMap::new();
+        let seq1: Vec<String> = Self::scalarize(
+            array1,
+            &mut scalar_values1,
+            &mut originals1,
+            &mut next_proxy,
+            &mut BTreeMap::new(),
+            None,
+            None,
+            &match_options,
+            path,
+        )?;
+
+        // The keys are ranked in the order of `originals1`.
+        let ranks: BTreeMap<&String, usize> = originals1
+            .keys()
+            .enumerate()
+            .map(|(rank, key)| (key, rank))
+            .collect();
+        let candidates1: Vec<Candidate<'_>> = seq1
+            .iter()
+            .enumerate()
+            .filter_map(|(index, key)| {
+                let value = originals1.get(key)?;
+                Some(Candidate {
+                    key: key.clone(),
+                    index,
+                    rank: ranks[key],
+                    value,
+                })
+            })
+            .collect();
+
+        let mut originals2 = BTreeMap::new();
+        let mut match_scores = BTreeMap::new();
+        let seq2: Vec<String> = Self::scalarize(
+            array2,
+            &mut BTreeMap::new(),
+            &mut originals2,
+            &mut next_proxy,
+            &mut match_scores,
+            Some(&candidates1),
+            Some(&scalar_values1),
+            &match_options,
+            path,
+        )?;
+
+        Ok(ScalarizedArrays {
+            seq1,
+            seq2,
+            array1,
+            array2,
+            originals1,
+            originals2,
+            match_scores,
+        })
+    }
+
+    /// Builds the change of an array item, appending its indices if
+    /// requested.
+    pub(crate) fn array_entry(
+        op: char,
+        value: Value,
+        indices: &[usize],
+        options: &DiffOptions,
+    ) -> Value {
+        let mut entry = vec![json!(op), value];
+        if options.include_indices {
+            entry.extend(indices.iter().map(|index| json!(index)));
+        }
+        Value::Array(entry)
     }
 
-    fn descalarize(
-        key: &str,
-        scalar_values: &Map<String, Value>,
-        originals: &Map<String, Value>,
+    /// Builds the change of two matched array items, appending their indices
+    /// and their match score if requested.
+    pub(crate) fn change_entry(
+        change: Value,
+        indices: &[usize; 2],
+        score: Option<f64>,
+        options: &DiffOptions,
     ) -> Value {
-        if let Some(val) = originals.get(key) {
-            val.clone()
-        } else {
-            Self::get_scalar(key, scalar_values)
+        let mut entry = Self::array_entry('~', change, indices, options);
+        if let (true, Some(score), Value::Array(entry)) =
+            (options.include_match_scores, score, &mut entry)
+        {
+            entry.push(options.markers.score(score));
         }
+        entry
     }
 
-    #[allow(clippy::too_many_lines)]
-    fn array_diff(array1: &[Value], array2: &[Value], keys_only: bool) -> Self {
-        let mut originals1 = Map::new();
-        let mut scalar_values1 = Map::new();
-        originals1.insert("__next".to_owned(), json!(1));
-        let seq1: Vec<String> = Self::scalarize(array1, &mut scalar_values1, &mut originals1, None);
-
-        let mut originals2 = Map::new();
-        let mut scalar_values2 = Map::new();
-        let originals1_value = originals1.get("__next").unwrap();
-        originals2.insert("__next".to_owned(), json!(originals1_value));
-        let seq2: Vec<String> = Self::scalarize(
+    fn array_diff(
+        array1: &[Value],
+        array2: &[Value],
+        options: &DiffOptions,
+        path: &str,
+        budget: &ChangeBudget,
+    ) -> Result<Self, DiffError> {
+        let scalarized = Self::scalarize_arrays(array1, array2, options, path)?;
+        Self::scalarized_array_diff(scalarized, options, path, budget)
+    }
+
+    #[allow(clippy::too_many_lines, clippy::cast_precision_loss)]
+    fn scalarized_array_diff(
+        scalarized: ScalarizedArrays<'_>,
+        options: &DiffOptions,
+        path: &str,
+        budget: &ChangeBudget,
+    ) -> Result<Self, DiffError> {
+        let ignores_values = options.ignores_values();
+        let ScalarizedArrays {
+            seq1,
+            seq2,
+            array1,
             array2,
-            &mut scalar_values2,
-            &mut originals2,
-            Some(&originals1),
-        );
+            originals1,
+            originals2,
+            match_scores,
+        } = scalarized;
 
         let opcodes = SequenceMatcher::new(&seq1, &seq2).get_opcodes();
 
         let mut result: Vec<Value> = Vec::new();
         let mut score: f64 = 0.;
         let mut all_equal = true;
+        let mut similarity = 0.;
+        let mut entries: usize = 0;
+        let mut filtered = false;
+
+        let entry = |op: char, value: Value, indices: &[usize]| {
+            Self::array_entry(op, value, indices, options)
+        };
+        // The deleted and added items may turn out to be moved, so they are
+        // only left out by the filter once the moves are detected.
+        let spend_item = |kind, filtered: &mut bool| {
+            if options.detect_moves {
+                budget.spend(1)
+            } else {
+                budget.spend_on(kind, filtered)
+            }
+        };
 
         for opcode in &opcodes {
-            if !(opcode.tag == "equal" || (keys_only && opcode.tag == "replace")) {
+            if !(opcode.tag == "equal" || (ignores_values && opcode.tag == "replace")) {
                 all_equal = false;
             }
 
-            match opcode.tag.as_str() {
+            match opcode.tag {
                 "equal" => {
-                    for key in seq1.iter().take(opcode.first_end).skip(opcode.first_start) {
+                    for index in opcode.first_start..opcode.first_end {
+                        let key = &seq1[index];
+                        let new_index = opcode.second_start + index - opcode.first_start;
                         let is_scalarized1 = Self::is_scalarized(key, &originals1);
-                        assert!(!is_scalarized1 || (Self::is_scalarized(key, &originals2)),
-                            "Internal bug: the items associated to the key {key} are different in the two dictionaries"
-                        );
-                        if is_scalarized1 {
-                            let item1 = Self::descalarize(key, &scalar_values1, &originals1);
-                            let item2 = Self::descalarize(key, &scalar_values2, &originals2);
+                        if is_scalarized1 && !Self::is_scalarized(key, &originals2) {
+                            // The items are not known to be equal, so they
+                            // are conservatively reported as replaced.
+                            let (item1, item2) = (&array1[index], &array2[new_index]);
+                            if spend_item(ChangeKind::Removed, &mut filtered) {
+                                result.push(entry('-', item1.clone(), &[index]));
+                            }
+                            if spend_item(ChangeKind::Added, &mut filtered) {
+                                result.push(entry('+', item2.clone(), &[new_index]));
+                            }
+                            all_equal = false;
+                            score -= 10.;
+                            entries += 2;
+                        } else if is_scalarized1 {
+                            let (item1, item2) = (&array1[index], &array2[new_index]);
                             let Self {
-                                score: _,
                                 diff: change,
-                            } = Self::diff(&item1, &item2, keys_only);
+                                similarity: subsimilarity,
+                                filtered: subfiltered,
+                                ..
+                            } = Self::diff_with_budget(
+                                item1,
+                                item2,
+                                options,
+                                &format!("{path}/{index}"),
+                                budget,
+                            )?;
                             if let Some(change) = change {
-                                result.push(json!([json!('~'), change]));
+                                let score = match_scores.get(key).copied();
+                                result.push(Self::change_entry(
+                                    change,
+                                    &[index, new_index],
+                                    score,
+                                    options,
+                                ));
                                 all_equal = false;
                             } else {
+                                filtered |= subfiltered;
                                 result.push(json!([json!(' ')]));
                             }
+                            similarity += subsimilarity;
+                            score += 10.;
+                            entries += 1;
                         } else {
-                            result
-                                .push(json!([json!(' '), Self::get_scalar(key, &scalar_values1)]));
+                            result.push(json!([json!(' '), array1[index]]));
+                            similarity += 1.;
+                            score += 10.;
+                            entries += 1;
                         }
-                        score += 10.;
                     }
                 }
                 "delete" => {
-                    for key in seq1.iter().take(opcode.first_end).skip(opcode.first_start) {
-                        result.push(json!([
-                            json!('-'),
-                            Self::descalarize(key, &scalar_values1, &originals1)
-                        ]));
+                    for (index, item) in array1
+                        .iter()
+                        .enumerate()
+                        .take(opcode.first_end)
+                        .skip(opcode.first_start)
+                    {
+                        if spend_item(ChangeKind::Removed, &mut filtered) {
+                            result.push(entry('-', item.clone(), &[index]));
+                        }
                         score -= 5.;
+                        entries += 1;
                     }
                 }
                 "insert" => {
-                    for key in seq2
+                    for (index, item) in array2
                         .iter()
+                        .enumerate()
                         .take(opcode.second_end)
                         .skip(opcode.second_start)
                     {
-                        result.push(json!([
-                            json!('+'),
-                            Self::descalarize(key, &scalar_values2, &originals2)
-                        ]));
+                        if spend_item(ChangeKind::Added, &mut filtered) {
+                            result.push(entry('+', item.clone(), &[index]));
+                        }
                         score -= 5.;
+                        entries += 1;
                     }
                 }
                 "replace" => {
-                    if keys_only {
-                        for (key1, key2) in seq1
-                            .iter()
-                            .take(opcode.first_end)
-                            .skip(opcode.first_start)
-                            .zip(
-                                seq2.iter()
-                                    .take(
-                                        opcode.first_end - opcode.first_start + opcode.second_start,
-                                    )
-                                    .skip(opcode.second_start),
-                            )
+                    if ignores_values {
+                        // The items of the two spans are paired, and the
+                        // items of the longest one beyond the other are
+                        // deleted or added.
+                        let overlap = (opcode.first_end - opcode.first_start)
+                            .min(opcode.second_end - opcode.second_start);
+                        entries += overlap;
+                        for (index, new_index) in (opcode.first_start..opcode.first_start + overlap)
+                            .zip(opcode.second_start..)
                         {
                             let Self {
-                                score: _,
                                 diff: change,
-                            } = Self::diff(
-                                &Self::descalarize(key1, &scalar_values1, &originals1),
-                                &Self::descalarize(key2, &scalar_values2, &originals2),
-                                keys_only,
-                            );
+                                similarity: subsimilarity,
+                                filtered: subfiltered,
+                                ..
+                            } = Self::diff_with_budget(
+                                &array1[index],
+                                &array2[new_index],
+                                options,
+                                &format!("{path}/{index}"),
+                                budget,
+                            )?;
                             if let Some(change) = change {
-                                result.push(json!([json!('~'), change]));
+                                result.push(entry('~', change, &[index, new_index]));
                                 all_equal = false;
                             } else {
+                                filtered |= subfiltered;
                                 result.push(json!(' '));
                             }
+                            similarity += subsimilarity;
+                        }
+                        for (index, item) in array1
+                            .iter()
+                            .enumerate()
+                            .take(opcode.first_end)
+                            .skip(opcode.first_start + overlap)
+                        {
+                            if spend_item(ChangeKind::Removed, &mut filtered) {
+                                result.push(entry('-', item.clone(), &[index]));
+                            }
+                            all_equal = false;
+                            score -= 5.;
+                            entries += 1;
+                        }
+                        for (index, item) in array2
+                            .iter()
+                            .enumerate()
+                            .take(opcode.second_end)
+                            .skip(opcode.second_start + overlap)
+                        {
+                            if spend_item(ChangeKind::Added, &mut filtered) {
+                                result.push(entry('+', item.clone(), &[index]));
+                            }
+                            all_equal = false;
+                            score -= 5.;
+                            entries += 1;
                         }
                     } else {
-                        for key in seq1.iter().take(opcode.first_end).skip(opcode.first_start) {
-                            result.push(json!([
-                                json!('-'),
-                                Self::descalarize(key, &scalar_values1, &originals1)
-                            ]));
+                        for (index, item) in array1
+                            .iter()
+                            .enumerate()
+                            .take(opcode.first_end)
+                            .skip(opcode.first_start)
+                        {
+                            if spend_item(ChangeKind::Removed, &mut filtered) {
+                                result.push(entry('-', item.clone(), &[index]));
+                            }
                             score -= 5.;
+                            entries += 1;
                         }
-                        for key in seq2
+                        for (index, item) in array2
                             .iter()
+                            .enumerate()
                             .take(opcode.second_end)
                             .skip(opcode.second_start)
                         {
-                            result.push(json!([
-                                json!('+'),
-                                Self::descalarize(key, &scalar_values2, &originals2)
-                            ]));
+                            if spend_item(ChangeKind::Added, &mut filtered) {
+                                result.push(entry('+', item.clone(), &[index]));
+                            }
                             score -= 5.;
+                            entries += 1;
                         }
                     }
                 }
@@ -328,50 +1263,576 @@ impl JsonDiff {
             }
         }
 
-        if all_equal || opcodes.is_empty() {
-            Self {
-                score: 100.,
-                diff: None,
-            }
-        } else {
-            Self {
+        if options.detect_moves {
+            result = Self::detect_moves(result, options);
+            result.retain(|entry| {
+                let kind = match entry.get(0).and_then(Value::as_str) {
+                    Some("-") => ChangeKind::Removed,
+                    Some("+") => ChangeKind::Added,
+                    Some(">") => ChangeKind::Moved,
+                    _ => return true,
+                };
+                filtered |= !budget.keeps(kind);
+                budget.keeps(kind)
+            });
+        }
+        // Matched items count as much as they are similar, while added and
+        // deleted items do not count at all.
+        let similarity = similarity / entries as f64;
+        if (all_equal && !filtered) || opcodes.is_empty() {
+            Ok(Self::identical(100.))
+        } else if Self::has_changed_entries(&result) {
+            Ok(Self {
                 score: score.max(0.),
                 diff: Some(json!(result)),
-            }
+                truncated: false,
+                filtered: false,
+                similarity,
+                markers: None,
+            })
+        } else if filtered {
+            Ok(Self::filtered(score.max(0.), similarity))
+        } else {
+            Ok(Self::identical(100.))
         }
     }
 
-    fn diff_with_score(json1: &Value, json2: &Value, keys_only: bool) -> Self {
-        if let (Value::Object(obj1), Value::Object(obj2)) = (json1, json2) {
-            return Self::object_diff(obj1, obj2, keys_only);
-        }
-        if let (Value::Array(array1), Value::Array(array2)) = (json1, json2) {
-            return Self::array_diff(array1, array2, keys_only);
-        }
+    /// Returns `true` if some entries of an array difference are changes,
+    /// which may all have been left out once the change budget is spent.
+    pub(crate) fn has_changed_entries(entries: &[Value]) -> bool {
+        entries
+            .iter()
+            .any(|entry| entry != " " && entry.get(0).map_or(true, |op| op != " "))
+    }
 
-        if !keys_only && json1 != json2 {
-            Self {
-                score: 0.,
-                diff: Some(json!({ "__old": json1, "__new": json2 })),
-            }
-        } else {
-            Self {
-                score: 100.,
-                diff: None,
+    /// Returns `true` if both values are objects or both are arrays.
+    pub(crate) fn are_same_containers(item1: &Value, item2: &Value) -> bool {
+        (item1.is_object() && item2.is_object()) || (item1.is_array() && item2.is_array())
+    }
+
+    /// Compares two arrays item by item, without searching for the best
+    /// match of each item. The items beyond the length of the shortest
+    /// array are deleted or added.
+    #[allow(clippy::cast_precision_loss)]
+    fn positional_array_diff(
+        array1: &[Value],
+        array2: &[Value],
+        options: &DiffOptions,
+        path: &str,
+        budget: &ChangeBudget,
+    ) -> Result<Self, DiffError> {
+        let mut result: Vec<Value> = Vec::new();
+        let mut score: f64 = 0.;
+        let mut all_equal = true;
+        let mut similarity = 0.;
+        let mut entries: usize = 0;
+        let mut filtered = false;
+
+        for (index, (item1, item2)) in array1.iter().zip(array2).enumerate() {
+            let json_diff =
+                Self::diff_with_budget(item1, item2, options, &format!("{path}/{index}"), budget)?;
+            // Differing scalars are reported as deleted and added, even if
+            // their change is left out by the filter.
+            let replaced = !Self::are_same_containers(item1, item2)
+                && (json_diff.diff.is_some() || json_diff.filtered);
+            if replaced {
+                if budget.spend_on(ChangeKind::Removed, &mut filtered) {
+                    result.push(Self::array_entry('-', item1.clone(), &[index], options));
+                }
+                if budget.spend_on(ChangeKind::Added, &mut filtered) {
+                    result.push(Self::array_entry('+', item2.clone(), &[index], options));
+                }
+                all_equal = false;
+                score -= 10.;
+                entries += 2;
+                continue;
+            }
+            filtered |= json_diff.filtered;
+            match json_diff.diff {
+                None if item1.is_object() || item1.is_array() => result.push(json!([json!(' ')])),
+                None => result.push(json!([json!(' '), item1])),
+                Some(change) => {
+                    result.push(Self::array_entry('~', change, &[index, index], options));
+                    all_equal = false;
+                }
+            }
+            similarity += json_diff.similarity;
+            score += 10.;
+            entries += 1;
+        }
+
+        let shortest = array1.len().min(array2.len());
+        for (index, item) in array1.iter().enumerate().skip(shortest) {
+            if budget.spend_on(ChangeKind::Removed, &mut filtered) {
+                result.push(Self::array_entry('-', item.clone(), &[index], options));
+            }
+            all_equal = false;
+            score -= 5.;
+            entries += 1;
+        }
+        for (index, item) in array2.iter().enumerate().skip(shortest) {
+            if budget.spend_on(ChangeKind::Added, &mut filtered) {
+                result.push(Self::array_entry('+', item.clone(), &[index], options));
+            }
+            all_equal = false;
+            score -= 5.;
+            entries += 1;
+        }
+
+        let similarity = similarity / entries as f64;
+        if all_equal && !filtered {
+            Ok(Self::identical(100.))
+        } else if Self::has_changed_entries(&result) {
+            Ok(Self {
+                score: score.max(0.),
+                similarity,
+                diff: Some(Value::Array(result)),
+                truncated: false,
+                filtered: false,
+                markers: None,
+            })
+        } else if filtered {
+            Ok(Self::filtered(score.max(0.), similarity))
+        } else {
+            Ok(Self::identical(100.))
+        }
+    }
+
+    /// Reports the deleted and added items having the same value as moves.
+    ///
+    /// The items kept in place are the largest set of unchanged, changed and
+    /// paired items whose order is the same in both arrays, preferring the
+    /// changed items, which cannot be moved, then the items keeping their
+    /// index, then the unchanged items. The other paired items are moved.
+    fn detect_moves(entries: Vec<Value>, options: &DiffOptions) -> Vec<Value> {
+        let op = |entry: &Value| entry.get(0).and_then(Value::as_str).map(String::from);
+
+        // The old and new indices of every entry.
+        let mut indices: Vec<(usize, usize)> = Vec::with_capacity(entries.len());
+        let (mut old_index, mut new_index) = (0, 0);
+        for entry in &entries {
+            indices.push((old_index, new_index));
+            match op(entry).as_deref() {
+                Some("-") => old_index += 1,
+                Some("+") => new_index += 1,
+                _ => {
+                    old_index += 1;
+                    new_index += 1;
+                }
+            }
+        }
+
+        // Pair each added item with the first deleted item having the same
+        // value, consuming every deleted item at most once. The unchanged
+        // and changed items are paired with themselves.
+        let mut deleted: Vec<usize> = (0..entries.len())
+            .filter(|&position| op(&entries[position]).as_deref() == Some("-"))
+            .collect();
+        let mut pairs: Vec<MovePair> = Vec::new();
+        for (position, entry) in entries.iter().enumerate() {
+            match op(entry).as_deref() {
+                Some("-") => {}
+                Some("+") => {
+                    let matching = deleted
+                        .iter()
+                        .position(|&deleted_position| entries[deleted_position][1] == entry[1]);
+                    if let Some(matching) = matching {
+                        let deleted_position = deleted.remove(matching);
+                        pairs.push(MovePair {
+                            old_index: indices[deleted_position].0,
+                            new_index: indices[position].1,
+                            deleted: Some(deleted_position),
+                            added: position,
+                        });
+                    }
+                }
+                _ => pairs.push(MovePair {
+                    old_index: indices[position].0,
+                    new_index: indices[position].1,
+                    deleted: None,
+                    added: position,
+                }),
+            }
+        }
+        pairs.sort_by_key(|pair| pair.old_index);
+        let kept = Self::heaviest_increasing_pairs(&pairs, new_index, |pair| {
+            [
+                usize::from(op(&entries[pair.added]).as_deref() == Some("~")),
+                1,
+                usize::from(pair.old_index == pair.new_index),
+                usize::from(pair.deleted.is_none()),
+            ]
+        });
+
+        let mut entries: Vec<Option<Value>> = entries.into_iter().map(Some).collect();
+        // The items left in the old array, i.e. the deleted items which are
+        // not moved, and the items of the new array, both keyed by index.
+        let mut old_items: BTreeMap<usize, Value> = deleted
+            .into_iter()
+            .filter_map(|position| Some((indices[position].0, entries[position].take()?)))
+            .collect();
+        let mut new_items: BTreeMap<usize, Value> = BTreeMap::new();
+        let mut kept_items: Vec<(usize, usize, Value)> = Vec::new();
+        for (index, pair) in pairs.iter().enumerate() {
+            let added = entries[pair.added].take().unwrap_or(Value::Null);
+            if let Some(deleted_position) = pair.deleted {
+                entries[deleted_position] = None;
+            }
+            if kept.contains(&index) {
+                let item = match (pair.deleted, added) {
+                    // A paired item kept in place is unchanged.
+                    (Some(_), Value::Array(mut added)) => {
+                        let value = added.swap_remove(1);
+                        if value.is_object() || (value.is_array() && options.fuzzy_match_arrays) {
+                            json!([' '])
+                        } else {
+                            json!([' ', value])
+                        }
+                    }
+                    (_, added) => added,
+                };
+                kept_items.push((pair.old_index, pair.new_index, item));
+            } else {
+                new_items.insert(pair.new_index, json!(['>', pair.old_index, pair.new_index]));
+            }
+        }
+        new_items.extend(
+            entries
+                .into_iter()
+                .enumerate()
+                .filter_map(|(position, entry)| Some((indices[position].1, entry?))),
+        );
+
+        // The kept items are in the same order in both arrays, so the other
+        // items are placed between them, the deleted ones first.
+        let mut result = Vec::new();
+        for (old_index, new_index, item) in kept_items {
+            let later_old_items = old_items.split_off(&old_index);
+            result.extend(mem::replace(&mut old_items, later_old_items).into_values());
+            let later_new_items = new_items.split_off(&new_index);
+            result.extend(mem::replace(&mut new_items, later_new_items).into_values());
+            result.push(item);
+        }
+        result.extend(old_items.into_values());
+        result.extend(new_items.into_values());
+        result
+    }
+
+    /// Returns the indices of the heaviest subsequence of `pairs`, sorted by
+    /// their old index, whose new indices, all below `new_len`, increase
+    /// too.
+    ///
+    /// The weights are compared lexicographically, and the best weight
+    /// ending at each new index is kept in a Fenwick tree, so that it takes
+    /// a time quasi-linear in the number of pairs.
+    fn heaviest_increasing_pairs(
+        pairs: &[MovePair],
+        new_len: usize,
+        weight: impl Fn(&MovePair) -> [usize; 4],
+    ) -> BTreeSet<usize> {
+        type Best = Option<([usize; 4], usize)>;
+        let mut tree: Vec<Best> = vec![None; new_len + 1];
+        let mut previous: Vec<Option<usize>> = vec![None; pairs.len()];
+        let mut best: Best = None;
+        for (index, pair) in pairs.iter().enumerate() {
+            // The best subsequence ending before the new index of the pair.
+            let mut before: Best = None;
+            let mut node = pair.new_index;
+            while node > 0 {
+                before = before.max(tree[node]);
+                node &= node - 1;
+            }
+            let (before_weight, _) = before.unwrap_or_default();
+            let own = weight(pair);
+            let total: [usize; 4] = core::array::from_fn(|part| before_weight[part] + own[part]);
+            previous[index] = before.map(|(_, before_index)| before_index);
+            best = best.max(Some((total, index)));
+            let mut node = pair.new_index + 1;
+            while node <= new_len {
+                tree[node] = tree[node].max(Some((total, index)));
+                node += node & node.wrapping_neg();
+            }
+        }
+
+        let mut kept = BTreeSet::new();
+        let mut index = best.map(|(_, index)| index);
+        while let Some(current) = index {
+            kept.insert(current);
+            index = previous[current];
+        }
+        kept
+    }
+
+    /// Compares two floats within `epsilon`.
+    ///
+    /// Non-finite floats cannot be parsed from JSON, but they may come from
+    /// the numbers of `serde_json/arbitrary_precision` too large for `f64`.
+    /// Two NaNs are then equal, so that they are not reported as changed,
+    /// and an infinity is only equal to the same infinity.
+    #[allow(clippy::float_cmp)]
+    fn floats_equal(float1: f64, float2: f64, epsilon: f64) -> bool {
+        if float1.is_nan() || float2.is_nan() {
+            float1.is_nan() && float2.is_nan()
+        } else if float1 == float2 {
+            true
+        } else if float1.is_infinite() || float2.is_infinite() {
+            false
+        } else {
+            (float1 - float2).abs() <= epsilon
+        }
+    }
+
+    fn as_integer(number: &Number) -> Option<i128> {
+        number
+            .as_i64()
+            .map(i128::from)
+            .or_else(|| number.as_u64().map(i128::from))
+    }
+
+    /// Compares two numbers within `epsilon`, without rounding the integers
+    /// which have no float representation.
+    #[allow(clippy::cast_precision_loss)]
+    fn numbers_equal(json1: &Value, json2: &Value, epsilon: f64) -> Option<bool> {
+        let (Value::Number(number1), Value::Number(number2)) = (json1, json2) else {
+            return None;
+        };
+        if let (Some(integer1), Some(integer2)) =
+            (Self::as_integer(number1), Self::as_integer(number2))
+        {
+            return Some(
+                integer1 == integer2 || (integer1 - integer2).unsigned_abs() as f64 <= epsilon,
+            );
+        }
+        Some(Self::floats_equal(
+            number1.as_f64()?,
+            number2.as_f64()?,
+            epsilon,
+        ))
+    }
+
+    /// Compares two numbers by their mathematical value, without rounding
+    /// the integers which have no float representation.
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_precision_loss,
+        clippy::float_cmp
+    )]
+    fn number_values_equal(json1: &Value, json2: &Value) -> Option<bool> {
+        let (Value::Number(number1), Value::Number(number2)) = (json1, json2) else {
+            return None;
+        };
+        // A float is equal to an integer if it is integral, i.e. it is not
+        // changed by a truncation. Floats out of the range of `i128`
+        // saturate, and they are then far from any `u64` or `i64`.
+        let is_integer = |float: f64, integer: i128| {
+            let truncated = float as i128;
+            truncated as f64 == float && truncated == integer
+        };
+        Some(
+            match (Self::as_integer(number1), Self::as_integer(number2)) {
+                (Some(integer1), Some(integer2)) => integer1 == integer2,
+                (Some(integer1), None) => is_integer(number2.as_f64()?, integer1),
+                (None, Some(integer2)) => is_integer(number1.as_f64()?, integer2),
+                (None, None) => Self::floats_equal(number1.as_f64()?, number2.as_f64()?, 0.),
+            },
+        )
+    }
+
+    /// Returns `true` if the difference between two values, which are not
+    /// both objects or both arrays, is to be reported.
+    fn scalars_differ(json1: &Value, json2: &Value, options: &DiffOptions) -> bool {
+        if options.keys_only {
+            false
+        } else if options.types_only {
+            !Self::check_type(json1, json2)
+        } else {
+            !Self::scalars_equal(json1, json2, options)
+        }
+    }
+
+    pub(crate) fn scalars_equal(json1: &Value, json2: &Value, options: &DiffOptions) -> bool {
+        if let Some(equal) = options
+            .scalar_comparators
+            .iter()
+            .find_map(|comparator| comparator.equal(json1, json2))
+        {
+            return equal;
+        }
+        if let Some((number1, number2)) = options.numeric_strings(json1, json2) {
+            let equal = match options.epsilon {
+                Some(epsilon) => Self::numbers_equal(&number1, &number2, epsilon),
+                None => Self::number_values_equal(&number1, &number2),
+            };
+            return equal.unwrap_or(false);
+        }
+        if let Some(epsilon) = options.epsilon {
+            if let Some(equal) = Self::numbers_equal(json1, json2, epsilon) {
+                return equal;
+            }
+        }
+        if options.numbers_by_value {
+            if let Some(equal) = Self::number_values_equal(json1, json2) {
+                return equal;
+            }
+        }
+        if options.case_insensitive {
+            if let (Value::String(string1), Value::String(string2)) = (json1, json2) {
+                return string1.to_lowercase() == string2.to_lowercase();
             }
         }
+        json1 == json2
+    }
+
+    /// Finds the difference of two values without any depth limit, so that
+    /// they can be reported as a whole.
+    fn opaque_diff(
+        json1: &Value,
+        json2: &Value,
+        options: &DiffOptions,
+        path: &str,
+    ) -> Result<Self, DiffError> {
+        Self::diff_with_budget(json1, json2, options, path, &ChangeBudget::unbounded())
+    }
+
+    /// Finds the difference of two values without limiting the number of
+    /// changes, so that they can be scored as a whole.
+    pub(crate) fn diff_with_score(
+        json1: &Value,
+        json2: &Value,
+        options: &DiffOptions,
+        path: &str,
+    ) -> Result<Self, DiffError> {
+        Self::diff_with_budget(json1, json2, options, path, &ChangeBudget::unlimited())
+    }
+
+    /// Returns the runs of characters kept, deleted and added to turn
+    /// `string1` into `string2`.
+    fn text_diff(string1: &str, string2: &str) -> Value {
+        let chars1: Vec<char> = string1.chars().collect();
+        let chars2: Vec<char> = string2.chars().collect();
+        let mut segments = Vec::new();
+        for opcode in SequenceMatcher::new(&chars1, &chars2).get_opcodes() {
+            let deleted: String = chars1[opcode.first_start..opcode.first_end]
+                .iter()
+                .collect();
+            let added: String = chars2[opcode.second_start..opcode.second_end]
+                .iter()
+                .collect();
+            if opcode.tag == "equal" {
+                segments.push(json!([" ", deleted]));
+                continue;
+            }
+            if !deleted.is_empty() {
+                segments.push(json!(["-", deleted]));
+            }
+            if !added.is_empty() {
+                segments.push(json!(["+", added]));
+            }
+        }
+        Value::Array(segments)
+    }
+
+    pub(crate) fn diff_with_budget(
+        json1: &Value,
+        json2: &Value,
+        options: &DiffOptions,
+        path: &str,
+        budget: &ChangeBudget,
+    ) -> Result<Self, DiffError> {
+        if budget.is_exhausted() {
+            // The values are not compared anymore, so they are assumed to
+            // differ unless they are strictly equal.
+            if json1 != json2 {
+                budget.truncate();
+            }
+            return Ok(Self::identical(100.));
+        }
+
+        if !budget.is_unbounded() && options.is_beyond_max_depth(path) {
+            let json_diff = Self::opaque_diff(json1, json2, options, path)?;
+            let mut filtered = false;
+            return Ok(if json_diff.diff.is_none() {
+                Self::identical(json_diff.score)
+            } else if budget.spend_on(ChangeKind::Changed, &mut filtered) {
+                Self {
+                    diff: Some(options.markers.change(json1, json2)),
+                    ..json_diff
+                }
+            } else if filtered {
+                Self::filtered(json_diff.score, json_diff.similarity)
+            } else {
+                Self::identical(json_diff.score)
+            });
+        }
+
+        if let (Value::Object(obj1), Value::Object(obj2)) = (json1, json2) {
+            return Self::object_diff(obj1, obj2, options, path, budget);
+        }
+        if let (Value::Array(array1), Value::Array(array2)) = (json1, json2) {
+            return if options.is_beyond_fuzzy_array_len(array1, array2) {
+                Self::positional_array_diff(array1, array2, options, path, budget)
+            } else if options.is_unordered(path) {
+                Self::unordered_array_diff(array1, array2, options, path, budget)
+            } else {
+                Self::array_diff(array1, array2, options, path, budget)
+            };
+        }
+
+        if let Some((embedded1, embedded2)) = options.embedded_json(json1, json2) {
+            let json_diff = Self::diff_with_budget(&embedded1, &embedded2, options, path, budget)?;
+            return Ok(Self {
+                diff: json_diff.diff.map(|diff| options.markers.embedded(diff)),
+                ..json_diff
+            });
+        }
+
+        let mut filtered = false;
+        if Self::scalars_differ(json1, json2, options)
+            && budget.spend_on(ChangeKind::Changed, &mut filtered)
+        {
+            let diff = match options.text_diff(json1, json2) {
+                Some((string1, string2)) => options.markers.text(Self::text_diff(string1, string2)),
+                None => options.markers.change(json1, json2),
+            };
+            Ok(Self {
+                score: 0.,
+                diff: Some(diff),
+                truncated: false,
+                filtered: false,
+                similarity: 0.,
+                markers: None,
+            })
+        } else if filtered {
+            Ok(Self::filtered(0., 0.))
+        } else {
+            Ok(Self::identical(100.))
+        }
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "std"))]
 mod tests {
 
+    use std::collections::BTreeMap;
     use std::error::Error;
     use std::fs::File;
     use std::io::BufReader;
     use std::path::Path;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use serde_json::Value;
 
     use super::JsonDiff;
+    use crate::budget::ChangeBudget;
+    use crate::colorize::colorize_to_array;
+    use crate::colorize::LineOptions;
+    use crate::error::DiffError;
+    use crate::filter::DiffFilter;
+    use crate::markers::DiffMarkers;
+    use crate::options::DiffOptions;
+    use crate::pointer::JsonPointer;
+    use crate::tree::{ArrayOp, DiffNode};
 
     #[test]
     fn test_scalar() {
@@ -585,6 +2046,145 @@ mod tests {
         assert_eq!(JsonDiff::diff(&json!(42), &json!(10), true).diff, None);
     }
 
+    #[test]
+    fn test_diff_at() {
+        let options = DiffOptions::default();
+        let json1 = json!({"foo": 42, "bar": {"bbbar": 10, "bbboz": 11, "a/b": [1]}});
+        let json2 = json!({"foo": 43, "bar": {"bbbar": 12, "bbboz": 11, "a/b": [2]}});
+
+        let at = |pointer| JsonPointer::parse(pointer).unwrap();
+
+        for pointer in ["/bar", "/bar/a~1b", "/foo", ""] {
+            let json_diff = JsonDiff::diff_at(&json1, &json2, &at(pointer), &options)
+                .unwrap()
+                .unwrap();
+            let expected = JsonDiff::diff_with_options(
+                json1.pointer(pointer).unwrap(),
+                json2.pointer(pointer).unwrap(),
+                &options,
+            );
+            assert_eq!(json_diff.diff, expected.diff);
+        }
+        assert_eq!(
+            JsonDiff::diff_at(&json1, &json2, &at("/bar"), &options)
+                .unwrap()
+                .unwrap()
+                .diff,
+            Some(json!({"bbbar": {"__old": 10, "__new": 12}, "a/b": [["-", 1], ["+", 2]]}))
+        );
+
+        // The JSON Pointers of the ignored keys are relative to the roots.
+        let options = DiffOptions {
+            ignore_keys: vec!["/bar/bbbar".to_owned()],
+            ..DiffOptions::default()
+        };
+        assert_eq!(
+            JsonDiff::diff_at(&json1, &json2, &at("/bar"), &options)
+                .unwrap()
+                .unwrap()
+                .diff,
+            Some(json!({"a/b": [["-", 1], ["+", 2]]}))
+        );
+
+        assert!(JsonDiff::diff_at(&json1, &json2, &at("/missing"), &options)
+            .unwrap()
+            .is_none());
+        let err = JsonDiff::diff_at(&json1, &json!({"bar": {}}), &at("/foo"), &options)
+            .err()
+            .unwrap();
+        assert!(matches!(
+            &err,
+            DiffError::PointerNotFound { pointer, in_first: false } if *pointer == at("/foo")
+        ));
+        assert_eq!(
+            err.to_string(),
+            "the JSON Pointer /foo does not resolve in the second JSON file"
+        );
+        assert!(matches!(
+            JsonDiff::diff_at(&json!({}), &json1, &at("/bar/bbbar"), &options),
+            Err(DiffError::PointerNotFound { in_first: true, .. })
+        ));
+    }
+
+    #[test]
+    fn test_check_type() {
+        let values = [
+            json!(null),
+            json!(true),
+            json!(1),
+            json!("foo"),
+            json!([1]),
+            json!({"foo": 1}),
+        ];
+        for (index1, value1) in values.iter().enumerate() {
+            for (index2, value2) in values.iter().enumerate() {
+                assert_eq!(JsonDiff::check_type(value1, value2), index1 == index2);
+            }
+        }
+        assert!(JsonDiff::check_type(&json!(false), &json!(true)));
+        assert!(JsonDiff::check_type(&json!(1), &json!(2.5)));
+        assert!(JsonDiff::check_type(&json!([]), &json!([{"foo": 1}])));
+
+        // An array is not matched with an object, even if it is the only
+        // candidate.
+        assert_eq!(
+            JsonDiff::diff(&json!([{"foo": 1, "bar": 2}]), &json!([[1, 2]]), false).diff,
+            Some(json!([["-", {"foo": 1, "bar": 2}], ["+", [1, 2]]]))
+        );
+    }
+
+    #[test]
+    fn test_max_changes() {
+        let json1 = json!({
+            "a": [1, 2, 3, 4, 5],
+            "b": {"x": 1, "y": 2, "z": 3},
+            "c": "foo",
+            "d": [{"id": 1}, {"id": 2}]
+        });
+        let json2 = json!({
+            "a": [6, 7, 8, 9, 10],
+            "b": {"u": 1, "v": 2, "w": 3},
+            "c": "bar",
+            "e": [true, false]
+        });
+        let complete = JsonDiff::diff(&json1, &json2, false);
+        assert!(!complete.truncated);
+        assert!(complete.flatten().len() > 3);
+
+        let options = DiffOptions {
+            max_changes: Some(3),
+            ..DiffOptions::default()
+        };
+        let truncated = JsonDiff::diff_with_options(&json1, &json2, &options);
+        assert!(truncated.truncated);
+        assert_eq!(truncated.flatten().len(), 3);
+        assert_eq!(
+            truncated.diff,
+            Some(json!({"a": [["-", 1], ["-", 2], ["-", 3]]}))
+        );
+
+        // Reaching the limit without leaving any change out is not a
+        // truncation.
+        let options = DiffOptions {
+            max_changes: Some(1),
+            ..DiffOptions::default()
+        };
+        let exact = JsonDiff::diff_with_options(
+            &json!({"a": 1, "b": 2}),
+            &json!({"a": 1, "b": 3}),
+            &options,
+        );
+        assert!(!exact.truncated);
+        assert_eq!(exact.diff, Some(json!({"b": {"__old": 2, "__new": 3}})));
+        let options = DiffOptions {
+            max_changes: Some(0),
+            ..DiffOptions::default()
+        };
+        let empty = JsonDiff::diff_with_options(&json1, &json2, &options);
+        assert!(empty.truncated);
+        assert_eq!(empty.diff, None);
+    }
+
     #[test]
     fn test_objects_keys() {
         assert_eq!(JsonDiff::diff(&json!({}), &json!({}), true).diff, None);
@@ -743,32 +2343,2392 @@ mod tests {
     }
 
     #[test]
-    fn test_diff_string() {
-        fn read_json_file(filename: &str) -> Result<serde_json::Value, Box<dyn Error>> {
-            // Get path
-            let path = Path::new(filename);
+    fn test_has_changes() {
+        let json_diff = JsonDiff::diff(&json!({"foo": 42}), &json!({"foo": 10}), false);
+        assert!(json_diff.has_changes());
+        assert_eq!(
+            json_diff.into_diff(),
+            Some(json!({"foo": {"__old": 42, "__new": 10}}))
+        );
 
-            // Open the file in read-only mode with buffer.
-            let file = File::open(path)?;
-            let reader = BufReader::new(file);
+        let json_diff = JsonDiff::diff(&json!({"foo": 42}), &json!({"foo": 10}), true);
+        assert!(!json_diff.has_changes());
+        assert_eq!(json_diff.into_diff(), None);
+    }
 
-            // Read the JSON contents of the file as an instance of `Value`.
-            let value = serde_json::from_reader(reader)?;
+    #[test]
+    fn test_similarity() {
+        let similarity =
+            |json1: &Value, json2: &Value| JsonDiff::diff(json1, json2, false).similarity();
 
-            // Return the `Value`.
-            Ok(value)
+        // Identical
+        assert!((similarity(&json!(42), &json!(42)) - 1.).abs() < f64::EPSILON);
+        assert!(
+            (similarity(
+                &json!({"foo": 42, "bar": [1, 2]}),
+                &json!({"foo": 42, "bar": [1, 2]})
+            ) - 1.)
+                .abs()
+                < f64::EPSILON
+        );
+
+        // Half-overlapping
+        assert!(
+            (similarity(&json!({"foo": 42, "bar": 1}), &json!({"foo": 42, "bar": 2})) - 0.5).abs()
+                < f64::EPSILON
+        );
+        assert!(
+            (similarity(
+                &json!({"foo": 42, "bar": 1, "baz": 2, "qux": 3}),
+                &json!({"foo": 42, "bar": 1, "quux": 2, "corge": 3})
+            ) - 2. / 6.)
+                .abs()
+                < f64::EPSILON
+        );
+        assert!((similarity(&json!([1, 2]), &json!([1, 3])) - 1. / 3.).abs() < f64::EPSILON);
+        assert!(
+            (similarity(
+                &json!({"foo": {"bar": 1, "baz": 2}}),
+                &json!({"foo": {"bar": 1, "baz": 3}})
+            ) - 0.5)
+                .abs()
+                < f64::EPSILON
+        );
+
+        // Disjoint
+        assert!(similarity(&json!(42), &json!("foo")).abs() < f64::EPSILON);
+        assert!(similarity(&json!({"foo": 42}), &json!({"bar": 42})).abs() < f64::EPSILON);
+        assert!(similarity(&json!([1, 2]), &json!([3, 4])).abs() < f64::EPSILON);
+
+        // Bounds
+        for (json1, json2) in [
+            (
+                json!({"foo": 1, "bar": 2, "baz": 3}),
+                json!({"foo": 1, "bar": 2, "baz": 3}),
+            ),
+            (
+                json!([{"id": 1, "foo": 1}]),
+                json!([{"id": 1, "foo": 2}, 3]),
+            ),
+            (json!({"foo": [1, 2, 3]}), json!({"foo": [3, 2, 1]})),
+        ] {
+            let json_diff = JsonDiff::diff(&json1, &json2, false);
+            assert!((0. ..=1.).contains(&json_diff.similarity()));
         }
+    }
 
-        let json1 = read_json_file("data/a.json").unwrap();
-        let json2 = read_json_file("data/b.json").unwrap();
+    #[test]
+    fn test_detect_moves() {
+        let options = DiffOptions {
+            detect_moves: true,
+            ..DiffOptions::default()
+        };
 
+        let json_diff = JsonDiff::diff_with_options(&json!([1, 2, 3]), &json!([3, 2, 1]), &options);
         assert_eq!(
-            JsonDiff::diff_string(&json1, &json2, false).unwrap(),
-            std::fs::read_to_string("data/result.jsdiff")
-                .unwrap()
-                .replace("\r\n", "\n")
+            json_diff.diff,
+            Some(json!([['>', 2, 0], [' ', 2], ['>', 0, 2]]))
         );
 
-        assert_eq!(JsonDiff::diff_string(&json1, &json1, false), None);
+        // The items keeping their order are not moved, unlike the others.
+        let json_diff =
+            JsonDiff::diff_with_options(&json!([1, 2, 3, 4, 5]), &json!([5, 2, 3, 4, 1]), &options);
+        assert_eq!(
+            json_diff.diff,
+            Some(json!([
+                ['>', 4, 0],
+                [' ', 2],
+                [' ', 3],
+                [' ', 4],
+                ['>', 0, 4]
+            ]))
+        );
+        let json_diff =
+            JsonDiff::diff_with_options(&json!([1, 2, 3, 4]), &json!([2, 3, 4, 1]), &options);
+        assert_eq!(
+            json_diff.diff,
+            Some(json!([[' ', 2], [' ', 3], [' ', 4], ['>', 0, 3]]))
+        );
+
+        // Each deleted item is moved at most once.
+        let json_diff = JsonDiff::diff_with_options(
+            &json!([{"id": 1, "foo": true}, 4, 5]),
+            &json!([4, 5, 6, {"id": 1, "foo": true}, {"id": 1, "foo": true}]),
+            &options,
+        );
+        assert_eq!(
+            json_diff.diff,
+            Some(json!([
+                [' ', 4],
+                [' ', 5],
+                ['+', 6],
+                ['>', 0, 3],
+                ['+', {"id": 1, "foo": true}]
+            ]))
+        );
+
+        // Items are not moved by default.
+        let json_diff = JsonDiff::diff(&json!([1, 2, 3]), &json!([3, 2, 1]), false);
+        assert_eq!(
+            json_diff.diff,
+            Some(json!([['+', 3], ['+', 2], [' ', 1], ['-', 2], ['-', 3]]))
+        );
+    }
+
+    #[test]
+    fn test_case_insensitive() {
+        let json1 = json!({"Status": "OK", "tags": ["Foo", "bar"]});
+        let json2 = json!({"status": "ok", "tags": ["foo", "BAR"]});
+
+        // Values
+        let options = DiffOptions {
+            case_insensitive: true,
+            ..DiffOptions::default()
+        };
+        assert_eq!(
+            JsonDiff::diff_with_options(&json1, &json2, &options).diff,
+            Some(json!({"Status__deleted": "OK", "status__added": "ok"}))
+        );
+        assert!(
+            JsonDiff::diff_with_options(&json!("OK"), &json!("ok"), &options)
+                .diff
+                .is_none()
+        );
+        assert!(JsonDiff::diff(&json!("OK"), &json!("ok"), false)
+            .diff
+            .is_some());
+
+        // Keys
+        let options = DiffOptions {
+            case_insensitive_keys: true,
+            ..DiffOptions::default()
+        };
+        assert_eq!(
+            JsonDiff::diff_with_options(&json1, &json2, &options).diff,
+            Some(json!({
+                "Status": {"__old": "OK", "__new": "ok"},
+                "tags": [["-", "Foo"], ["-", "bar"], ["+", "foo"], ["+", "BAR"]]
+            }))
+        );
+
+        // Values and keys
+        let options = DiffOptions {
+            case_insensitive: true,
+            case_insensitive_keys: true,
+            ..DiffOptions::default()
+        };
+        assert!(JsonDiff::diff_with_options(&json1, &json2, &options)
+            .diff
+            .is_none());
+
+        // Keys only
+        let options = DiffOptions {
+            case_insensitive_keys: true,
+            ..DiffOptions::new(true)
+        };
+        assert!(
+            JsonDiff::diff_with_options(&json!({"Foo": 1}), &json!({"foo": 2}), &options)
+                .diff
+                .is_none()
+        );
+        assert!(JsonDiff::diff(&json!({"Foo": 1}), &json!({"foo": 2}), true)
+            .diff
+            .is_some());
+    }
+
+    #[test]
+    fn test_max_depth() {
+        let json1 = json!({"foo": {"bar": 1, "baz": [1, 2]}, "qux": 1});
+        let json2 = json!({"foo": {"bar": 2, "baz": [1, 2]}, "qux": 1});
+        let with_max_depth = |max_depth| DiffOptions {
+            max_depth: Some(max_depth),
+            ..DiffOptions::default()
+        };
+
+        assert_eq!(
+            JsonDiff::diff_with_options(&json1, &json2, &with_max_depth(0)).diff,
+            Some(json!({"__old": json1, "__new": json2}))
+        );
+        assert_eq!(
+            JsonDiff::diff_with_options(&json1, &json2, &with_max_depth(1)).diff,
+            Some(json!({
+                "foo": {
+                    "__old": {"bar": 1, "baz": [1, 2]},
+                    "__new": {"bar": 2, "baz": [1, 2]}
+                }
+            }))
+        );
+        assert_eq!(
+            JsonDiff::diff_with_options(&json1, &json2, &with_max_depth(2)).diff,
+            JsonDiff::diff(&json1, &json2, false).diff
+        );
+        assert!(
+            JsonDiff::diff_with_options(&json1, &json1, &with_max_depth(0))
+                .diff
+                .is_none()
+        );
+
+        // Items of arrays are one level deeper than the arrays.
+        assert_eq!(
+            JsonDiff::diff_with_options(
+                &json!([1, {"id": 1, "foo": [1, 2]}]),
+                &json!([1, {"id": 1, "foo": [1, 3]}]),
+                &with_max_depth(2)
+            )
+            .diff,
+            Some(json!([
+                [" ", 1],
+                ["~", {"foo": {"__old": [1, 2], "__new": [1, 3]}}]
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_include_match_scores() {
+        let options = DiffOptions {
+            include_match_scores: true,
+            ..DiffOptions::default()
+        };
+        let json1 = json!([{"id": 1, "name": "foo", "size": 2}, {"id": 2, "name": "bar"}]);
+        let json2 = json!([{"id": 2, "name": "baz"}, {"id": 1, "name": "foo", "size": 3}]);
+
+        // By default, there are no scores.
+        assert_eq!(
+            JsonDiff::diff(&json1, &json2, false).diff.unwrap()[1],
+            json!(["~", {"size": {"__old": 2, "__new": 3}}])
+        );
+
+        let diff = JsonDiff::diff_with_options(&json1, &json2, &options)
+            .diff
+            .unwrap();
+        let entry = diff.as_array().unwrap()[1].as_array().unwrap();
+        assert_eq!(entry.len(), 3);
+        assert_eq!(entry[1], json!({"size": {"__old": 2, "__new": 3}}));
+        let score = entry[2]["__score"].as_f64().unwrap();
+        assert!(score > options.array_match_threshold && score <= 100.);
+
+        // The score follows the indices, and the pairs of unordered arrays
+        // have one too.
+        let options = DiffOptions {
+            include_indices: true,
+            array_unordered: true,
+            ..options
+        };
+        let diff = JsonDiff::diff_with_options(&json1, &json2, &options)
+            .diff
+            .unwrap();
+        for entry in diff.as_array().unwrap() {
+            assert_eq!(entry[0], "~");
+            assert!(entry[2].is_u64() && entry[3].is_u64());
+            assert!(entry[4]["__score"].as_f64().unwrap() > options.array_match_threshold);
+        }
+
+        // The items paired by their position have no score.
+        let positional = DiffOptions {
+            array_unordered: false,
+            max_fuzzy_array_len: Some(0),
+            ..options.clone()
+        };
+        let diff = JsonDiff::diff_with_options(&json!([{"a": 1}]), &json!([{"a": 2}]), &positional)
+            .diff
+            .unwrap();
+        assert_eq!(diff, json!([["~", {"a": {"__old": 1, "__new": 2}}, 0, 0]]));
+
+        // The scores are left out of the tree.
+        let json_diff = JsonDiff::diff_with_options(&json1, &json2, &options);
+        assert!(json_diff.tree().is_some());
+    }
+
+    #[test]
+    fn test_include_indices() {
+        let options = DiffOptions {
+            include_indices: true,
+            ..DiffOptions::default()
+        };
+
+        let json1 = json!([1, 2, {"id": 1, "foo": 1}, 3, 4, 5]);
+        let json2 = json!([0, 1, {"id": 1, "foo": 2}, 3, 6, 7]);
+        assert_eq!(
+            JsonDiff::diff_with_options(&json1, &json2, &options).diff,
+            Some(json!([
+                ["+", 0, 0],
+                [" ", 1],
+                ["-", 2, 1],
+                ["~", {"foo": {"__old": 1, "__new": 2}}, 2, 2],
+                [" ", 3],
+                ["-", 4, 4],
+                ["-", 5, 5],
+                ["+", 6, 4],
+                ["+", 7, 5]
+            ]))
+        );
+
+        // Indices are not included by default.
+        assert_eq!(
+            JsonDiff::diff(&json1, &json2, false).diff,
+            Some(json!([
+                ["+", 0],
+                [" ", 1],
+                ["-", 2],
+                ["~", {"foo": {"__old": 1, "__new": 2}}],
+                [" ", 3],
+                ["-", 4],
+                ["-", 5],
+                ["+", 6],
+                ["+", 7]
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_max_fuzzy_array_len() {
+        let comparisons = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&comparisons);
+        let options = DiffOptions {
+            max_fuzzy_array_len: Some(100),
+            scalar_comparators: vec![Arc::new(move |_: &Value, _: &Value| {
+                counter.fetch_add(1, Ordering::Relaxed);
+                None
+            })],
+            ..DiffOptions::default()
+        };
+
+        let array1: Vec<Value> = (0..500).map(|i| json!({"id": i, "value": i})).collect();
+        let mut array2 = array1.clone();
+        array2[10]["value"] = json!(-1);
+        array2.push(json!({"id": 500, "value": 500}));
+
+        let json_diff =
+            JsonDiff::diff_with_options(&Value::Array(array1), &Value::Array(array2), &options);
+        // The items are not scored against each other, but only compared
+        // with the item at the same index, scalar by scalar.
+        assert_eq!(comparisons.load(Ordering::Relaxed), 2 * 500);
+
+        let diff = json_diff.diff.unwrap();
+        let entries = diff.as_array().unwrap();
+        assert_eq!(entries.len(), 501);
+        assert_eq!(
+            entries[10],
+            json!(["~", {"value": {"__old": 10, "__new": -1}}])
+        );
+        assert_eq!(entries[500], json!(["+", {"id": 500, "value": 500}]));
+        assert!(entries
+            .iter()
+            .enumerate()
+            .all(|(index, entry)| index == 10 || index == 500 || entry == &json!([" "])));
+
+        // Items are compared by index beyond the maximum length.
+        assert_eq!(
+            JsonDiff::diff_with_options(&json!([1, 2]), &json!([2]), &options).diff,
+            Some(json!([["-", 1], [" ", 2]]))
+        );
+        let options = DiffOptions {
+            max_fuzzy_array_len: Some(1),
+            ..DiffOptions::default()
+        };
+        assert_eq!(
+            JsonDiff::diff_with_options(&json!([1, 2]), &json!([2]), &options).diff,
+            Some(json!([["-", 1], ["+", 2], ["-", 2]]))
+        );
+    }
+
+    #[test]
+    fn test_shared_match_scores() {
+        // Equal items are scored once against the objects of the first array,
+        // even with options scoped at JSON Pointers, so the scalars are
+        // compared fewer times than there are pairs of items.
+        let array1: Vec<Value> = (0..20)
+            .map(|i| json!({"id": i % 3, "tags": ["a", "b"]}))
+            .collect();
+        let mut array2 = array1.clone();
+        array2.rotate_left(1);
+        let (json1, json2) = (Value::Array(array1), Value::Array(array2));
+
+        let diff_counting = |ignore_keys: Vec<String>| {
+            let comparisons = Arc::new(AtomicUsize::new(0));
+            let counter = Arc::clone(&comparisons);
+            let options = DiffOptions {
+                ignore_keys,
+                scalar_comparators: vec![Arc::new(move |_: &Value, _: &Value| {
+                    counter.fetch_add(1, Ordering::Relaxed);
+                    None
+                })],
+                ..DiffOptions::default()
+            };
+            let json_diff = JsonDiff::diff_with_options(&json1, &json2, &options);
+            (json_diff, comparisons.load(Ordering::Relaxed))
+        };
+        let (shared, comparisons) = diff_counting(Vec::new());
+        let (scoped, scoped_comparisons) = diff_counting(vec!["/missing".to_owned()]);
+
+        assert_eq!(shared.diff, scoped.diff);
+        assert!((shared.score - scoped.score).abs() < f64::EPSILON);
+        assert_eq!(comparisons, scoped_comparisons);
+        assert!(comparisons < 20 * 20);
+    }
+
+    #[test]
+    fn test_path_match_scores() {
+        // The items are scored at the path of the object they are matched
+        // with, which is the path their difference is reported at.
+        let json1 = json!([{"id": 1, "tags": ["a", "b", "c"]}]);
+        let json2 = json!([
+            {"id": 2, "tags": ["c", "b", "a"]},
+            {"id": 2, "tags": ["c", "b", "a"]}
+        ]);
+
+        // The tags of the object are unordered, so the first equal item is
+        // matched and the reported tags are equal.
+        let options = DiffOptions {
+            unordered_paths: vec!["/0/tags".to_owned()],
+            include_match_scores: true,
+            ..DiffOptions::default()
+        };
+        assert_eq!(
+            JsonDiff::diff_with_options(&json1, &json2, &options).diff,
+            Some(json!([
+                ["~", {"id": {"__old": 1, "__new": 2}}, {"__score": 60.0}],
+                ["+", {"id": 2, "tags": ["c", "b", "a"]}]
+            ]))
+        );
+
+        // The unordered path only exists in the second array, so neither
+        // item is matched.
+        let options = DiffOptions {
+            unordered_paths: vec!["/1/tags".to_owned()],
+            ..options
+        };
+        assert_eq!(
+            JsonDiff::diff_with_options(&json1, &json2, &options).diff,
+            Some(json!([
+                ["-", {"id": 1, "tags": ["a", "b", "c"]}],
+                ["+", {"id": 2, "tags": ["c", "b", "a"]}],
+                ["+", {"id": 2, "tags": ["c", "b", "a"]}]
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_array_match_threshold() {
+        // The objects score `60.`: `20.` for each shared key and `20.` for
+        // the equal value of `id`.
+        let json1 = json!([{"id": 1, "foo": 1}]);
+        let json2 = json!([{"id": 1, "foo": 2}]);
+
+        assert_eq!(
+            JsonDiff::diff(&json1, &json2, false).diff,
+            Some(json!([["~", {"foo": {"__old": 1, "__new": 2}}]]))
+        );
+
+        let options = DiffOptions {
+            array_match_threshold: 70.,
+            ..DiffOptions::default()
+        };
+        assert_eq!(
+            JsonDiff::diff_with_options(&json1, &json2, &options).diff,
+            Some(json!([
+                ["-", {"id": 1, "foo": 1}],
+                ["+", {"id": 1, "foo": 2}]
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_key_order() {
+        let json1: Value = serde_json::from_str(r#"{"b": 1, "d": 1, "a": 1, "c": 1}"#).unwrap();
+        let json2: Value = serde_json::from_str(r#"{"e": 1, "b": 2, "a": 2, "c": 1}"#).unwrap();
+
+        let diff = JsonDiff::diff(&json1, &json2, false).diff.unwrap();
+        let keys: Vec<&str> = diff
+            .as_object()
+            .unwrap()
+            .keys()
+            .map(String::as_str)
+            .collect();
+
+        // `Map` is sorted unless `serde_json/preserve_order` is enabled.
+        let preserves_order = json2.as_object().unwrap().keys().next().unwrap() == "e";
+        if preserves_order {
+            assert_eq!(keys, ["b", "d__deleted", "a", "e__added"]);
+        } else {
+            assert_eq!(keys, ["a", "b", "d__deleted", "e__added"]);
+        }
+    }
+
+    #[test]
+    fn test_duplicate_keys() {
+        // Duplicate keys collapse to their last value when parsed.
+        let json1: Value = serde_json::from_str(r#"{"foo": 10, "foo": 20}"#).unwrap();
+        let json2: Value = serde_json::from_str(r#"{"foo": 20, "foo": 30}"#).unwrap();
+        assert_eq!(json1, json!({"foo": 20}));
+
+        assert_eq!(JsonDiff::diff(&json1, &json2, true).diff, None);
+        assert_eq!(
+            JsonDiff::diff(&json1, &json2, false).diff,
+            Some(json!({"foo": {"__old": 20, "__new": 30}}))
+        );
+    }
+
+    #[test]
+    fn test_sort_keys() {
+        let json1: Value =
+            serde_json::from_str(r#"{"b": 1, "d": {"z": 1, "y": 1}, "a": [{"d": 1, "c": 1}]}"#)
+                .unwrap();
+        let json2: Value = serde_json::from_str(
+            r#"{"e": {"z": 1, "y": 1}, "b": 2, "a": [{"d": 2, "c": 1}, {"z": 1, "a": 1}]}"#,
+        )
+        .unwrap();
+        let options = DiffOptions {
+            sort_keys: true,
+            ..DiffOptions::default()
+        };
+
+        // The output is the same whether `serde_json/preserve_order` is
+        // enabled or not.
+        let json_diff = JsonDiff::diff_with_options(&json1, &json2, &options);
+        assert_eq!(
+            serde_json::to_string(&json_diff.diff).unwrap(),
+            concat!(
+                r#"{"a":[["~",{"d":{"__new":2,"__old":1}}],["+",{"a":1,"z":1}]],"#,
+                r#""b":{"__new":2,"__old":1},"d__deleted":{"y":1,"z":1},"e__added":{"y":1,"z":1}}"#
+            )
+        );
+    }
+
+    #[test]
+    fn test_partial_eq() {
+        let json1 = json!({"a": 1, "b": [1, 2]});
+        let json2 = json!({"a": 2, "b": [1, 2]});
+        let json_diff = JsonDiff::diff(&json1, &json2, false);
+
+        assert_eq!(json_diff, JsonDiff::diff(&json1, &json2, false));
+        assert_eq!(json_diff.clone(), json_diff);
+        assert_ne!(json_diff, JsonDiff::diff(&json1, &json1, false));
+        assert_ne!(json_diff, JsonDiff::diff(&json1, &json2, true));
+        assert!(format!("{json_diff:?}").starts_with("JsonDiff { score: "));
+
+        // The scores of the wrapped differences are `NaN`.
+        let diff = json_diff.diff.clone().unwrap();
+        assert_eq!(
+            JsonDiff::from_diff_value(diff.clone()),
+            JsonDiff::from_diff_value(diff)
+        );
+        assert_ne!(JsonDiff::from_diff_value(json!(null)), json_diff);
+    }
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn test_filter() {
+        let filtered = |json1: &Value, json2: &Value, filter| {
+            let options = DiffOptions {
+                filter,
+                ..DiffOptions::default()
+            };
+            JsonDiff::diff_with_options(json1, json2, &options).diff
+        };
+        let json1 = json!({"a": 1, "b": {"c": [1, 2, {"d": 1, "x": 1}], "e": true}, "f": null});
+        let json2 = json!({"a": 2, "b": {"c": [0, 1, {"d": 2, "x": 1}], "g": false}, "h": []});
+
+        assert_eq!(
+            filtered(&json1, &json2, DiffFilter::AddedOnly),
+            Some(
+                json!({"b": {"c": [["+", 0], [" ", 1], [" "]], "g__added": false}, "h__added": []})
+            )
+        );
+        assert_eq!(
+            filtered(&json1, &json2, DiffFilter::DeletedOnly),
+            Some(json!({
+                "b": {"c": [[" ", 1], ["-", 2], [" "]], "e__deleted": true},
+                "f__deleted": null
+            }))
+        );
+        assert_eq!(
+            filtered(&json1, &json2, DiffFilter::ChangedOnly),
+            Some(json!({
+                "a": {"__old": 1, "__new": 2},
+                "b": {"c": [[" ", 1], ["~", {"d": {"__old": 1, "__new": 2}}]]}
+            }))
+        );
+        assert_eq!(
+            filtered(&json1, &json2, DiffFilter::All),
+            JsonDiff::diff(&json1, &json2, false).diff
+        );
+
+        // A difference without any change left is `None`.
+        assert_eq!(
+            filtered(&json!({"a": 1}), &json!({"a": 2}), DiffFilter::AddedOnly),
+            None
+        );
+
+        let json1: Value =
+            serde_json::from_str(&std::fs::read_to_string("data/a.json").unwrap()).unwrap();
+        let json2: Value =
+            serde_json::from_str(&std::fs::read_to_string("data/b.json").unwrap()).unwrap();
+        let diff = filtered(&json1, &json2, DiffFilter::AddedOnly).unwrap();
+        assert_eq!(
+            diff,
+            json!({"boz": [["+", 0], [" ", 1], [" ", 4], [" ", 5], [" ", 6], ["+", 7]]})
+        );
+        let lines = colorize_to_array(&diff);
+        assert!(lines.iter().all(|line| !line.starts_with('-')));
+        assert_eq!(lines.iter().filter(|line| line.starts_with('+')).count(), 2);
+
+        // The changes left out are not spent from the budget, while the
+        // score is still that of the whole difference.
+        let (json1, json2) = (
+            json!({"a": 1, "b": 2, "c": 3}),
+            json!({"x": 1, "y": 2, "z": 3}),
+        );
+        let options = DiffOptions {
+            filter: DiffFilter::AddedOnly,
+            max_changes: Some(3),
+            ..DiffOptions::default()
+        };
+        let json_diff = JsonDiff::diff_with_options(&json1, &json2, &options);
+        assert_eq!(
+            json_diff.diff,
+            Some(json!({"x__added": 1, "y__added": 2, "z__added": 3}))
+        );
+        assert!(!json_diff.truncated);
+        assert_eq!(json_diff.score, JsonDiff::diff(&json1, &json2, false).score);
+
+        let options = DiffOptions {
+            filter: DiffFilter::DeletedOnly,
+            ..DiffOptions::default()
+        };
+        let json_diff = JsonDiff::diff_with_options(
+            &json!({"a": 1, "b": 2}),
+            &json!({"a": 1, "b": 3}),
+            &options,
+        );
+        assert_eq!(json_diff.diff, None);
+        assert!(json_diff.similarity() < 1.);
+
+        // Moves and renames are changes.
+        let options = DiffOptions {
+            filter: DiffFilter::ChangedOnly,
+            detect_moves: true,
+            detect_renames: true,
+            ..DiffOptions::default()
+        };
+        assert_eq!(
+            JsonDiff::diff_with_options(
+                &json!({"a": [1, 2, 3], "b": "value"}),
+                &json!({"a": [2, 3, 1, 4], "c": "value"}),
+                &options
+            )
+            .diff,
+            Some(json!({"a": [[" ", 2], [" ", 3], [">", 0, 2]], "b->c": null}))
+        );
+    }
+
+    #[test]
+    fn test_change_key_order() {
+        let options = DiffOptions {
+            sort_keys: true,
+            ..DiffOptions::default()
+        };
+        let json1: Value = serde_json::from_str(r#"{"a": 1, "b": {"c": "x", "d": 1}}"#).unwrap();
+        let json2: Value = serde_json::from_str(r#"{"b": {"d": 1, "c": "y"}, "a": 2}"#).unwrap();
+        let swapped1: Value = serde_json::from_str(r#"{"b": {"d": 1, "c": "x"}, "a": 1}"#).unwrap();
+        let swapped2: Value = serde_json::from_str(r#"{"a": 2, "b": {"c": "y", "d": 1}}"#).unwrap();
+
+        // The same changes are serialized alike, whatever the order of the
+        // keys of the inputs and whether `serde_json/preserve_order` is
+        // enabled or not.
+        let serialize = |json1: &Value, json2: &Value| {
+            let diff = JsonDiff::diff_with_options(json1, json2, &options).diff;
+            serde_json::to_string(&diff).unwrap()
+        };
+        let expected = r#"{"a":{"__new":2,"__old":1},"b":{"c":{"__new":"y","__old":"x"}}}"#;
+        assert_eq!(serialize(&json1, &json2), expected);
+        assert_eq!(serialize(&swapped1, &swapped2), expected);
+
+        // Without sorting, the old side comes first if the order is kept.
+        let diff = JsonDiff::diff(&json1, &json2, false).diff.unwrap();
+        let keys: Vec<&String> = diff["a"].as_object().unwrap().keys().collect();
+        let preserves_order = json2.as_object().unwrap().keys().next().unwrap() == "b";
+        if preserves_order {
+            assert_eq!(keys, ["__old", "__new"]);
+        } else {
+            assert_eq!(keys, ["__new", "__old"]);
+        }
+    }
+
+    #[test]
+    fn test_text_diff() {
+        let json1 = json!({"greeting": "hello world", "name": "foo", "id": 1});
+        let json2 = json!({"greeting": "hello brave world", "name": "foo ", "id": 2});
+        let options = DiffOptions {
+            text_diff: Some(20),
+            ..DiffOptions::default()
+        };
+
+        let json_diff = JsonDiff::diff_with_options(&json1, &json2, &options);
+        let diff = json!({
+            "greeting": {"__text": [[" ", "hello "], ["+", "brave "], [" ", "world"]]},
+            "id": {"__old": 1, "__new": 2},
+            "name": {"__text": [[" ", "foo"], ["+", " "]]}
+        });
+        assert_eq!(json_diff.diff, Some(diff.clone()));
+        assert_eq!(json_diff.tree().unwrap().to_value(), Some(diff));
+        assert_eq!(json_diff.flatten()[0].new, Some(json!("hello brave world")));
+        assert_eq!(json_diff.stats().values_changed, 3);
+        assert_eq!(
+            json_diff.to_string(),
+            concat!(
+                " {\n",
+                "   greeting: \"hello {+brave +}world\"\n",
+                "-  id: 1\n",
+                "+  id: 2\n",
+                "   name: \"foo{+ +}\"\n",
+                " }\n",
+            )
+        );
+
+        // Longer strings are replaced as a whole.
+        let options = DiffOptions {
+            text_diff: Some(15),
+            ..DiffOptions::default()
+        };
+        assert_eq!(
+            JsonDiff::diff_with_options(
+                &json!("hello world"),
+                &json!("hello brave world"),
+                &options
+            )
+            .diff,
+            Some(json!({"__old": "hello world", "__new": "hello brave world"}))
+        );
+        assert_eq!(
+            JsonDiff::diff_with_options(&json!("a\tb"), &json!("a b"), &options).to_string(),
+            " \"a[-\\t-]{+ +}b\"\n"
+        );
+    }
+
+    #[test]
+    fn test_parse_embedded_json() {
+        let json1 = json!({"payload": "{\"a\": 1, \"b\": [1, 2]}", "text": "foo"});
+        let json2 = json!({"payload": "{\"a\":2,\"b\":[1,2]}", "text": "bar"});
+        let options = DiffOptions {
+            parse_embedded_json: true,
+            ..DiffOptions::default()
+        };
+
+        let json_diff = JsonDiff::diff_with_options(&json1, &json2, &options);
+        let diff = json!({
+            "payload": {"__embedded": {"a": {"__old": 1, "__new": 2}}},
+            "text": {"__old": "foo", "__new": "bar"}
+        });
+        assert_eq!(json_diff.diff, Some(diff.clone()));
+        assert_eq!(json_diff.flatten()[0].path.to_string(), "/payload/a");
+        assert_eq!(json_diff.tree().unwrap().to_value(), Some(diff));
+        assert_eq!(
+            json_diff.to_string(),
+            concat!(
+                " {\n",
+                "   payload (embedded JSON): {\n",
+                "-    a: 1\n",
+                "+    a: 2\n",
+                "   }\n",
+                "-  text: \"foo\"\n",
+                "+  text: \"bar\"\n",
+                " }\n",
+            )
+        );
+
+        // Only the text of the embedded JSON differs.
+        assert_eq!(
+            JsonDiff::diff_with_options(&json!("[1, 2]"), &json!("[1,2]"), &options).diff,
+            None
+        );
+
+        // A string which is not an embedded object or array falls back to
+        // the comparison of the strings.
+        for (old, new) in [("{\"a\": 1}", "{\"a\": 2"), ("1", "2")] {
+            assert_eq!(
+                JsonDiff::diff_with_options(&json!(old), &json!(new), &options).diff,
+                Some(json!({"__old": old, "__new": new}))
+            );
+        }
+
+        assert_eq!(
+            JsonDiff::diff(&json1, &json2, false).diff,
+            Some(json!({
+                "payload": {
+                    "__old": "{\"a\": 1, \"b\": [1, 2]}",
+                    "__new": "{\"a\":2,\"b\":[1,2]}"
+                },
+                "text": {"__old": "foo", "__new": "bar"}
+            }))
+        );
+    }
+
+    #[test]
+    fn test_non_finite_numbers() {
+        // `serde_json` stores non-finite floats as `null`.
+        assert_eq!(json!(f64::NAN), Value::Null);
+        assert_eq!(json!(f64::INFINITY), Value::Null);
+        assert_eq!(
+            JsonDiff::diff(&json!([f64::NAN]), &json!([f64::NAN]), false).diff,
+            None
+        );
+
+        for epsilon in [0., 1e-9, f64::MAX] {
+            assert!(JsonDiff::floats_equal(f64::NAN, f64::NAN, epsilon));
+            assert!(!JsonDiff::floats_equal(f64::NAN, 1., epsilon));
+            assert!(JsonDiff::floats_equal(
+                f64::INFINITY,
+                f64::INFINITY,
+                epsilon
+            ));
+            assert!(!JsonDiff::floats_equal(
+                f64::INFINITY,
+                f64::NEG_INFINITY,
+                epsilon
+            ));
+            assert!(!JsonDiff::floats_equal(f64::INFINITY, f64::MAX, epsilon));
+        }
+        assert!(JsonDiff::floats_equal(1., 1., f64::NAN));
+
+        let options = DiffOptions {
+            epsilon: Some(0.5),
+            ..DiffOptions::default()
+        };
+        let diff =
+            |json1: &Value, json2: &Value| JsonDiff::diff_with_options(json1, json2, &options).diff;
+        // These integers have the same `f64` representation.
+        assert!(diff(&json!(u64::MAX), &json!(u64::MAX - 1)).is_some());
+        assert!(diff(&json!(i64::MIN), &json!(u64::MAX)).is_some());
+        assert!(diff(&json!(u64::MAX), &json!(u64::MAX)).is_none());
+        assert!(diff(&json!(f64::MAX), &json!(f64::MAX)).is_none());
+        assert!(diff(&json!(f64::MAX), &json!(-f64::MAX)).is_some());
+        assert!(diff(&json!(1), &json!(1.25)).is_none());
+
+        let options = DiffOptions {
+            epsilon: Some(f64::NAN),
+            ..DiffOptions::default()
+        };
+        assert!(
+            JsonDiff::diff_with_options(&json!(1.5), &json!(1.5), &options)
+                .diff
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_coerce_numeric_strings() {
+        let options = DiffOptions {
+            coerce_numeric_strings: true,
+            ..DiffOptions::default()
+        };
+        let diff_coerced =
+            |json1: &Value, json2: &Value| JsonDiff::diff_with_options(json1, json2, &options).diff;
+
+        // By default, a numeric string is a change of type.
+        assert_eq!(
+            JsonDiff::diff(&json!({"a": "1"}), &json!({"a": 1}), false).diff,
+            Some(json!({"a": {"__old": "1", "__new": 1}}))
+        );
+        assert_eq!(diff_coerced(&json!({"a": "1"}), &json!({"a": 1})), None);
+        assert_eq!(diff_coerced(&json!(-2.5), &json!("-2.5")), None);
+        assert_eq!(diff_coerced(&json!("1.0"), &json!(1)), None);
+        assert_eq!(diff_coerced(&json!("1e3"), &json!(1000)), None);
+        assert_eq!(diff_coerced(&json!(["1", 2]), &json!([1, "2"])), None);
+
+        // The strings which are not JSON numbers are not coerced.
+        for string in ["01", "+1", " 1", "1 ", "1.", ".5", "0x1", "one", ""] {
+            assert_eq!(
+                diff_coerced(&json!(string), &json!(1)),
+                Some(json!({"__old": string, "__new": 1}))
+            );
+        }
+        assert_eq!(
+            diff_coerced(&json!("2"), &json!(1)),
+            Some(json!({"__old": "2", "__new": 1}))
+        );
+        // Two strings are still compared as strings.
+        assert_eq!(
+            diff_coerced(&json!("1"), &json!("1.0")),
+            Some(json!({"__old": "1", "__new": "1.0"}))
+        );
+
+        let options = DiffOptions {
+            epsilon: Some(0.01),
+            ..options
+        };
+        assert_eq!(
+            JsonDiff::diff_with_options(&json!("1.001"), &json!(1), &options).diff,
+            None
+        );
+    }
+
+    #[test]
+    fn test_numbers_by_value() {
+        let options = DiffOptions {
+            numbers_by_value: true,
+            ..DiffOptions::default()
+        };
+        let diff_by_value =
+            |json1: &Value, json2: &Value| JsonDiff::diff_with_options(json1, json2, &options).diff;
+
+        // By default, the representation of numbers matters.
+        assert_eq!(
+            JsonDiff::diff(&json!(1), &json!(1.0), false).diff,
+            Some(json!({"__old": 1, "__new": 1.0}))
+        );
+        assert_eq!(diff_by_value(&json!(1), &json!(1.0)), None);
+        assert_eq!(diff_by_value(&json!(-1.0), &json!(-1)), None);
+        assert_eq!(diff_by_value(&json!(0.0), &json!(-0.0)), None);
+        assert_eq!(diff_by_value(&json!(u64::MAX), &json!(u64::MAX)), None);
+        assert_eq!(
+            diff_by_value(&json!(1), &json!(1.5)),
+            Some(json!({"__old": 1, "__new": 1.5}))
+        );
+        assert_eq!(
+            diff_by_value(&json!(-1), &json!(u64::MAX)),
+            Some(json!({"__old": -1, "__new": u64::MAX}))
+        );
+        assert_eq!(
+            diff_by_value(&json!(1), &json!("1")),
+            Some(json!({"__old": 1, "__new": "1"}))
+        );
+
+        // 2^53 + 1 has no float representation, so it is not rounded to
+        // the closest float, 2^53.
+        let two_pow_53: u64 = 1 << 53;
+        assert_eq!(
+            diff_by_value(&json!(two_pow_53), &json!(9_007_199_254_740_992.0)),
+            None
+        );
+        assert_eq!(
+            diff_by_value(&json!(two_pow_53 + 1), &json!(9_007_199_254_740_992.0)),
+            Some(json!({"__old": two_pow_53 + 1, "__new": 9_007_199_254_740_992.0}))
+        );
+        assert_eq!(
+            diff_by_value(&json!(two_pow_53 + 1), &json!(two_pow_53)),
+            Some(json!({"__old": two_pow_53 + 1, "__new": two_pow_53}))
+        );
+        // A float parsed from the same digits is rounded though.
+        let parsed: Value = serde_json::from_str("9007199254740993.0").unwrap();
+        assert!(diff_by_value(&json!(two_pow_53 + 1), &parsed).is_some());
+        // Floats beyond the range of the integers.
+        assert_eq!(
+            diff_by_value(&json!(u64::MAX), &json!(1e30)),
+            Some(json!({"__old": u64::MAX, "__new": 1e30}))
+        );
+
+        // Equal numbers are matched within arrays too.
+        assert_eq!(
+            diff_by_value(&json!([1, 2.5, 3]), &json!([1.0, 2.5, 3.0])),
+            None
+        );
+    }
+
+    #[test]
+    fn test_epsilon() {
+        let options = DiffOptions {
+            epsilon: Some(1e-9),
+            ..DiffOptions::default()
+        };
+
+        assert_eq!(
+            JsonDiff::diff(&json!(0.1 + 0.2), &json!(0.3), false).diff,
+            Some(json!({"__old": 0.1 + 0.2, "__new": 0.3 }))
+        );
+
+        let json_diff = JsonDiff::diff_with_options(&json!(0.1 + 0.2), &json!(0.3), &options);
+        assert_eq!(json_diff.diff, None);
+        assert!((json_diff.score - 100.).abs() < f64::EPSILON);
+
+        assert_eq!(
+            JsonDiff::diff(&json!(2), &json!(2.0), false).diff,
+            Some(json!({"__old": 2, "__new": 2.0 }))
+        );
+        assert_eq!(
+            JsonDiff::diff_with_options(&json!(2), &json!(2.0), &options).diff,
+            None
+        );
+
+        assert_eq!(
+            JsonDiff::diff_with_options(&json!(0.3), &json!(0.4), &options).diff,
+            Some(json!({"__old": 0.3, "__new": 0.4 }))
+        );
+
+        assert_eq!(
+            JsonDiff::diff_with_options(&json!("0.3"), &json!(0.3), &options).diff,
+            Some(json!({"__old": "0.3", "__new": 0.3 }))
+        );
+
+        assert_eq!(
+            JsonDiff::diff_with_options(
+                &json!({"foo": 0.1 + 0.2, "bar": [1, 2.5, {"baz": 3}]}),
+                &json!({"foo": 0.3, "bar": [1.0, 2.5, {"baz": 3.0}]}),
+                &options
+            )
+            .diff,
+            None
+        );
+
+        assert_eq!(
+            JsonDiff::diff_with_options(
+                &json!({"foo": 0.1 + 0.2, "bar": {"baz": 3}}),
+                &json!({"foo": 0.3, "bar": {"baz": 4}}),
+                &options
+            )
+            .diff,
+            Some(json!({"bar": {"baz": {"__old": 3, "__new": 4 } } }))
+        );
+    }
+
+    #[test]
+    fn test_ignore_keys() {
+        let options = DiffOptions {
+            ignore_keys: vec!["timestamp".to_owned(), "/metadata/_etag".to_owned()],
+            ..DiffOptions::default()
+        };
+
+        assert_eq!(
+            JsonDiff::diff_with_options(
+                &json!({"foo": 42, "timestamp": 1}),
+                &json!({"foo": 42, "timestamp": 2}),
+                &options
+            )
+            .diff,
+            None
+        );
+
+        assert_eq!(
+            JsonDiff::diff_with_options(
+                &json!({"foo": 42, "timestamp": 1}),
+                &json!({"foo": 42}),
+                &options
+            )
+            .diff,
+            None
+        );
+
+        assert_eq!(
+            JsonDiff::diff_with_options(
+                &json!({"foo": 42}),
+                &json!({"foo": 42, "bar": {"timestamp": 2}}),
+                &options
+            )
+            .diff,
+            Some(json!({"bar__added": {"timestamp": 2}}))
+        );
+
+        assert_eq!(
+            JsonDiff::diff_with_options(
+                &json!({"metadata": {"_etag": "a", "timestamp": 1, "name": "foo"}}),
+                &json!({"metadata": {"timestamp": 2, "name": "bar"}, "_etag": "b"}),
+                &options
+            )
+            .diff,
+            Some(json!({
+                "_etag__added": "b",
+                "metadata": {"name": {"__old": "foo", "__new": "bar"}}
+            }))
+        );
+
+        assert_eq!(
+            JsonDiff::diff_with_options(
+                &json!([
+                    {"id": 1, "foo": 10, "timestamp": 1},
+                    {"id": 2, "foo": 20, "timestamp": 2}
+                ]),
+                &json!([
+                    {"id": 1, "foo": 10, "timestamp": 3},
+                    {"id": 2, "foo": 21, "timestamp": 4}
+                ]),
+                &options
+            )
+            .diff,
+            Some(json!([[' '], ['~', {"foo": {"__old": 20, "__new": 21}}]]))
+        );
+
+        let ignored = JsonDiff::diff_with_options(
+            &json!({"foo": 42, "bar": 10, "timestamp": 1}),
+            &json!({"foo": 42, "bar": 11, "last_modified": 2}),
+            &DiffOptions {
+                ignore_keys: vec!["timestamp".to_owned(), "last_modified".to_owned()],
+                ..DiffOptions::default()
+            },
+        );
+        let expected = JsonDiff::diff(
+            &json!({"foo": 42, "bar": 10}),
+            &json!({"foo": 42, "bar": 11}),
+            false,
+        );
+        assert_eq!(ignored.diff, expected.diff);
+        assert!((ignored.score - expected.score).abs() < f64::EPSILON);
+
+        let ignored = JsonDiff::diff_with_options(
+            &json!({"foo": 42, "timestamp": 1}),
+            &json!({"foo": 42, "timestamp": 2}),
+            &options,
+        );
+        let expected = JsonDiff::diff(&json!({"foo": 42}), &json!({"foo": 42}), false);
+        assert!((ignored.score - expected.score).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_keys_only_unequal_replace() {
+        // The middle items form a `replace` opcode from 2 to 3 items.
+        let json1 = json!([0, 1, 2, 9]);
+        let json2 = json!([0, 3, 4, 5, 9]);
+        let json_diff = JsonDiff::diff(&json1, &json2, true);
+        assert_eq!(
+            json_diff.diff,
+            Some(json!([[' ', 0], ' ', ' ', ['+', 5], [' ', 9]]))
+        );
+        assert_eq!(
+            JsonDiff::diff(&json2, &json1, true).diff,
+            Some(json!([[' ', 0], ' ', ' ', ['-', 5], [' ', 9]]))
+        );
+        assert_eq!(
+            JsonDiff::diff_with_options(
+                &json1,
+                &json2,
+                &DiffOptions {
+                    include_indices: true,
+                    ..DiffOptions::new(true)
+                }
+            )
+            .diff,
+            Some(json!([[' ', 0], ' ', ' ', ['+', 5, 3], [' ', 9]]))
+        );
+    }
+
+    #[test]
+    fn test_show_context_keys() {
+        let json1 = json!({"id": 1, "name": "foo", "tags": ["a"], "meta": {"a": 1}});
+        let json2 = json!({"id": 1, "name": "bar", "tags": ["a"], "meta": {"a": 1}});
+        let options = DiffOptions {
+            show_context_keys: true,
+            ..DiffOptions::default()
+        };
+
+        let json_diff = JsonDiff::diff_with_options(&json1, &json2, &options);
+        assert_eq!(
+            json_diff.diff,
+            Some(json!({
+                "id__unchanged": 1,
+                "meta__unchanged": {"a": 1},
+                "name": {"__old": "foo", "__new": "bar"},
+                "tags__unchanged": ["a"]
+            }))
+        );
+        assert_eq!(
+            json_diff.to_string(),
+            concat!(
+                " {\n",
+                "   id: 1\n",
+                "   meta: {\n",
+                "     a: 1\n",
+                "   }\n",
+                "-  name: \"foo\"\n",
+                "+  name: \"bar\"\n",
+                "   tags: [\n",
+                "     \"a\"\n",
+                "   ]\n",
+                " }\n",
+            )
+        );
+        assert_eq!(json_diff.flatten().len(), 1);
+        assert_eq!(json_diff.stats_by_key().len(), 1);
+
+        // The unchanged objects are not shown.
+        assert_eq!(
+            JsonDiff::diff_with_options(&json1, &json1, &options).diff,
+            None
+        );
+    }
+
+    #[test]
+    fn test_types_only() {
+        let options = DiffOptions {
+            types_only: true,
+            ..DiffOptions::default()
+        };
+        let diff =
+            |json1: Value, json2: Value| JsonDiff::diff_with_options(&json1, &json2, &options).diff;
+
+        assert_eq!(diff(json!(42), json!(10)), None);
+        assert_eq!(
+            diff(json!(42), json!("42")),
+            Some(json!({"__old": 42, "__new": "42"}))
+        );
+        assert_eq!(
+            diff(json!([1]), json!({"a": 1})),
+            Some(json!({"__old": [1], "__new": {"a": 1}}))
+        );
+        assert_eq!(
+            diff(
+                json!({"a": {"b": 1, "c": [1, 2], "d": null}}),
+                json!({"a": {"b": 2, "c": [3, "4"], "d": false}, "e": 1})
+            ),
+            Some(json!({
+                "a": {
+                    "c": [' ', ['~', {"__old": 2, "__new": "4"}]],
+                    "d": {"__old": null, "__new": false}
+                },
+                "e__added": 1
+            }))
+        );
+        // Comparing only the keys ignores the types too.
+        let options = DiffOptions {
+            keys_only: true,
+            ..options
+        };
+        assert_eq!(
+            JsonDiff::diff_with_options(&json!(42), &json!("42"), &options).diff,
+            None
+        );
+    }
+
+    #[test]
+    fn test_null_equals_missing() {
+        let options = DiffOptions {
+            null_equals_missing: true,
+            ..DiffOptions::default()
+        };
+
+        let json_diff = JsonDiff::diff_with_options(&json!({"a": null}), &json!({}), &options);
+        assert_eq!(json_diff.diff, None);
+        let reversed = JsonDiff::diff_with_options(&json!({}), &json!({"a": null}), &options);
+        assert_eq!(reversed.diff, None);
+        assert!((json_diff.score - reversed.score).abs() < f64::EPSILON);
+        assert!((reversed.similarity() - 1.).abs() < f64::EPSILON);
+
+        assert_eq!(
+            JsonDiff::diff_with_options(&json!({"a": 1}), &json!({}), &options).diff,
+            Some(json!({"a__deleted": 1}))
+        );
+        assert_eq!(
+            JsonDiff::diff_with_options(&json!({"a": null}), &json!({"a": 1}), &options).diff,
+            Some(json!({"a": {"__old": null, "__new": 1}}))
+        );
+        assert_eq!(
+            JsonDiff::diff_with_options(
+                &json!({"foo": {"a": null, "b": 1}}),
+                &json!({"foo": {"b": 2, "c": null}}),
+                &options
+            )
+            .diff,
+            Some(json!({"foo": {"b": {"__old": 1, "__new": 2}}}))
+        );
+
+        // By default, a `null` differs from a missing key.
+        assert_eq!(
+            JsonDiff::diff(&json!({"a": null}), &json!({}), false).diff,
+            Some(json!({"a__deleted": null}))
+        );
+    }
+
+    #[test]
+    fn test_strict_arrays() {
+        let json1 = json!([
+            {"foo": 10, "bar": {"bbbar": 10, "bbboz": 11}},
+            {"foo": 20, "bar": {"bbbar": 50, "bbboz": 25}},
+            {"foo": 30, "bar": {"bbbar": 92, "bbboz": 34}}
+        ]);
+        let json2 = json!([
+            {"foo": 10, "bar": {"bbbar": 10, "bbboz": 11}},
+            {"foo": 21, "bar": {"bbbar": 50, "bbboz": 25}},
+            {"foo": 30, "bar": {"bbbar": 92, "bbboz": 34}}
+        ]);
+
+        // By default, the similar objects are matched.
+        assert_eq!(
+            JsonDiff::diff(&json1, &json2, false).diff,
+            Some(json!([
+                [' '],
+                ['~', {"foo": {"__old": 20, "__new": 21}}],
+                [' ']
+            ]))
+        );
+
+        let options = DiffOptions {
+            strict_arrays: true,
+            ..DiffOptions::default()
+        };
+        assert_eq!(
+            JsonDiff::diff_with_options(&json1, &json2, &options).diff,
+            Some(json!([
+                [' '],
+                ['-', {"foo": 20, "bar": {"bbbar": 50, "bbboz": 25}}],
+                ['+', {"foo": 21, "bar": {"bbbar": 50, "bbboz": 25}}],
+                [' ']
+            ]))
+        );
+        assert_eq!(
+            JsonDiff::diff_with_options(&json1, &json1, &options).diff,
+            None
+        );
+
+        // The objects equal with the options are still matched, in order.
+        let options = DiffOptions {
+            strict_arrays: true,
+            ignore_keys: vec!["t".into()],
+            ..DiffOptions::default()
+        };
+        assert_eq!(
+            JsonDiff::diff_with_options(
+                &json!([{"a": 1, "t": 1}, {"a": 1, "t": 2}, {"a": 2}]),
+                &json!([{"a": 1, "t": 3}, {"a": 3}, {"a": 1, "t": 4}]),
+                &options
+            )
+            .diff,
+            Some(json!([[' '], ['+', {"a": 3}], [' '], ['-', {"a": 2}]]))
+        );
+
+        // The identified objects are still matched by their identity.
+        let options = DiffOptions {
+            strict_arrays: true,
+            array_keys: vec!["id".into()],
+            ..DiffOptions::default()
+        };
+        assert_eq!(
+            JsonDiff::diff_with_options(
+                &json!([{"id": 1, "x": 1}]),
+                &json!([{"id": 1, "x": 2}]),
+                &options
+            )
+            .diff,
+            Some(json!([['~', {"x": {"__old": 1, "__new": 2}}]]))
+        );
+    }
+
+    #[test]
+    fn test_values_only() {
+        let options = DiffOptions {
+            values_only: true,
+            ..DiffOptions::default()
+        };
+
+        assert_eq!(
+            JsonDiff::diff_with_options(
+                &json!({"a": 1, "b": 2}),
+                &json!({"a": 9, "c": 3}),
+                &options
+            )
+            .diff,
+            Some(json!({"a": {"__old": 1, "__new": 9}}))
+        );
+        assert_eq!(
+            JsonDiff::diff_with_options(
+                &json!({"foo": {"a": 1, "b": 2}, "bar": 1}),
+                &json!({"foo": {"a": 2, "c": 3}, "baz": 1}),
+                &options
+            )
+            .diff,
+            Some(json!({"foo": {"a": {"__old": 1, "__new": 2}}}))
+        );
+
+        // Objects without any shared key are equal.
+        let json_diff = JsonDiff::diff_with_options(&json!({"a": 1}), &json!({"b": 2}), &options);
+        assert_eq!(json_diff.diff, None);
+        assert!((json_diff.similarity() - 1.).abs() < f64::EPSILON);
+
+        // The items of arrays are still added and deleted.
+        assert_eq!(
+            JsonDiff::diff_with_options(&json!({"a": [1]}), &json!({"a": [1, 2]}), &options).diff,
+            Some(json!({"a": [[" ", 1], ["+", 2]]}))
+        );
+    }
+
+    #[test]
+    fn test_empty_equals_missing() {
+        let options = DiffOptions {
+            empty_equals_missing: true,
+            ..DiffOptions::default()
+        };
+
+        for (json1, json2) in [
+            (json!({"tags": []}), json!({})),
+            (json!({}), json!({"tags": []})),
+            (json!({"meta": {}}), json!({})),
+            (json!({}), json!({"meta": {}})),
+            (json!({"tags": []}), json!({"tags": []})),
+        ] {
+            let json_diff = JsonDiff::diff_with_options(&json1, &json2, &options);
+            assert_eq!(json_diff.diff, None);
+            assert!((json_diff.similarity() - 1.).abs() < f64::EPSILON);
+        }
+
+        assert_eq!(
+            JsonDiff::diff_with_options(&json!({"tags": [1]}), &json!({}), &options).diff,
+            Some(json!({"tags__deleted": [1]}))
+        );
+        assert_eq!(
+            JsonDiff::diff_with_options(&json!({}), &json!({"meta": {"a": 1}}), &options).diff,
+            Some(json!({"meta__added": {"a": 1}}))
+        );
+        assert_eq!(
+            JsonDiff::diff_with_options(&json!({"tags": []}), &json!({"tags": {}}), &options).diff,
+            Some(json!({"tags": {"__old": [], "__new": {}}}))
+        );
+        assert_eq!(
+            JsonDiff::diff_with_options(
+                &json!({"foo": {"tags": [], "b": 1}}),
+                &json!({"foo": {"b": 2, "meta": {}}}),
+                &options
+            )
+            .diff,
+            Some(json!({"foo": {"b": {"__old": 1, "__new": 2}}}))
+        );
+
+        // A `null` still differs from a missing key, unless
+        // `null_equals_missing` is set too.
+        assert_eq!(
+            JsonDiff::diff_with_options(&json!({"a": null}), &json!({}), &options).diff,
+            Some(json!({"a__deleted": null}))
+        );
+
+        // By default, an empty array differs from a missing key.
+        assert_eq!(
+            JsonDiff::diff(&json!({"tags": []}), &json!({}), false).diff,
+            Some(json!({"tags__deleted": []}))
+        );
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn test_parallel() {
+        let generate = |offset: u64| {
+            let mut obj = serde_json::Map::new();
+            for index in 0..64_u64 {
+                let items: Vec<Value> = (0..72_u64)
+                    .map(|item| {
+                        json!({
+                            "id": item,
+                            "name": format!("item{}", (item * 7 + index) % 100),
+                            "value": (item + index * offset) % 13,
+                        })
+                    })
+                    .filter(|item| item["value"] != json!(offset))
+                    .collect();
+                obj.insert(
+                    format!("key{index}"),
+                    json!({"items": items, "index": index}),
+                );
+            }
+            Value::Object(obj)
+        };
+        let json1 = generate(3);
+        let json2 = generate(5);
+
+        let parallel = JsonDiff::diff_with_options(&json1, &json2, &DiffOptions::default());
+        let serial = JsonDiff::diff_with_options(
+            &json1,
+            &json2,
+            &DiffOptions {
+                parallel: false,
+                ..DiffOptions::default()
+            },
+        );
+        assert!(parallel.diff.is_some());
+        assert_eq!(parallel.diff, serial.diff);
+        assert_eq!(parallel.score.to_bits(), serial.score.to_bits());
+        assert_eq!(
+            parallel.similarity().to_bits(),
+            serial.similarity().to_bits()
+        );
+    }
+
+    #[test]
+    fn test_detect_renames() {
+        let options = DiffOptions {
+            detect_renames: true,
+            ..DiffOptions::default()
+        };
+
+        // A clean rename.
+        let json_diff = JsonDiff::diff_with_options(
+            &json!({"color": "red", "size": 1}),
+            &json!({"colour": "red", "size": 1}),
+            &options,
+        );
+        assert_eq!(json_diff.diff, Some(json!({"color->colour": null})));
+        assert_eq!(
+            crate::colorize_to_array(json_diff.diff.as_ref().unwrap()),
+            [" {", ">  color -> colour", " }"]
+        );
+        assert_eq!(json_diff.stats().keys_renamed, 1);
+
+        // A rename with a value change.
+        let json_diff = JsonDiff::diff_with_options(
+            &json!({"address": {"street": "a", "city": "b", "zip": 1}}),
+            &json!({"location": {"street": "a", "city": "b", "zip": 2}}),
+            &options,
+        );
+        assert_eq!(
+            json_diff.diff,
+            Some(json!({"address->location": {"zip": {"__old": 1, "__new": 2}}}))
+        );
+        assert_eq!(
+            crate::colorize_to_array(json_diff.diff.as_ref().unwrap()),
+            [
+                " {",
+                "   address -> location: {",
+                "-    zip: 1",
+                "+    zip: 2",
+                "   }",
+                " }"
+            ]
+        );
+        assert_eq!(json_diff.tree().unwrap().to_value(), json_diff.diff.clone());
+        assert_eq!(
+            json_diff.invert(),
+            Some(json!({"location->address": {"zip": {"__old": 2, "__new": 1}}}))
+        );
+
+        // Equal values are paired first, and different scalars are never
+        // paired.
+        let json_diff = JsonDiff::diff_with_options(
+            &json!({"a": {"x": 1, "y": 2, "z": 3}, "b": 1, "c": "foo"}),
+            &json!({"d": {"x": 1, "y": 2, "z": 4}, "e": {"x": 1, "y": 2, "z": 3}, "f": "bar"}),
+            &options,
+        );
+        assert_eq!(
+            json_diff.diff,
+            Some(json!({
+                "a->e": null,
+                "b__deleted": 1,
+                "c__deleted": "foo",
+                "d__added": {"x": 1, "y": 2, "z": 4},
+                "f__added": "bar"
+            }))
+        );
+
+        // The keys which would need to be escaped are never paired, and
+        // keys containing the separator are escaped.
+        assert_eq!(
+            JsonDiff::diff_with_options(&json!({"a->b": 1}), &json!({"c": 1}), &options).diff,
+            Some(json!({"a->b__escaped__deleted": 1, "c__added": 1}))
+        );
+        assert_eq!(
+            JsonDiff::diff(&json!({"color": "red"}), &json!({"colour": "red"}), false).diff,
+            Some(json!({"color__deleted": "red", "colour__added": "red"}))
+        );
+    }
+
+    #[test]
+    fn test_markers() {
+        // Keys which look like markers are escaped, and read back as is.
+        let json1 = json!({"count__added": 1, "__old": 2, "__new": 3, "foo": {"bar__deleted": 4}});
+        let json2 = json!({"__old": 5, "__new": 6, "foo": {"bar__deleted": 7}, "baz__added": 8});
+        let diff = JsonDiff::diff(&json1, &json2, false).diff.unwrap();
+        assert_eq!(
+            diff,
+            json!({
+                "count__added__escaped__deleted": 1,
+                "__old__escaped": {"__old": 2, "__new": 5},
+                "__new__escaped": {"__old": 3, "__new": 6},
+                "foo": {"bar__deleted__escaped": {"__old": 4, "__new": 7}},
+                "baz__added__escaped__added": 8
+            })
+        );
+        assert_eq!(
+            crate::colorize_to_array(&diff),
+            [
+                " {",
+                "-  __new: 3",
+                "+  __new: 6",
+                "-  __old: 2",
+                "+  __old: 5",
+                "+  baz__added: 8",
+                "-  count__added: 1",
+                "   foo: {",
+                "-    bar__deleted: 4",
+                "+    bar__deleted: 7",
+                "   }",
+                " }"
+            ]
+        );
+
+        let options = DiffOptions {
+            markers: DiffMarkers {
+                old: "<".to_owned(),
+                new: ">".to_owned(),
+                added: "+".to_owned(),
+                deleted: "-".to_owned(),
+                unchanged: "=".to_owned(),
+                escaped: "!".to_owned(),
+                renamed: "=>".to_owned(),
+                embedded: "@".to_owned(),
+                text: "~".to_owned(),
+                score: "%".to_owned(),
+            },
+            ..DiffOptions::default()
+        };
+        let json1 = json!({"foo": 1, "bar": 2, "a-": 3});
+        let json2 = json!({"foo": 4, "baz": 5, "a-": 3});
+        let json_diff = JsonDiff::diff_with_options(&json1, &json2, &options);
+        let diff = json_diff.diff.clone().unwrap();
+        assert_eq!(diff, json!({"foo": {"<": 1, ">": 4}, "bar-": 2, "baz+": 5}));
+
+        // The markers are kept, to format, parse and convert the difference.
+        let lines = [
+            " {",
+            "-  bar: 2",
+            "+  baz: 5",
+            "-  foo: 1",
+            "+  foo: 4",
+            " }",
+        ];
+        assert_eq!(json_diff.to_string().lines().collect::<Vec<_>>(), lines);
+        let context_options = crate::ContextOptions {
+            markers: options.markers.clone(),
+            ..crate::ContextOptions::default()
+        };
+        assert_eq!(
+            crate::colorize_to_array_with(&diff, &context_options),
+            lines
+        );
+        assert_eq!(json_diff.flatten().len(), 3);
+        assert_eq!(
+            json_diff.stats(),
+            JsonDiff::diff(&json1, &json2, false).stats()
+        );
+        assert_eq!(
+            json_diff.invert(),
+            Some(json!({"foo": {"<": 4, ">": 1}, "bar+": 2, "baz-": 5}))
+        );
+        assert_eq!(
+            json_diff.to_json_patch(),
+            JsonDiff::diff(&json1, &json2, false).to_json_patch()
+        );
+        assert_eq!(
+            JsonDiff::from_diff_value(diff)
+                .with_markers(options.markers.clone())
+                .to_string(),
+            json_diff.to_string()
+        );
+        assert_eq!(
+            JsonDiff::diff_with_options(&json!({"a-": 3}), &json!({}), &options).diff,
+            Some(json!({"a-!-": 3}))
+        );
+    }
+
+    #[test]
+    fn test_try_diff() {
+        let json1 = json!({"foo": [{"bar": 10, "baz": 11}, 2, 3]});
+        let json2 = json!({"foo": [{"bar": 10, "baz": 12}, 3]});
+        let json_diff = JsonDiff::try_diff(&json1, &json2, false).unwrap();
+        assert_eq!(json_diff.diff, JsonDiff::diff(&json1, &json2, false).diff);
+
+        // A crafted JSON file nested deeper than the default limit is
+        // rejected instead of overflowing the stack.
+        let mut crafted = json!(1);
+        for _ in 0..200 {
+            crafted = Value::Array(vec![crafted]);
+        }
+        assert!(matches!(
+            JsonDiff::try_diff(&crafted, &json1, false),
+            Err(DiffError::TooDeep(128))
+        ));
+        assert!(matches!(
+            JsonDiff::try_diff(&json1, &crafted, true),
+            Err(DiffError::TooDeep(128))
+        ));
+
+        // The errors are returned instead of panicking.
+        let options = DiffOptions {
+            max_nesting: Some(2),
+            ..DiffOptions::default()
+        };
+        assert!(matches!(
+            JsonDiff::try_diff_with_options(&json1, &json2, &options),
+            Err(DiffError::TooDeep(2))
+        ));
+    }
+
+    #[test]
+    fn test_scalarize_arrays() {
+        let array1 = [json!({"foo": 1, "bar": 2}), json!(2), json!([3])];
+        let array2 = [json!({"baz": 5}), json!({"foo": 1, "bar": 4}), json!([3])];
+        let scalarized =
+            JsonDiff::scalarize_arrays(&array1, &array2, &DiffOptions::default(), "").unwrap();
+
+        // The proxies are numbered across both arrays and shared by matches.
+        assert_eq!(scalarized.seq1, ["__$!SCALAR1", "2", "[3]"]);
+        assert_eq!(scalarized.seq2, ["__$!SCALAR2", "__$!SCALAR1", "[3]"]);
+
+        // The items are borrowed from the arrays rather than cloned.
+        assert!(core::ptr::eq(
+            scalarized.originals1["__$!SCALAR1"],
+            &array1[0]
+        ));
+        assert!(core::ptr::eq(
+            scalarized.originals2["__$!SCALAR1"],
+            &array2[1]
+        ));
+    }
+
+    #[test]
+    fn test_scalarize_duplicates() {
+        // Equal scalars share a key, so that the sequence matcher pairs them.
+        let array1 = [json!("a"), json!("a"), json!("b"), json!("a")];
+        let array2 = [json!("a"), json!("b"), json!("b"), json!("a")];
+        let scalarized =
+            JsonDiff::scalarize_arrays(&array1, &array2, &DiffOptions::default(), "").unwrap();
+        assert_eq!(scalarized.seq1, ["\"a\"", "\"a\"", "\"b\"", "\"a\""]);
+        assert_eq!(scalarized.seq2, ["\"a\"", "\"b\"", "\"b\"", "\"a\""]);
+        assert_eq!(
+            JsonDiff::diff(&json!(array1), &json!(array2), false).diff,
+            Some(json!([
+                ["-", "a"],
+                [" ", "a"],
+                [" ", "b"],
+                ["+", "b"],
+                [" ", "a"]
+            ]))
+        );
+
+        // Scalars equal within the options share a key too, but each item
+        // keeps its own value.
+        let options = DiffOptions {
+            epsilon: Some(0.5),
+            ..DiffOptions::default()
+        };
+        assert_eq!(
+            JsonDiff::diff_with_options(&json!([5, 1.0]), &json!([1.2, 5, 0.8]), &options).diff,
+            Some(json!([["+", 1.2], [" ", 5], [" ", 1.0]]))
+        );
+        let options = DiffOptions {
+            case_insensitive: true,
+            ..DiffOptions::default()
+        };
+        assert_eq!(
+            JsonDiff::diff_with_options(&json!(["b", "a"]), &json!(["A", "b", "a"]), &options).diff,
+            Some(json!([["+", "A"], [" ", "b"], [" ", "a"]]))
+        );
+
+        // A string equal to a proxy does not collide with it.
+        let array1 = [json!({"id": 1}), json!("__$!SCALAR1")];
+        let array2 = [json!("__$!SCALAR1"), json!({"id": 1})];
+        assert_eq!(
+            JsonDiff::diff(&json!(array1), &json!(array2), false).diff,
+            Some(json!([["+", "__$!SCALAR1"], [" "], ["-", "__$!SCALAR1"]]))
+        );
+    }
+
+    #[test]
+    fn test_mismatched_key() {
+        // A key associated to an object of the first array, and to a scalar
+        // of the second one, breaks the scalarization: the items are then
+        // reported as replaced instead of panicking.
+        let array1 = [json!({"foo": 1}), json!(2)];
+        let array2 = [json!("bar"), json!(2)];
+        let key = "__$!SCALAR1".to_owned();
+        let scalarized = super::ScalarizedArrays {
+            seq1: vec![key.clone(), "2".to_owned()],
+            seq2: vec![key.clone(), "2".to_owned()],
+            array1: &array1,
+            array2: &array2,
+            originals1: BTreeMap::from([(key, &array1[0])]),
+            originals2: BTreeMap::new(),
+            match_scores: BTreeMap::new(),
+        };
+        let options = DiffOptions {
+            include_indices: true,
+            ..DiffOptions::default()
+        };
+        let json_diff =
+            JsonDiff::scalarized_array_diff(scalarized, &options, "", &ChangeBudget::unlimited())
+                .unwrap();
+        assert_eq!(
+            json_diff.diff,
+            Some(json!([["-", {"foo": 1}, 0], ["+", "bar", 0], [" ", 2]]))
+        );
+    }
+
+    #[cfg(feature = "colorize")]
+    #[test]
+    fn test_diff_string_colored() {
+        console::set_colors_enabled(true);
+
+        let json1 = json!({"foo": 42});
+        let json2 = json!({"foo": 10});
+        assert_eq!(
+            JsonDiff::diff_string_colored(&json1, &json2, false, true).unwrap(),
+            " {\n\u{1b}[31m-  foo: 42\u{1b}[0m\n\u{1b}[32m+  foo: 10\u{1b}[0m\n }\n"
+        );
+        assert_eq!(
+            JsonDiff::diff_string_colored(&json1, &json2, false, false),
+            JsonDiff::diff_string(&json1, &json2, false)
+        );
+        assert!(JsonDiff::diff_string_colored(&json1, &json1, false, true).is_none());
+    }
+
+    #[test]
+    fn test_diff_string() {
+        fn read_json_file(filename: &str) -> Result<serde_json::Value, Box<dyn Error>> {
+            // Get path
+            let path = Path::new(filename);
+
+            // Open the file in read-only mode with buffer.
+            let file = File::open(path)?;
+            let reader = BufReader::new(file);
+
+            // Read the JSON contents of the file as an instance of `Value`.
+            let value = serde_json::from_reader(reader)?;
+
+            // Return the `Value`.
+            Ok(value)
+        }
+
+        let json1 = read_json_file("data/a.json").unwrap();
+        let json2 = read_json_file("data/b.json").unwrap();
+
+        assert_eq!(
+            JsonDiff::diff_string(&json1, &json2, false).unwrap(),
+            std::fs::read_to_string("data/result.jsdiff")
+                .unwrap()
+                .replace("\r\n", "\n")
+        );
+
+        assert_eq!(JsonDiff::diff_string(&json1, &json1, false), None);
+    }
+
+    #[test]
+    fn test_display() {
+        let json1 = serde_json::from_str(&std::fs::read_to_string("data/a.json").unwrap()).unwrap();
+        let json2 = serde_json::from_str(&std::fs::read_to_string("data/b.json").unwrap()).unwrap();
+
+        // A difference is displayed as `diff_string` formats it.
+        let json_diff = JsonDiff::diff(&json1, &json2, false);
+        assert_eq!(
+            format!("{json_diff}"),
+            std::fs::read_to_string("data/result.jsdiff")
+                .unwrap()
+                .replace("\r\n", "\n")
+        );
+        assert_eq!(format!("{}", JsonDiff::diff(&json1, &json1, false)), "");
+    }
+
+    #[test]
+    fn test_diff_string_with_score() {
+        let json1 = json!({"foo": 42, "bar": [1, 2, 3]});
+        let json2 = json!({"foo": 10, "bar": [1, 3]});
+
+        for (json1, json2) in [(&json1, &json2), (&json1, &json1)] {
+            let (score, diff_string) = JsonDiff::diff_string_with_score(json1, json2, false);
+            assert_eq!(
+                score.to_bits(),
+                JsonDiff::diff(json1, json2, false).score.to_bits()
+            );
+            assert_eq!(diff_string, JsonDiff::diff_string(json1, json2, false));
+        }
+        assert_eq!(
+            JsonDiff::diff_string_with_score(&json1, &json1, false),
+            (200., None)
+        );
+    }
+
+    #[test]
+    fn test_from_diff_value() {
+        let diff = json!({"foo": {"__old": 42, "__new": 10}, "bar": [[" ", 1], ["-", 2]]});
+        let json_diff = JsonDiff::from_diff_value(diff.clone());
+        assert!(json_diff.score.is_nan());
+        assert_eq!(json_diff.diff, Some(diff));
+        assert_eq!(
+            json_diff.to_string(),
+            JsonDiff::diff_string(
+                &json!({"foo": 42, "bar": [1, 2]}),
+                &json!({"foo": 10, "bar": [1]}),
+                false
+            )
+            .unwrap()
+        );
+        assert_eq!(
+            json_diff.to_string(),
+            concat!(
+                " {\n",
+                "   bar: [\n",
+                "     1\n",
+                "-    2\n",
+                "   ]\n",
+                "-  foo: 42\n",
+                "+  foo: 10\n",
+                " }\n",
+            )
+        );
+        assert_eq!(json_diff.stats().values_changed, 1);
+        assert_eq!(
+            json_diff.invert(),
+            Some(json!({"foo": {"__old": 10, "__new": 42}, "bar": [[" ", 1], ["+", 2]]}))
+        );
+
+        let json_diff = JsonDiff::from_diff_value(Value::Null);
+        assert!(!json_diff.has_changes());
+        assert_eq!(json_diff.to_json_patch(), Some(json!([])));
+    }
+
+    // Drops a deeply nested value without any recursion.
+    fn drop_nested(value: Value) {
+        let mut stack = vec![value];
+        while let Some(value) = stack.pop() {
+            match value {
+                Value::Array(array) => stack.extend(array),
+                Value::Object(obj) => stack.extend(obj.into_iter().map(|(_, value)| value)),
+                _ => {}
+            }
+        }
+    }
+
+    #[test]
+    fn test_max_nesting() {
+        let nested = |depth: usize, leaf: Value| {
+            let mut value = leaf;
+            for level in 0..depth {
+                // The values are moved, since `json!` would serialize them.
+                value = if level % 2 == 0 {
+                    Value::Array(vec![value])
+                } else {
+                    Value::Object([("a".to_owned(), value)].into_iter().collect())
+                };
+            }
+            value
+        };
+
+        // The JSON files nested deeper than the default limit are not
+        // compared.
+        let options = DiffOptions::default();
+        let (json1, json2) = (nested(50_000, json!(1)), nested(50_000, json!(2)));
+        assert!(matches!(
+            JsonDiff::try_diff_with_options(&json1, &json2, &options),
+            Err(DiffError::TooDeep(128))
+        ));
+        assert!(matches!(
+            JsonDiff::diff_at(&json1, &json2, &JsonPointer::parse("/0").unwrap(), &options),
+            Err(DiffError::TooDeep(128))
+        ));
+        assert!(matches!(
+            JsonDiff::try_diff_with_patch(&json1, &json2, &options),
+            Err(DiffError::TooDeep(128))
+        ));
+        assert!(matches!(
+            JsonDiff::try_diff_with_defaults(&json1, &json2, &json!({}), &options),
+            Err(DiffError::TooDeep(128))
+        ));
+
+        drop_nested(json1);
+        drop_nested(json2);
+
+        // The infallible functions report them as replaced as a whole
+        // instead, unless they are equal.
+        let (json1, json2) = (nested(130, json!(1)), nested(130, json!(2)));
+        let replaced = Some(json!({"__old": json1, "__new": json2}));
+        let json_diff = JsonDiff::diff(&json1, &json2, false);
+        assert_eq!(json_diff.diff, replaced);
+        assert!(!json_diff.truncated);
+        assert_eq!(
+            JsonDiff::diff_with_defaults(&json1, &json2, &json!({}), &options).diff,
+            replaced
+        );
+        let with_patch = JsonDiff::diff_with_patch(&json1, &json2, &options);
+        assert_eq!(with_patch.diff, replaced);
+        assert_eq!(
+            with_patch.json_patch,
+            json!([{"op": "replace", "path": "", "value": json2}])
+        );
+        assert_eq!(JsonDiff::diff(&json1, &json1, false).diff, None);
+
+        // The nesting is not limited without a limit.
+        let options = DiffOptions {
+            max_nesting: None,
+            ..DiffOptions::default()
+        };
+        let json_diff = JsonDiff::diff_with_options(&json1, &json2, &options);
+        assert_ne!(json_diff.diff, replaced);
+        assert!(json_diff.has_changes());
+
+        // The JSON files nested up to the limit are compared.
+        let options = DiffOptions {
+            max_nesting: Some(16),
+            ..DiffOptions::default()
+        };
+        let (json1, json2) = (nested(16, json!(1)), nested(16, json!(2)));
+        let json_diff = JsonDiff::diff_with_options(&json1, &json2, &options);
+        assert!(json_diff.has_changes());
+        assert!(!json_diff.truncated);
+        let (json1, json2) = (nested(17, json!(1)), nested(17, json!(2)));
+        assert_eq!(
+            JsonDiff::try_diff_with_options(&json1, &json2, &options)
+                .err()
+                .unwrap()
+                .to_string(),
+            "a JSON file is nested deeper than 16 objects or arrays"
+        );
+    }
+
+    #[test]
+    fn test_array_keys() {
+        let options = DiffOptions {
+            array_keys: vec!["region".to_owned(), "name".to_owned()],
+            ..DiffOptions::default()
+        };
+        let json1 = json!([
+            {"region": "eu", "name": "foo", "size": 1, "color": "red"},
+            {"region": "us", "name": "foo", "size": 2, "color": "blue"},
+        ]);
+        let json2 = json!([
+            {"region": "us", "name": "foo", "size": 1, "color": "red"},
+            {"region": "eu", "name": "foo", "size": 2, "color": "blue"},
+        ]);
+
+        // The reordered objects are paired by their identity rather than by
+        // their similarity.
+        let unordered = DiffOptions {
+            array_unordered: true,
+            ..DiffOptions::default()
+        };
+        assert_eq!(
+            JsonDiff::diff_with_options(&json1, &json2, &unordered).diff,
+            Some(json!([
+                ["~", {"region": {"__old": "eu", "__new": "us"}}],
+                ["~", {"region": {"__old": "us", "__new": "eu"}}],
+            ]))
+        );
+        let unordered = DiffOptions {
+            array_keys: options.array_keys.clone(),
+            ..unordered
+        };
+        assert_eq!(
+            JsonDiff::diff_with_options(&json1, &json2, &unordered).diff,
+            Some(json!([
+                [
+                    "~",
+                    {"size": {"__old": 1, "__new": 2}, "color": {"__old": "red", "__new": "blue"}}
+                ],
+                [
+                    "~",
+                    {"size": {"__old": 2, "__new": 1}, "color": {"__old": "blue", "__new": "red"}}
+                ],
+            ]))
+        );
+
+        // The objects of different identities are never matched.
+        let json1 = json!([{"region": "eu", "name": "foo", "size": 1, "color": "red"}]);
+        let json2 = json!([{"region": "us", "name": "foo", "size": 1, "color": "red"}]);
+        assert_eq!(
+            JsonDiff::diff(&json1, &json2, false).diff,
+            Some(json!([["~", {"region": {"__old": "eu", "__new": "us"}}]]))
+        );
+        assert_eq!(
+            JsonDiff::diff_with_options(&json1, &json2, &options).diff,
+            Some(json!([["-", json1[0]], ["+", json2[0]]]))
+        );
+
+        // The fields may be JSON Pointers.
+        let options = DiffOptions {
+            array_keys: vec!["/meta/id".to_owned(), "kind".to_owned()],
+            ..DiffOptions::default()
+        };
+        assert_eq!(
+            JsonDiff::diff_with_options(
+                &json!([{"meta": {"id": 1}, "kind": "a", "size": 1}]),
+                &json!([{"meta": {"id": 1, "rev": 2}, "kind": "a", "size": 9, "new": true}]),
+                &options
+            )
+            .diff,
+            Some(json!([[
+                "~",
+                {"meta": {"rev__added": 2}, "size": {"__old": 1, "__new": 9}, "new__added": true}
+            ]]))
+        );
+
+        // The objects missing a field are matched by their score.
+        let json1 = json!([{"meta": {"id": 1}, "size": 1, "color": "red"}, {"kind": "a"}]);
+        let json2 = json!([{"meta": {"id": 2}, "size": 1, "color": "red"}, {"kind": "a"}]);
+        assert_eq!(
+            JsonDiff::diff_with_options(&json1, &json2, &options).diff,
+            Some(json!([["~", {"meta": {"id": {"__old": 1, "__new": 2}}}], [" "]]))
+        );
+    }
+
+    #[test]
+    fn test_fuzzy_match_arrays() {
+        let options = DiffOptions {
+            fuzzy_match_arrays: true,
+            ..DiffOptions::default()
+        };
+        let json1 = json!([[1, 2], [3, 4]]);
+        let json2 = json!([[1, 2, 9], [3, 4]]);
+
+        // By default, a changed nested array is deleted and added.
+        assert_eq!(
+            JsonDiff::diff(&json1, &json2, false).diff,
+            Some(json!([["-", [1, 2]], ["+", [1, 2, 9]], [" ", [3, 4]]]))
+        );
+        assert_eq!(
+            JsonDiff::diff_with_options(&json1, &json2, &options).diff,
+            Some(json!([["~", [[" ", 1], [" ", 2], ["+", 9]]], [" "]]))
+        );
+
+        // The most similar array is matched, even among other items.
+        assert_eq!(
+            JsonDiff::diff_with_options(
+                &json!([[1, 2, 3, 4], [7, 8, 9, 10], "x"]),
+                &json!([[7, 8, 0, 10], "x"]),
+                &options
+            )
+            .diff,
+            Some(json!([
+                ["-", [1, 2, 3, 4]],
+                ["~", [[" ", 7], [" ", 8], ["-", 9], ["+", 0], [" ", 10]]],
+                [" ", "x"]
+            ]))
+        );
+
+        // Arrays sharing too few items are still deleted and added.
+        assert_eq!(
+            JsonDiff::diff_with_options(&json!([[1, 2]]), &json!([[1, 3, 4]]), &options).diff,
+            Some(json!([["-", [1, 2]], ["+", [1, 3, 4]]]))
+        );
+    }
+
+    /// A xorshift generator, so that the random arrays are always the same.
+    struct Rng(u64);
+
+    impl Rng {
+        // The remainder is smaller than a `usize`.
+        #[allow(clippy::cast_possible_truncation)]
+        fn below(&mut self, bound: usize) -> usize {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            (self.0 % bound as u64) as usize
+        }
+
+        // Few distinct values, so that the arrays share items.
+        fn value(&mut self, depth: usize) -> Value {
+            match self.below(if depth == 0 { 4 } else { 6 }) {
+                0 => json!(self.below(4)),
+                1 => json!(["a", "b", "c"][self.below(3)]),
+                2 => json!(null),
+                3 => json!(self.below(2) == 0),
+                4 => {
+                    let keys = ["id", "name", "tags"];
+                    (0..self.below(4))
+                        .map(|_| {
+                            let key = keys[self.below(3)];
+                            (String::from(key), self.value(depth - 1))
+                        })
+                        .collect::<serde_json::Map<_, _>>()
+                        .into()
+                }
+                _ => self.array(depth - 1),
+            }
+        }
+
+        fn array(&mut self, depth: usize) -> Value {
+            (0..self.below(6)).map(|_| self.value(depth)).collect()
+        }
+
+        // Removes, inserts and replaces some items of an array.
+        fn edit(&mut self, array: &Value) -> Value {
+            let mut items = array.as_array().unwrap().clone();
+            for _ in 0..self.below(4) {
+                let index = self.below(items.len() + 1);
+                match self.below(3) {
+                    0 if index < items.len() => {
+                        items.remove(index);
+                    }
+                    1 if index < items.len() => items[index] = self.value(2),
+                    _ => items.insert(index, self.value(2)),
+                }
+            }
+            Value::Array(items)
+        }
+    }
+
+    // Applies a difference without moves to the old value.
+    fn apply(value: &Value, node: &DiffNode) -> Value {
+        match (value, node) {
+            (_, DiffNode::Unchanged) => value.clone(),
+            (_, DiffNode::Scalar { old, new }) => {
+                assert_eq!(value, old);
+                new.clone()
+            }
+            (
+                Value::Object(obj),
+                DiffNode::Object {
+                    added,
+                    deleted,
+                    changed,
+                    renamed,
+                },
+            ) => {
+                assert!(renamed.is_empty());
+                let mut obj = obj.clone();
+                for (key, old) in deleted {
+                    assert_eq!(obj.remove(key).as_ref(), Some(old));
+                }
+                for (key, node) in changed {
+                    let new = apply(&obj[key], node);
+                    obj.insert(key.clone(), new);
+                }
+                for (key, new) in added {
+                    assert!(obj.insert(key.clone(), new.clone()).is_none());
+                }
+                Value::Object(obj)
+            }
+            (Value::Array(array), DiffNode::Array(ops)) => {
+                let mut old_items = array.iter();
+                let mut new_items = Vec::new();
+                for op in ops {
+                    match op {
+                        ArrayOp::Unchanged(kept) => {
+                            let item = old_items.next().unwrap();
+                            if let Some(kept) = kept {
+                                assert_eq!(item, kept);
+                            }
+                            new_items.push(item.clone());
+                        }
+                        ArrayOp::Removed { value, .. } => {
+                            assert_eq!(old_items.next(), Some(value));
+                        }
+                        ArrayOp::Added { value, .. } => new_items.push(value.clone()),
+                        ArrayOp::Changed { diff, .. } => {
+                            new_items.push(apply(old_items.next().unwrap(), diff));
+                        }
+                        ArrayOp::Moved { .. } => panic!("unexpected move"),
+                    }
+                }
+                assert_eq!(old_items.next(), None);
+                Value::Array(new_items)
+            }
+            _ => panic!("{node:?} is not a difference of {value}"),
+        }
+    }
+
+    #[test]
+    fn test_array_round_trip() {
+        let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+        let options = [
+            DiffOptions::default(),
+            DiffOptions {
+                include_indices: true,
+                ..DiffOptions::default()
+            },
+            DiffOptions {
+                max_fuzzy_array_len: Some(0),
+                ..DiffOptions::default()
+            },
+            DiffOptions {
+                fuzzy_match_arrays: true,
+                ..DiffOptions::default()
+            },
+            DiffOptions {
+                array_keys: vec!["id".to_owned()],
+                ..DiffOptions::default()
+            },
+            DiffOptions {
+                strict_arrays: true,
+                ..DiffOptions::default()
+            },
+        ];
+        for _ in 0..2000 {
+            let json1 = rng.array(2);
+            // Edited arrays share most items, random ones share few.
+            let json2 = if rng.below(2) == 0 {
+                rng.edit(&json1)
+            } else {
+                rng.array(2)
+            };
+            for options in &options {
+                let json_diff = JsonDiff::diff_with_options(&json1, &json2, options);
+                let node = json_diff.tree().unwrap();
+                assert_eq!(apply(&json1, &node), json2, "{json1} -> {json2}");
+                // Equal objects may be paired with different equals, so
+                // only the arrays without differences must be equal.
+                if json_diff.diff.is_none() {
+                    assert_eq!(json1, json2);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_diff_lines() {
+        let json1 = json!({"foo": 42, "bar": [1, 2]});
+        let json2 = json!({"foo": 10, "bar": [1, 3]});
+
+        let lines = JsonDiff::diff_lines(&json1, &json2, false).unwrap();
+        assert_eq!(
+            lines,
+            [
+                " {",
+                "   bar: [",
+                "     1",
+                "-    2",
+                "+    3",
+                "   ]",
+                "-  foo: 42",
+                "+  foo: 10",
+                " }"
+            ]
+        );
+        assert_eq!(JsonDiff::diff_lines(&json1, &json1, false), None);
+
+        assert_eq!(
+            JsonDiff::diff_string_with(&json1, &json2, false, &LineOptions::default()),
+            JsonDiff::diff_string(&json1, &json2, false)
+        );
+        assert_eq!(
+            JsonDiff::diff_string_with(
+                &json1,
+                &json2,
+                false,
+                &LineOptions {
+                    trailing_newline: false,
+                    ..LineOptions::default()
+                }
+            )
+            .unwrap(),
+            lines.join("\n")
+        );
+        assert_eq!(
+            JsonDiff::diff_string_with(
+                &json!([1]),
+                &json!([2]),
+                false,
+                &LineOptions {
+                    line_ending: "\r\n",
+                    ..LineOptions::default()
+                }
+            )
+            .unwrap(),
+            " [\r\n-  1\r\n+  2\r\n ]\r\n"
+        );
+        assert_eq!(
+            JsonDiff::diff_string_with(&json1, &json1, false, &LineOptions::default()),
+            None
+        );
+    }
+
+    #[test]
+    fn test_diff_files() {
+        let options = DiffOptions::default();
+
+        let json_diff = JsonDiff::diff_files("data/a.json", "data/b.json", &options).unwrap();
+        let json1 = serde_json::from_str(&std::fs::read_to_string("data/a.json").unwrap()).unwrap();
+        let json2 = serde_json::from_str(&std::fs::read_to_string("data/b.json").unwrap()).unwrap();
+        assert_eq!(json_diff.diff, JsonDiff::diff(&json1, &json2, false).diff);
+
+        let err = JsonDiff::diff_files("data/a.json", "data/missing.json", &options)
+            .err()
+            .unwrap();
+        assert!(matches!(&err, DiffError::Io(path, _) if path == Path::new("data/missing.json")));
+        assert!(err.source().is_some());
+
+        let err = JsonDiff::diff_files("data/a.json", "data/result.jsdiff", &options)
+            .err()
+            .unwrap();
+        assert!(matches!(err, DiffError::Json(..)));
     }
 }
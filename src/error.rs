@@ -0,0 +1,65 @@
+use alloc::string::String;
+use core::fmt;
+#[cfg(feature = "std")]
+use std::path::PathBuf;
+
+use crate::pointer::JsonPointer;
+
+/// Errors which can occur while finding the JSON structural difference
+/// of two JSON files.
+#[derive(Debug)]
+#[allow(clippy::module_name_repetitions)]
+pub enum DiffError {
+    /// The JSON Pointer only resolves in one of the JSON files.
+    PointerNotFound {
+        /// The JSON Pointer.
+        pointer: JsonPointer,
+        /// `true` if it does not resolve in the first JSON file, `false` if
+        /// it does not resolve in the second one.
+        in_first: bool,
+    },
+    /// The JSON Pointer is not valid, see [`JsonPointer::parse`].
+    InvalidPointer(String),
+    /// A JSON file is nested deeper than [`DiffOptions::max_nesting`].
+    ///
+    /// [`DiffOptions::max_nesting`]: crate::DiffOptions::max_nesting
+    TooDeep(usize),
+    /// The file at the given path cannot be read.
+    #[cfg(feature = "std")]
+    Io(PathBuf, std::io::Error),
+    /// The file at the given path is not a valid JSON file.
+    #[cfg(feature = "std")]
+    Json(PathBuf, serde_json::Error),
+}
+
+impl fmt::Display for DiffError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::PointerNotFound { pointer, in_first } => write!(
+                f,
+                "the JSON Pointer {pointer} does not resolve in the {} JSON file",
+                if *in_first { "first" } else { "second" }
+            ),
+            Self::InvalidPointer(pointer) => write!(f, "{pointer} is not a valid JSON Pointer"),
+            Self::TooDeep(max_nesting) => write!(
+                f,
+                "a JSON file is nested deeper than {max_nesting} objects or arrays"
+            ),
+            #[cfg(feature = "std")]
+            Self::Io(path, err) => write!(f, "{}: {err}", path.display()),
+            #[cfg(feature = "std")]
+            Self::Json(path, err) => write!(f, "{}: {err}", path.display()),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for DiffError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(_, err) => Some(err),
+            Self::Json(_, err) => Some(err),
+            _ => None,
+        }
+    }
+}
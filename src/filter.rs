@@ -0,0 +1,126 @@
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use serde_json::{Map, Value};
+
+use crate::flatten::ChangeKind;
+use crate::markers::{DiffMarkers, MarkedKey};
+
+/// The kinds of changes kept in a JSON structural difference, see
+/// [`DiffOptions::filter`].
+///
+/// [`DiffOptions::filter`]: crate::DiffOptions::filter
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DiffFilter {
+    /// Every change.
+    #[default]
+    All,
+    /// Only the added keys and array items.
+    AddedOnly,
+    /// Only the deleted keys and array items.
+    DeletedOnly,
+    /// Only the values replaced by another value, the renamed keys and the
+    /// moved array items.
+    ChangedOnly,
+}
+
+impl DiffFilter {
+    /// Returns `true` if the changes of the given kind are kept.
+    pub(crate) fn keeps(self, kind: ChangeKind) -> bool {
+        match self {
+            Self::All => true,
+            Self::AddedOnly => kind == ChangeKind::Added,
+            Self::DeletedOnly => kind == ChangeKind::Removed,
+            Self::ChangedOnly => matches!(
+                kind,
+                ChangeKind::Changed | ChangeKind::Moved | ChangeKind::Renamed
+            ),
+        }
+    }
+
+    /// Returns `diff` without the changes of the other kinds, or `None` if
+    /// no change is left, for the differences found before being reported.
+    ///
+    /// The unchanged array items and the unchanged keys shown for context
+    /// are kept along with the remaining changes, while the array items
+    /// without any change left are shown as unchanged.
+    pub(crate) fn filter(self, diff: Value, markers: &DiffMarkers) -> Option<Value> {
+        match diff {
+            Value::Object(mut obj) => {
+                if markers.as_change(&obj).is_some() || markers.as_text(&obj).is_some() {
+                    return self
+                        .keeps(ChangeKind::Changed)
+                        .then_some(Value::Object(obj));
+                }
+                if markers.as_embedded(&obj).is_some() {
+                    let embedded = obj.remove(&markers.embedded)?;
+                    return Some(markers.embedded(self.filter(embedded, markers)?));
+                }
+                self.filter_object(obj, markers)
+            }
+            Value::Array(entries) => self.filter_entries(entries, markers),
+            _ => self.keeps(ChangeKind::Changed).then_some(diff),
+        }
+    }
+
+    fn filter_object(self, obj: Map<String, Value>, markers: &DiffMarkers) -> Option<Value> {
+        // The entries kept, along with whether they are a change.
+        let mut kept = Vec::new();
+        for (key, value) in obj {
+            let entry = match markers.parse_key(&key) {
+                MarkedKey::Added(_) => self.keeps(ChangeKind::Added).then_some((value, true)),
+                MarkedKey::Deleted(_) => self.keeps(ChangeKind::Removed).then_some((value, true)),
+                MarkedKey::Renamed(..) => self.keeps(ChangeKind::Renamed).then_some((value, true)),
+                MarkedKey::Unchanged(_) => Some((value, false)),
+                MarkedKey::Shared(_) => self.filter(value, markers).map(|value| (value, true)),
+            };
+            if let Some((value, is_change)) = entry {
+                kept.push((key, value, is_change));
+            }
+        }
+        kept.iter().any(|(_, _, is_change)| *is_change).then(|| {
+            Value::Object(
+                kept.into_iter()
+                    .map(|(key, value, _)| (key, value))
+                    .collect(),
+            )
+        })
+    }
+
+    fn filter_entries(self, entries: Vec<Value>, markers: &DiffMarkers) -> Option<Value> {
+        // The entries kept, along with whether they are a change.
+        let mut kept = Vec::new();
+        for entry in entries {
+            let Value::Array(mut items) = entry else {
+                kept.push((entry, false));
+                continue;
+            };
+            let is_kept = match items.first().and_then(Value::as_str) {
+                Some("+") => self.keeps(ChangeKind::Added),
+                Some("-") => self.keeps(ChangeKind::Removed),
+                Some(">") => self.keeps(ChangeKind::Moved),
+                Some("~") if items.len() > 1 => {
+                    // An item without any change left is shown as
+                    // unchanged, so that the other items keep their place.
+                    let Some(change) = self.filter(items[1].take(), markers) else {
+                        kept.push((Value::Array(vec![Value::from(" ")]), false));
+                        continue;
+                    };
+                    items[1] = change;
+                    true
+                }
+                _ => {
+                    kept.push((Value::Array(items), false));
+                    continue;
+                }
+            };
+            if is_kept {
+                kept.push((Value::Array(items), true));
+            }
+        }
+        kept.iter()
+            .any(|(_, is_change)| *is_change)
+            .then(|| Value::Array(kept.into_iter().map(|(entry, _)| entry).collect()))
+    }
+}
@@ -0,0 +1,325 @@
+use alloc::collections::BTreeSet;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use serde_json::Value;
+
+use crate::diff::JsonDiff;
+use crate::markers::{DiffMarkers, MarkedKey};
+use crate::pointer::JsonPointer;
+
+/// The kind of a [`Change`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChangeKind {
+    /// A value only present in the new JSON file.
+    Added,
+    /// A value only present in the old JSON file.
+    Removed,
+    /// A value replaced by another value.
+    Changed,
+    /// An array item moved to another index.
+    Moved,
+    /// A key renamed, whose value may have changed too.
+    Renamed,
+}
+
+/// A leaf change of a JSON structural difference.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Change {
+    /// The JSON Pointer to the changed value.
+    ///
+    /// Removed, changed and moved items of an array are addressed by their
+    /// index in the old array, while added items are addressed by their
+    /// index in the new array. Renamed keys, and the changes of their
+    /// values, are addressed by their old name.
+    pub path: JsonPointer,
+    /// The kind of the change.
+    pub kind: ChangeKind,
+    /// The old value, if any.
+    ///
+    /// For a moved item, its index in the old array. For a renamed key, its
+    /// old name.
+    pub old: Option<Value>,
+    /// The new value, if any.
+    ///
+    /// For a moved item, its index in the new array. For a renamed key, its
+    /// new name.
+    pub new: Option<Value>,
+}
+
+impl Change {
+    fn new(path: JsonPointer, kind: ChangeKind, old: Option<&Value>, new: Option<&Value>) -> Self {
+        Self {
+            path,
+            kind,
+            old: old.cloned(),
+            new: new.cloned(),
+        }
+    }
+}
+
+fn flatten_value(
+    diff: &Value,
+    path: &JsonPointer,
+    markers: &DiffMarkers,
+    changes: &mut Vec<Change>,
+) {
+    match diff {
+        Value::Object(obj) => {
+            if let Some((old, new)) = markers.as_change(obj) {
+                changes.push(Change::new(
+                    path.clone(),
+                    ChangeKind::Changed,
+                    Some(old),
+                    Some(new),
+                ));
+                return;
+            }
+            if let Some(segments) = markers.as_text(obj) {
+                let (old, new) = DiffMarkers::text_sides(segments);
+                changes.push(Change::new(
+                    path.clone(),
+                    ChangeKind::Changed,
+                    Some(&Value::from(old)),
+                    Some(&Value::from(new)),
+                ));
+                return;
+            }
+            // The changes of embedded JSON are located at the string.
+            if let Some(embedded) = markers.as_embedded(obj) {
+                flatten_value(embedded, path, markers, changes);
+                return;
+            }
+            for (key, value) in obj {
+                match markers.parse_key(key) {
+                    MarkedKey::Added(key) => changes.push(Change::new(
+                        path.join_key(key),
+                        ChangeKind::Added,
+                        None,
+                        Some(value),
+                    )),
+                    MarkedKey::Deleted(key) => changes.push(Change::new(
+                        path.join_key(key),
+                        ChangeKind::Removed,
+                        Some(value),
+                        None,
+                    )),
+                    MarkedKey::Unchanged(_) => {}
+                    MarkedKey::Shared(key) => {
+                        flatten_value(value, &path.join_key(key), markers, changes);
+                    }
+                    MarkedKey::Renamed(old_key, new_key) => {
+                        let key_path = path.join_key(old_key);
+                        changes.push(Change::new(
+                            key_path.clone(),
+                            ChangeKind::Renamed,
+                            Some(&Value::from(old_key)),
+                            Some(&Value::from(new_key)),
+                        ));
+                        flatten_value(value, &key_path, markers, changes);
+                    }
+                }
+            }
+        }
+        Value::Array(entries) => flatten_array(entries, path, markers, changes),
+        _ => {}
+    }
+}
+
+fn flatten_array(
+    entries: &[Value],
+    path: &JsonPointer,
+    markers: &DiffMarkers,
+    changes: &mut Vec<Change>,
+) {
+    let op = |entry: &Value| entry.get(0).and_then(Value::as_str).map(String::from);
+    let index_at = |entry: &Value, position| {
+        entry
+            .get(position)
+            .and_then(Value::as_u64)
+            .and_then(|index| usize::try_from(index).ok())
+    };
+
+    // The deletions of the moved items are not part of the difference, so
+    // their old indices are skipped while counting the old items.
+    let moved: BTreeSet<usize> = entries
+        .iter()
+        .filter(|entry| op(entry).as_deref() == Some(">"))
+        .filter_map(|entry| index_at(entry, 1))
+        .collect();
+    let (mut old_index, mut new_index) = (0, 0);
+    let mut next_old_index = || {
+        while moved.contains(&old_index) {
+            old_index += 1;
+        }
+        old_index += 1;
+        old_index - 1
+    };
+
+    // The indices included in the entries, if any, take precedence over
+    // the counted ones.
+    for entry in entries {
+        match (op(entry).as_deref(), entry.get(1)) {
+            (Some("-"), Some(value)) => {
+                let index = next_old_index();
+                let index = index_at(entry, 2).unwrap_or(index);
+                changes.push(Change::new(
+                    path.join_index(index),
+                    ChangeKind::Removed,
+                    Some(value),
+                    None,
+                ));
+            }
+            (Some("+"), Some(value)) => {
+                let index = index_at(entry, 2).unwrap_or(new_index);
+                new_index += 1;
+                changes.push(Change::new(
+                    path.join_index(index),
+                    ChangeKind::Added,
+                    None,
+                    Some(value),
+                ));
+            }
+            (Some("~"), Some(change)) => {
+                let index = next_old_index();
+                let index = index_at(entry, 2).unwrap_or(index);
+                new_index += 1;
+                flatten_value(change, &path.join_index(index), markers, changes);
+            }
+            (Some(">"), _) => {
+                new_index += 1;
+                if let (Some(from), Some(to)) = (index_at(entry, 1), index_at(entry, 2)) {
+                    changes.push(Change::new(
+                        path.join_index(from),
+                        ChangeKind::Moved,
+                        Some(&Value::from(from)),
+                        Some(&Value::from(to)),
+                    ));
+                }
+            }
+            _ => {
+                next_old_index();
+                new_index += 1;
+            }
+        }
+    }
+}
+
+impl JsonDiff {
+    /// Returns the leaf changes of the JSON structural difference as a
+    /// flat list, in the order they appear in the difference.
+    ///
+    /// The indices of the items of arrays compared with
+    /// [`DiffOptions::array_unordered`] are only exact when the difference
+    /// includes them, i.e. with [`DiffOptions::include_indices`].
+    ///
+    /// [`DiffOptions::array_unordered`]: crate::DiffOptions::array_unordered
+    /// [`DiffOptions::include_indices`]: crate::DiffOptions::include_indices
+    #[must_use]
+    pub fn flatten(&self) -> Vec<Change> {
+        let mut changes = Vec::new();
+        if let Some(diff) = &self.diff {
+            flatten_value(diff, &JsonPointer::new(), &self.markers(), &mut changes);
+        }
+        changes
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+
+    use serde_json::Value;
+
+    use super::{Change, ChangeKind};
+    use crate::diff::JsonDiff;
+    use crate::options::DiffOptions;
+    use crate::pointer::JsonPointer;
+
+    fn change(path: &str, kind: ChangeKind, old: Option<Value>, new: Option<Value>) -> Change {
+        Change {
+            path: JsonPointer::parse(path).unwrap(),
+            kind,
+            old,
+            new,
+        }
+    }
+
+    #[test]
+    fn test_flatten_object() {
+        let json_diff = JsonDiff::diff(
+            &json!({"foo": {"bar": {"baz": 1, "a/b": true}, "qux": "x"}, "count__added": 0}),
+            &json!({"foo": {"bar": {"baz": 2, "a/b": true}, "quux": null}}),
+            false,
+        );
+        assert_eq!(
+            json_diff.flatten(),
+            [
+                change("/count__added", ChangeKind::Removed, Some(json!(0)), None),
+                change(
+                    "/foo/bar/baz",
+                    ChangeKind::Changed,
+                    Some(json!(1)),
+                    Some(json!(2))
+                ),
+                change("/foo/quux", ChangeKind::Added, None, Some(json!(null))),
+                change("/foo/qux", ChangeKind::Removed, Some(json!("x")), None),
+            ]
+        );
+
+        let json_diff = JsonDiff::diff(&json!(1), &json!("1"), false);
+        assert_eq!(
+            json_diff.flatten(),
+            [change(
+                "",
+                ChangeKind::Changed,
+                Some(json!(1)),
+                Some(json!("1"))
+            )]
+        );
+        assert!(JsonDiff::diff(&json!(1), &json!(1), false)
+            .flatten()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_flatten_array() {
+        let json1 = json!({"foo": [1, 2, {"bar": 3, "baz": true, "qux": null}, 4]});
+        let json2 = json!({"foo": [0, 1, {"bar": 5, "baz": true, "qux": null}, 4, 6]});
+        let expected = [
+            change("/foo/0", ChangeKind::Added, None, Some(json!(0))),
+            change("/foo/1", ChangeKind::Removed, Some(json!(2)), None),
+            change(
+                "/foo/2/bar",
+                ChangeKind::Changed,
+                Some(json!(3)),
+                Some(json!(5)),
+            ),
+            change("/foo/4", ChangeKind::Added, None, Some(json!(6))),
+        ];
+        assert_eq!(JsonDiff::diff(&json1, &json2, false).flatten(), expected);
+
+        let options = DiffOptions {
+            include_indices: true,
+            ..DiffOptions::default()
+        };
+        assert_eq!(
+            JsonDiff::diff_with_options(&json1, &json2, &options).flatten(),
+            expected
+        );
+
+        // The deletion of a moved item does not shift the old indices.
+        let options = DiffOptions {
+            detect_moves: true,
+            ..DiffOptions::default()
+        };
+        assert_eq!(
+            JsonDiff::diff_with_options(&json!([1, 2, 3, 4]), &json!([2, 3, 1, 5]), &options)
+                .flatten(),
+            [
+                change("/3", ChangeKind::Removed, Some(json!(4)), None),
+                change("/0", ChangeKind::Moved, Some(json!(0)), Some(json!(2))),
+                change("/3", ChangeKind::Added, None, Some(json!(5))),
+            ]
+        );
+    }
+}
@@ -0,0 +1,431 @@
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+
+use serde_json::Value;
+
+use crate::diff::JsonDiff;
+use crate::error::DiffError;
+use crate::options::DiffOptions;
+use crate::tree::{parse_node, ArrayOp, DiffNode, TextSegment};
+
+/// A JSON structural difference along with the equivalent JSON Patch,
+/// found in a single comparison.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DiffWithPatch {
+    /// The similarity score, as in [`JsonDiff::score`].
+    pub score: f64,
+    /// The JSON structural difference, as in [`JsonDiff::diff`].
+    pub diff: Option<Value>,
+    /// The JSON Patch, as defined by RFC 6902, i.e. an array of operations.
+    pub json_patch: Value,
+}
+
+/// An item of an array being patched.
+enum Slot<'a> {
+    /// An item of the old array, along with its index there and its
+    /// difference.
+    Old(usize, Option<&'a DiffNode>),
+    /// An item only present in the new array.
+    New(&'a Value),
+}
+
+/// Builds the operations of a JSON Patch from the nodes of a JSON
+/// structural difference.
+struct PatchBuilder<'a> {
+    operations: Vec<Value>,
+    /// The new JSON file, where the strings embedding JSON are read, since
+    /// their difference cannot give them back.
+    target: Option<&'a Value>,
+}
+
+impl PatchBuilder<'_> {
+    fn node(&mut self, node: &DiffNode, path: &str) -> Option<()> {
+        match node {
+            DiffNode::Unchanged => {}
+            DiffNode::Scalar { new, .. } => {
+                self.operations
+                    .push(json!({"op": "replace", "path": path, "value": new}));
+            }
+            DiffNode::Object {
+                added,
+                deleted,
+                changed,
+                renamed,
+            } => {
+                for key in deleted.keys() {
+                    let path = JsonDiff::child_path(path, key);
+                    self.operations.push(json!({"op": "remove", "path": path}));
+                }
+                for (old_key, (new_key, node)) in renamed {
+                    let new_path = JsonDiff::child_path(path, new_key);
+                    self.operations.push(json!({
+                        "op": "move",
+                        "from": JsonDiff::child_path(path, old_key),
+                        "path": new_path,
+                    }));
+                    self.node(node, &new_path)?;
+                }
+                for (key, node) in changed {
+                    self.node(node, &JsonDiff::child_path(path, key))?;
+                }
+                for (key, value) in added {
+                    let path = JsonDiff::child_path(path, key);
+                    self.operations
+                        .push(json!({"op": "add", "path": path, "value": value}));
+                }
+            }
+            DiffNode::Array(ops) => self.array(ops, path)?,
+            DiffNode::Text(segments) => {
+                let new: String = segments
+                    .iter()
+                    .filter_map(|segment| match segment {
+                        TextSegment::Kept(text) | TextSegment::Added(text) => Some(text.as_str()),
+                        TextSegment::Removed(_) => None,
+                    })
+                    .collect();
+                self.operations
+                    .push(json!({"op": "replace", "path": path, "value": new}));
+            }
+            DiffNode::Embedded(_) => {
+                let value = self.target?.pointer(path)?;
+                self.operations
+                    .push(json!({"op": "replace", "path": path, "value": value}));
+            }
+        }
+        Some(())
+    }
+
+    fn array(&mut self, ops: &[ArrayOp], path: &str) -> Option<()> {
+        // The moved items are left out of the other entries, which are
+        // otherwise in the order of the old array.
+        let moved: Vec<usize> = ops
+            .iter()
+            .filter_map(|op| match op {
+                ArrayOp::Moved { from, .. } => Some(*from),
+                _ => None,
+            })
+            .collect();
+        let mut old_index = 0;
+        let mut next_old_index = || {
+            while moved.contains(&old_index) {
+                old_index += 1;
+            }
+            old_index += 1;
+            old_index - 1
+        };
+
+        let mut slots = Vec::with_capacity(ops.len());
+        let mut removed = Vec::new();
+        for op in ops {
+            match op {
+                ArrayOp::Unchanged(_) => slots.push(Slot::Old(next_old_index(), None)),
+                ArrayOp::Changed { diff, .. } => {
+                    slots.push(Slot::Old(next_old_index(), Some(diff)));
+                }
+                ArrayOp::Removed { .. } => removed.push(next_old_index()),
+                ArrayOp::Added { value, .. } => slots.push(Slot::New(value)),
+                ArrayOp::Moved { from, .. } => slots.push(Slot::Old(*from, None)),
+            }
+        }
+
+        // The old indices of the items of the array being patched, `None`
+        // for the added items.
+        let old_len = ops
+            .iter()
+            .filter(|op| !matches!(op, ArrayOp::Added { .. }))
+            .count();
+        let mut items: Vec<Option<usize>> = (0..old_len).map(Some).collect();
+
+        // The removed items are in ascending order, so removing them from
+        // the last one keeps the indices of the others.
+        for &index in removed.iter().rev() {
+            if index >= items.len() {
+                return None;
+            }
+            items.remove(index);
+            let path = JsonDiff::child_path(path, &index.to_string());
+            self.operations.push(json!({"op": "remove", "path": path}));
+        }
+
+        // Every item before `position` is already in place.
+        for (position, slot) in slots.into_iter().enumerate() {
+            let item_path = JsonDiff::child_path(path, &position.to_string());
+            match slot {
+                Slot::Old(old_index, diff) => {
+                    let current = items[position..]
+                        .iter()
+                        .position(|&item| item == Some(old_index))?
+                        + position;
+                    if current != position {
+                        let item = items.remove(current);
+                        items.insert(position, item);
+                        self.operations.push(json!({
+                            "op": "move",
+                            "from": JsonDiff::child_path(path, &current.to_string()),
+                            "path": item_path,
+                        }));
+                    }
+                    if let Some(diff) = diff {
+                        self.node(diff, &item_path)?;
+                    }
+                }
+                Slot::New(value) => {
+                    items.insert(position, None);
+                    self.operations
+                        .push(json!({"op": "add", "path": item_path, "value": value}));
+                }
+            }
+        }
+        Some(())
+    }
+}
+
+fn json_patch(node: &DiffNode, target: Option<&Value>) -> Option<Value> {
+    let mut builder = PatchBuilder {
+        operations: Vec::new(),
+        target,
+    };
+    builder.node(node, "")?;
+    Some(Value::Array(builder.operations))
+}
+
+impl DiffNode {
+    /// Returns the JSON Patch, as defined by RFC 6902, which applies the
+    /// difference represented by the node.
+    ///
+    /// Returns `None` if the node changes the JSON embedded in a string,
+    /// since the new string cannot be rebuilt from its difference.
+    #[must_use]
+    pub fn to_json_patch(&self) -> Option<Value> {
+        json_patch(self, None)
+    }
+}
+
+impl JsonDiff {
+    /// Returns the JSON Patch, as defined by RFC 6902, which applies the
+    /// JSON structural difference, without comparing the JSON files again.
+    ///
+    /// Returns `None` when it cannot be parsed, or when it changes the JSON
+    /// embedded in a string.
+    #[must_use]
+    pub fn to_json_patch(&self) -> Option<Value> {
+        self.tree()?.to_json_patch()
+    }
+
+    /// Finds the JSON structural difference of two JSON files, along with
+    /// the JSON Patch turning the first one into the second one.
+    ///
+    /// The JSON Patch is built from the JSON structural difference, so the
+    /// JSON files are only compared once. It is as faithful as the
+    /// difference: the changes hidden by the options, such as the values
+    /// with [`DiffOptions::keys_only`] or the order of the unordered arrays,
+    /// are not part of it, and neither are the changes left out with
+    /// [`DiffOptions::max_changes`].
+    ///
+    /// If a JSON file is nested deeper than [`DiffOptions::max_nesting`],
+    /// the first one is replaced as a whole by the second one, as
+    /// [`JsonDiff::diff_with_options`] does.
+    /// Use [`JsonDiff::try_diff_with_patch`] to get an error instead.
+    #[must_use]
+    pub fn diff_with_patch(json1: &Value, json2: &Value, options: &DiffOptions) -> DiffWithPatch {
+        Self::with_patch(
+            Self::diff_with_options(json1, json2, options),
+            json2,
+            options,
+        )
+    }
+
+    /// Finds the JSON structural difference of two JSON files, along with
+    /// the JSON Patch turning the first one into the second one, as
+    /// [`JsonDiff::diff_with_patch`] does.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DiffError::TooDeep`] if a JSON file is nested deeper than
+    /// [`DiffOptions::max_nesting`].
+    pub fn try_diff_with_patch(
+        json1: &Value,
+        json2: &Value,
+        options: &DiffOptions,
+    ) -> Result<DiffWithPatch, DiffError> {
+        let json_diff = Self::try_diff_with_options(json1, json2, options)?;
+        Ok(Self::with_patch(json_diff, json2, options))
+    }
+
+    /// Adds to a JSON structural difference the JSON Patch turning the first
+    /// JSON file into the second one.
+    fn with_patch(json_diff: Self, json2: &Value, options: &DiffOptions) -> DiffWithPatch {
+        let json_patch = match &json_diff.diff {
+            Some(diff) => parse_node(diff, &options.markers)
+                .and_then(|node| json_patch(&node, Some(json2)))
+                // A difference which cannot be turned into operations
+                // replaces the whole JSON file.
+                .unwrap_or_else(|| json!([{"op": "replace", "path": "", "value": json2}])),
+            None => Value::Array(vec![]),
+        };
+        DiffWithPatch {
+            score: json_diff.score,
+            diff: json_diff.diff,
+            json_patch,
+        }
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+
+    use serde_json::Value;
+
+    use crate::diff::JsonDiff;
+    use crate::options::DiffOptions;
+    use crate::pointer::JsonPointer;
+
+    fn parse_pointer(pointer: &str) -> (String, String) {
+        let mut parent = JsonPointer::parse(pointer).unwrap();
+        let token = parent.pop().unwrap();
+        (parent.to_string(), token)
+    }
+
+    fn remove(document: &mut Value, pointer: &str) -> Value {
+        let (parent, token) = parse_pointer(pointer);
+        match document.pointer_mut(&parent).unwrap() {
+            Value::Object(obj) => obj.remove(&token).unwrap(),
+            Value::Array(arr) => arr.remove(token.parse().unwrap()),
+            _ => panic!("{pointer} is not in a container"),
+        }
+    }
+
+    fn add(document: &mut Value, pointer: &str, value: Value) {
+        if pointer.is_empty() {
+            *document = value;
+            return;
+        }
+        let (parent, token) = parse_pointer(pointer);
+        match document.pointer_mut(&parent).unwrap() {
+            Value::Object(obj) => {
+                obj.insert(token, value);
+            }
+            Value::Array(arr) if token == "-" => arr.push(value),
+            Value::Array(arr) => arr.insert(token.parse().unwrap(), value),
+            _ => panic!("{pointer} is not in a container"),
+        }
+    }
+
+    // The operations of RFC 6902 which may be part of a patch.
+    fn apply_json_patch(original: &Value, operations: &Value) -> Value {
+        let mut document = original.clone();
+        for operation in operations.as_array().unwrap() {
+            let pointer = operation["path"].as_str().unwrap();
+            match operation["op"].as_str().unwrap() {
+                "add" => add(&mut document, pointer, operation["value"].clone()),
+                "remove" => {
+                    remove(&mut document, pointer);
+                }
+                "replace" => *document.pointer_mut(pointer).unwrap() = operation["value"].clone(),
+                "move" => {
+                    let value = remove(&mut document, operation["from"].as_str().unwrap());
+                    add(&mut document, pointer, value);
+                }
+                op => panic!("unexpected operation {op}"),
+            }
+        }
+        document
+    }
+
+    #[test]
+    fn test_diff_with_patch() {
+        let pairs = [
+            (json!({"a": 1}), json!({"a": 1})),
+            (json!(1), json!("foo")),
+            (
+                json!({"a": 1, "b/c": {"d~": [1, 2, 3]}, "e": true}),
+                json!({"a": 2, "b/c": {"d~": [1, 3, 4]}, "f": null}),
+            ),
+            (json!([1, 2, 3]), json!([3, 2, 1])),
+            (
+                json!([{"id": 1, "foo": 1}, {"id": 2, "foo": 2}, "bar"]),
+                json!(["baz", {"id": 2, "foo": 3}, "bar", {"id": 3}]),
+            ),
+            (
+                json!({"items": [[1, 2], {"a": [true]}], "name": "foo"}),
+                json!({"items": [{"a": [false, true]}, [2]], "name": "bar"}),
+            ),
+        ];
+        for detect_moves in [false, true] {
+            let options = DiffOptions {
+                detect_moves,
+                include_indices: detect_moves,
+                ..DiffOptions::default()
+            };
+            for (json1, json2) in &pairs {
+                let diff_with_patch = JsonDiff::diff_with_patch(json1, json2, &options);
+                let json_diff = JsonDiff::diff_with_options(json1, json2, &options);
+                assert_eq!(diff_with_patch.diff, json_diff.diff);
+                assert!((diff_with_patch.score - json_diff.score).abs() < f64::EPSILON);
+                assert_eq!(apply_json_patch(json1, &diff_with_patch.json_patch), *json2);
+            }
+        }
+
+        let diff_with_patch = JsonDiff::diff_with_patch(
+            &json!([1, 2, 3]),
+            &json!([3, 2, 1]),
+            &DiffOptions {
+                detect_moves: true,
+                ..DiffOptions::default()
+            },
+        );
+        assert_eq!(
+            diff_with_patch.json_patch,
+            json!([
+                {"op": "move", "from": "/2", "path": "/0"},
+                {"op": "move", "from": "/2", "path": "/1"},
+            ])
+        );
+
+        // The renamed keys are moved.
+        let options = DiffOptions {
+            detect_renames: true,
+            ..DiffOptions::default()
+        };
+        let json1 = json!({"color": {"red": 255, "green": 0}});
+        let json2 = json!({"colour": {"red": 255, "green": 1}});
+        let diff_with_patch = JsonDiff::diff_with_patch(&json1, &json2, &options);
+        assert_eq!(
+            diff_with_patch.json_patch,
+            json!([
+                {"op": "move", "from": "/color", "path": "/colour"},
+                {"op": "replace", "path": "/colour/green", "value": 1},
+            ])
+        );
+        assert_eq!(apply_json_patch(&json1, &diff_with_patch.json_patch), json2);
+
+        // The strings embedding JSON are replaced as a whole.
+        let options = DiffOptions {
+            parse_embedded_json: true,
+            ..DiffOptions::default()
+        };
+        let json1 = json!({"payload": "{\"a\": 1}"});
+        let json2 = json!({"payload": "{\"a\": 2}"});
+        let diff_with_patch = JsonDiff::diff_with_patch(&json1, &json2, &options);
+        assert_eq!(apply_json_patch(&json1, &diff_with_patch.json_patch), json2);
+        let json_diff = JsonDiff::diff_with_options(&json1, &json2, &options);
+        assert_eq!(json_diff.to_json_patch(), None);
+    }
+
+    #[test]
+    fn test_to_json_patch() {
+        let json_diff = JsonDiff::diff(&json!({"a": [1, 2]}), &json!({"a": [2], "b": 3}), false);
+        assert_eq!(
+            json_diff.to_json_patch(),
+            Some(json!([
+                {"op": "remove", "path": "/a/0"},
+                {"op": "add", "path": "/b", "value": 3},
+            ]))
+        );
+        assert_eq!(
+            JsonDiff::diff(&json!(1), &json!(1), false).to_json_patch(),
+            Some(json!([]))
+        );
+    }
+}
@@ -1,11 +1,138 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
 #[macro_use]
 extern crate serde_json;
 
+mod array_match;
+pub use crate::array_match::ArrayMatch;
+
+mod budget;
+mod common;
+mod compare;
+#[cfg(feature = "chrono")]
+pub use crate::compare::DateTimes;
+pub use crate::compare::ScalarComparator;
+
 mod diff;
 pub use crate::diff::JsonDiff;
 
+mod error;
+pub use crate::error::DiffError;
+
+mod filter;
+pub use crate::filter::DiffFilter;
+
+mod flatten;
+pub use crate::flatten::{Change, ChangeKind};
+
+mod json_patch;
+pub use crate::json_patch::DiffWithPatch;
+
+#[cfg(feature = "std")]
+mod location;
+#[cfg(feature = "std")]
+pub use crate::location::{LocatedDiff, Location};
+
+mod markers;
+pub use crate::markers::DiffMarkers;
+
+mod merge;
+pub use crate::merge::{Conflict, Merge3Result};
+
+mod merge_patch;
+
+mod normalize;
+pub use crate::normalize::{DropDefaults, FillDefaults, Normalizer, SortScalarArrays};
+
+mod options;
+pub use crate::options::DiffOptions;
+
+mod parallel;
+mod pointer;
+pub use crate::pointer::JsonPointer;
+
+mod sequence;
+mod serialize;
+mod stats;
+pub use crate::stats::DiffStats;
+
+mod tree;
+pub use crate::tree::{parse_array_diff, ArrayChange, ArrayOp, DiffNode, TextSegment};
+
+mod unified;
+mod unordered;
+mod visit;
+pub use crate::visit::DiffVisitor;
+
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
 mod colorize;
-pub use crate::colorize::colorize_to_array;
+pub use crate::colorize::{
+    colorize_into, colorize_to_array, colorize_to_array_with, colorize_to_html,
+    colorize_to_records, colorize_to_records_with, ContextOptions, DiffLine, DiffOp, Indent,
+    LineOptions,
+};
 
 #[cfg(feature = "colorize")]
-pub use crate::colorize::colorize;
+pub use crate::colorize::{colorize, colorize_with, Theme};
+#[cfg(feature = "colorize")]
+pub use console::{Color, Style};
+
+// Only `core` and `alloc` are used, so that these tests also build
+// with `default-features = false`.
+#[cfg(test)]
+mod tests {
+
+    use alloc::string::{String, ToString};
+    use alloc::vec::Vec;
+
+    use serde_json::Value;
+
+    use crate::{DiffOptions, DiffVisitor, JsonDiff, JsonPointer};
+
+    struct Paths(Vec<String>);
+
+    impl DiffVisitor for Paths {
+        fn on_added(&mut self, path: &JsonPointer, _value: &Value) {
+            self.0.push(path.to_string());
+        }
+
+        fn on_removed(&mut self, path: &JsonPointer, _value: &Value) {
+            self.0.push(path.to_string());
+        }
+
+        fn on_changed(&mut self, path: &JsonPointer, _old: &Value, _new: &Value) {
+            self.0.push(path.to_string());
+        }
+    }
+
+    #[test]
+    fn test_core_diff() {
+        let json1 = json!({"foo": 42, "bar": [1, 2, {"id": 1, "baz": true}]});
+        let json2 = json!({"foo": 10, "bar": [2, 3, {"id": 1, "baz": false}]});
+
+        let json_diff = JsonDiff::try_diff(&json1, &json2, false).unwrap();
+        assert_eq!(
+            json_diff.diff,
+            Some(json!({
+                "foo": {"__old": 42, "__new": 10},
+                "bar": [
+                    ["-", 1],
+                    [" ", 2],
+                    ["+", 3],
+                    ["~", {"baz": {"__old": true, "__new": false}}]
+                ]
+            }))
+        );
+        assert!(JsonDiff::try_diff(&json1, &json1, false)
+            .unwrap()
+            .diff
+            .is_none());
+
+        let mut paths = Paths(Vec::new());
+        JsonDiff::visit(&json1, &json2, &DiffOptions::default(), &mut paths).unwrap();
+        assert_eq!(paths.0, ["/bar/0", "/bar/1", "/bar/2/baz", "/foo"]);
+    }
+}
@@ -0,0 +1,270 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use serde_json::Value;
+
+use crate::diff::JsonDiff;
+use crate::error::DiffError;
+use crate::flatten::ChangeKind;
+use crate::options::DiffOptions;
+use crate::pointer::JsonPointer;
+
+/// A position in the text of a JSON file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Location {
+    /// The line, starting from `1`.
+    pub line: usize,
+    /// The column in characters, starting from `1`.
+    pub column: usize,
+}
+
+/// A JSON structural difference along with the locations of its changes
+/// in the new JSON file.
+pub struct LocatedDiff {
+    /// The JSON structural difference.
+    pub diff: JsonDiff,
+    /// The location of every change, keyed by its path, see
+    /// [`JsonDiff::flatten`].
+    ///
+    /// Added and changed values, moved items and renamed keys are located
+    /// where they appear in the new JSON file, while removed values are
+    /// located at the object or array which contained them.
+    pub locations: BTreeMap<JsonPointer, Location>,
+}
+
+// A lenient scanner of JSON text which records the byte offset of every
+// value. Object members are located at their key. The text is expected to
+// be valid JSON, so it stops at the first unexpected byte.
+struct Scanner<'a> {
+    text: &'a str,
+    position: usize,
+    offsets: BTreeMap<String, usize>,
+}
+
+impl Scanner<'_> {
+    fn peek(&self) -> Option<u8> {
+        self.text.as_bytes().get(self.position).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\t' | b'\r' | b'\n')) {
+            self.position += 1;
+        }
+    }
+
+    fn skip_string(&mut self) {
+        self.position += 1;
+        while let Some(byte) = self.peek() {
+            self.position += 1;
+            match byte {
+                b'\\' => self.position += 1,
+                b'"' => return,
+                _ => {}
+            }
+        }
+    }
+
+    fn value(&mut self, path: &str) -> Option<()> {
+        self.skip_whitespace();
+        self.offsets
+            .entry(String::from(path))
+            .or_insert(self.position);
+        match self.peek()? {
+            b'{' => self.object(path),
+            b'[' => self.array(path),
+            b'"' => {
+                self.skip_string();
+                Some(())
+            }
+            _ => {
+                while !matches!(
+                    self.peek(),
+                    None | Some(b',' | b']' | b'}' | b' ' | b'\t' | b'\r' | b'\n')
+                ) {
+                    self.position += 1;
+                }
+                Some(())
+            }
+        }
+    }
+
+    fn object(&mut self, path: &str) -> Option<()> {
+        self.position += 1;
+        loop {
+            self.skip_whitespace();
+            match self.peek()? {
+                b'}' => {
+                    self.position += 1;
+                    return Some(());
+                }
+                b',' => self.position += 1,
+                b'"' => {
+                    let start = self.position;
+                    self.skip_string();
+                    let key: String =
+                        serde_json::from_str(&self.text[start..self.position]).ok()?;
+                    let key_path = JsonDiff::child_path(path, &key);
+                    // Duplicate keys keep their last value, as `serde_json`.
+                    self.offsets.insert(key_path.clone(), start);
+                    self.skip_whitespace();
+                    if self.peek()? != b':' {
+                        return None;
+                    }
+                    self.position += 1;
+                    self.value(&key_path)?;
+                }
+                _ => return None,
+            }
+        }
+    }
+
+    fn array(&mut self, path: &str) -> Option<()> {
+        self.position += 1;
+        let mut index = 0;
+        loop {
+            self.skip_whitespace();
+            match self.peek()? {
+                b']' => {
+                    self.position += 1;
+                    return Some(());
+                }
+                b',' => self.position += 1,
+                _ => {
+                    self.value(&format!("{path}/{index}"))?;
+                    index += 1;
+                }
+            }
+        }
+    }
+}
+
+/// Returns the location of every value of the JSON text, keyed by its JSON
+/// Pointer.
+fn locate(text: &str) -> BTreeMap<String, Location> {
+    let mut scanner = Scanner {
+        text,
+        position: 0,
+        offsets: BTreeMap::new(),
+    };
+    let _ = scanner.value("");
+
+    let mut locations = BTreeMap::new();
+    for (path, offset) in scanner.offsets {
+        let before = &text[..offset];
+        let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+        let location = Location {
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+        };
+        locations.insert(path, location);
+    }
+    locations
+}
+
+impl JsonDiff {
+    /// Reads and parses the JSON files at the given paths, then finds their
+    /// JSON structural difference using the given options, along with the
+    /// locations of its changes in the second file.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`DiffError`] if a file cannot be read or parsed, or if it
+    /// is nested deeper than [`DiffOptions::max_nesting`].
+    pub fn diff_located(
+        path1: impl AsRef<Path>,
+        path2: impl AsRef<Path>,
+        options: &DiffOptions,
+    ) -> Result<LocatedDiff, DiffError> {
+        let (_, json1) = Self::read_json_file(path1.as_ref())?;
+        let (text2, json2) = Self::read_json_file(path2.as_ref())?;
+
+        let diff = Self::try_diff_with_options(&json1, &json2, options)?;
+
+        let all_locations = locate(&text2);
+        let locations = diff
+            .flatten()
+            .into_iter()
+            .filter_map(|change| {
+                let parent = change.path.parent().unwrap_or_default();
+                // A removed value is located at its parent, while moved
+                // items and renamed keys are located at their new place.
+                let location = match (change.kind, &change.new) {
+                    (ChangeKind::Removed, _) => all_locations.get(&parent.to_string()),
+                    (ChangeKind::Moved, Some(index)) => {
+                        all_locations.get(&format!("{parent}/{index}"))
+                    }
+                    (ChangeKind::Renamed, Some(Value::String(new_key))) => {
+                        all_locations.get(&parent.join_key(new_key).to_string())
+                    }
+                    _ => all_locations.get(&change.path.to_string()),
+                };
+                Some((change.path, *location?))
+            })
+            .collect();
+
+        Ok(LocatedDiff { diff, locations })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{locate, Location};
+    use crate::diff::JsonDiff;
+    use crate::options::DiffOptions;
+    use crate::pointer::JsonPointer;
+
+    #[test]
+    fn test_locate() {
+        let locations = locate("{\n  \"foo\": [1, \"é\", {\"a/b\": null}],\n  \"b\\\"ar\": {}\n}");
+        let at = |line, column| Location { line, column };
+
+        assert_eq!(locations[""], at(1, 1));
+        assert_eq!(locations["/foo"], at(2, 3));
+        assert_eq!(locations["/foo/0"], at(2, 11));
+        assert_eq!(locations["/foo/1"], at(2, 14));
+        assert_eq!(locations["/foo/2"], at(2, 19));
+        assert_eq!(locations["/foo/2/a~1b"], at(2, 20));
+        assert_eq!(locations["/b\"ar"], at(3, 3));
+        assert_eq!(locations.len(), 7);
+    }
+
+    #[test]
+    fn test_diff_located() {
+        let located =
+            JsonDiff::diff_located("data/a.json", "data/b.json", &DiffOptions::default()).unwrap();
+        let at = |line, column| Location { line, column };
+
+        assert!(located.diff.diff.is_some());
+        assert_eq!(
+            located
+                .locations
+                .into_iter()
+                .map(|(path, location)| (path.to_string(), location))
+                .collect::<Vec<_>>(),
+            [
+                ("/boz/0".to_owned(), at(5, 5)),
+                ("/boz/1".to_owned(), at(4, 3)),
+                ("/boz/2".to_owned(), at(4, 3)),
+                ("/boz/5".to_owned(), at(10, 5)),
+                ("/fubar/kaboom/afoo/aboz".to_owned(), at(17, 9)),
+            ]
+        );
+
+        // The locations follow the difference, whatever its options.
+        let options = DiffOptions {
+            detect_moves: true,
+            detect_renames: true,
+            ..DiffOptions::default()
+        };
+        let located = JsonDiff::diff_located("data/a.json", "data/b.json", &options).unwrap();
+        let paths: Vec<JsonPointer> = located
+            .diff
+            .flatten()
+            .into_iter()
+            .map(|change| change.path)
+            .collect();
+        assert!(!located.locations.is_empty());
+        assert!(located.locations.keys().all(|path| paths.contains(path)));
+    }
+}
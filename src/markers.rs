@@ -0,0 +1,334 @@
+use alloc::borrow::{Cow, ToOwned};
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use serde_json::{Map, Value};
+
+/// The keys and suffixes which mark the changes of a JSON structural
+/// difference.
+///
+/// The keys of the JSON files which could be mistaken for a marker, i.e.
+/// those equal to [`DiffMarkers::old`], [`DiffMarkers::new`],
+/// [`DiffMarkers::embedded`](field@DiffMarkers::embedded) or
+/// [`DiffMarkers::text`](field@DiffMarkers::text), ending in
+/// one of the suffixes, or containing [`DiffMarkers::renamed`], are escaped
+/// by appending
+/// [`DiffMarkers::escaped`] to them. For instance, a deleted key
+/// `count__deleted` is reported as `count__deleted__escaped__deleted`.
+///
+/// The markers must not be empty.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DiffMarkers {
+    /// Key of the old side of a changed value.
+    ///
+    /// Defaults to `"__old"`.
+    pub old: String,
+    /// Key of the new side of a changed value.
+    ///
+    /// Defaults to `"__new"`.
+    pub new: String,
+    /// Suffix of the keys only present in the new JSON file.
+    ///
+    /// Defaults to `"__added"`.
+    pub added: String,
+    /// Suffix of the keys only present in the old JSON file.
+    ///
+    /// Defaults to `"__deleted"`.
+    pub deleted: String,
+    /// Suffix of the unchanged keys shown for context, see
+    /// [`DiffOptions::show_context_keys`].
+    ///
+    /// Defaults to `"__unchanged"`.
+    ///
+    /// [`DiffOptions::show_context_keys`]: crate::DiffOptions::show_context_keys
+    pub unchanged: String,
+    /// Suffix of the escaped keys.
+    ///
+    /// Defaults to `"__escaped"`.
+    pub escaped: String,
+    /// Separator of the old and new names of a renamed key, e.g.
+    /// `"color->colour"`.
+    ///
+    /// Defaults to `"->"`.
+    pub renamed: String,
+    /// Key of the difference of two strings containing embedded JSON, see
+    /// [`DiffOptions::parse_embedded_json`].
+    ///
+    /// Defaults to `"__embedded"`.
+    ///
+    /// [`DiffOptions::parse_embedded_json`]: crate::DiffOptions::parse_embedded_json
+    pub embedded: String,
+    /// Key of the difference of two strings compared character by
+    /// character, see [`DiffOptions::text_diff`].
+    ///
+    /// Defaults to `"__text"`.
+    ///
+    /// [`DiffOptions::text_diff`]: field@crate::DiffOptions::text_diff
+    pub text: String,
+    /// Key of the match score of two array items, see
+    /// [`DiffOptions::include_match_scores`]. It is never escaped, since it
+    /// only marks the last item of an array change.
+    ///
+    /// Defaults to `"__score"`.
+    ///
+    /// [`DiffOptions::include_match_scores`]: crate::DiffOptions::include_match_scores
+    pub score: String,
+}
+
+impl Default for DiffMarkers {
+    fn default() -> Self {
+        Self {
+            old: "__old".to_owned(),
+            new: "__new".to_owned(),
+            added: "__added".to_owned(),
+            deleted: "__deleted".to_owned(),
+            unchanged: "__unchanged".to_owned(),
+            escaped: "__escaped".to_owned(),
+            renamed: "->".to_owned(),
+            embedded: "__embedded".to_owned(),
+            text: "__text".to_owned(),
+            score: "__score".to_owned(),
+        }
+    }
+}
+
+/// A key of an object of a JSON structural difference, already unescaped.
+pub(crate) enum MarkedKey<'a> {
+    Added(&'a str),
+    Deleted(&'a str),
+    Unchanged(&'a str),
+    Shared(&'a str),
+    Renamed(&'a str, &'a str),
+}
+
+impl DiffMarkers {
+    fn escape<'a>(&self, key: &'a str) -> Cow<'a, str> {
+        if key == self.old
+            || key == self.new
+            || key == self.embedded
+            || key == self.text
+            || key.ends_with(&self.added)
+            || key.ends_with(&self.deleted)
+            || key.ends_with(&self.unchanged)
+            || key.ends_with(&self.escaped)
+            || key.contains(&self.renamed)
+        {
+            Cow::Owned(format!("{key}{}", self.escaped))
+        } else {
+            Cow::Borrowed(key)
+        }
+    }
+
+    fn unescape<'a>(&self, key: &'a str) -> &'a str {
+        key.strip_suffix(self.escaped.as_str()).unwrap_or(key)
+    }
+
+    /// Returns the key reporting `key` as only present in the new JSON file.
+    pub(crate) fn added_key(&self, key: &str) -> String {
+        format!("{}{}", self.escape(key), self.added)
+    }
+
+    /// Returns the key reporting `key` as only present in the old JSON file.
+    pub(crate) fn deleted_key(&self, key: &str) -> String {
+        format!("{}{}", self.escape(key), self.deleted)
+    }
+
+    /// Returns the key showing `key` as unchanged, for context.
+    pub(crate) fn unchanged_key(&self, key: &str) -> String {
+        format!("{}{}", self.escape(key), self.unchanged)
+    }
+
+    /// Returns the key reporting a change of the value of `key`.
+    pub(crate) fn shared_key<'a>(&self, key: &'a str) -> Cow<'a, str> {
+        self.escape(key)
+    }
+
+    /// Returns `true` if `old_key` can be reported as renamed to `new_key`,
+    /// i.e. if none of them would need to be escaped.
+    pub(crate) fn can_rename(&self, old_key: &str, new_key: &str) -> bool {
+        let is_escaped = |key| matches!(self.escape(key), Cow::Owned(_));
+        !(is_escaped(old_key) || is_escaped(new_key))
+    }
+
+    /// Returns the key reporting `old_key` as renamed to `new_key`.
+    pub(crate) fn renamed_key(&self, old_key: &str, new_key: &str) -> String {
+        format!("{old_key}{}{new_key}", self.renamed)
+    }
+
+    /// Returns the change from `old` to `new`.
+    ///
+    /// The old side is inserted first, so it comes first when `Map` keeps
+    /// the insertion order, i.e. with the `preserve_order` feature of
+    /// `serde_json`. Otherwise the keys are sorted, which is why a
+    /// serialized [`JsonDiff`] always writes the old side first.
+    ///
+    /// [`JsonDiff`]: crate::JsonDiff
+    pub(crate) fn change(&self, old: &Value, new: &Value) -> Value {
+        let mut change = Map::new();
+        change.insert(self.old.clone(), old.clone());
+        change.insert(self.new.clone(), new.clone());
+        Value::Object(change)
+    }
+
+    /// Returns the old and new sides of `obj` if it is a change.
+    pub(crate) fn as_change<'a>(
+        &self,
+        obj: &'a Map<String, Value>,
+    ) -> Option<(&'a Value, &'a Value)> {
+        if obj.len() == 2 {
+            Some((obj.get(&self.old)?, obj.get(&self.new)?))
+        } else {
+            None
+        }
+    }
+
+    /// Returns the difference `diff` of the JSON embedded in two strings.
+    pub(crate) fn embedded(&self, diff: Value) -> Value {
+        let mut embedded = Map::new();
+        embedded.insert(self.embedded.clone(), diff);
+        Value::Object(embedded)
+    }
+
+    /// Returns the difference of the embedded JSON if `obj` is one.
+    pub(crate) fn as_embedded<'a>(&self, obj: &'a Map<String, Value>) -> Option<&'a Value> {
+        if obj.len() == 1 {
+            obj.get(&self.embedded)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the difference `segments` of two strings compared character
+    /// by character.
+    pub(crate) fn text(&self, segments: Value) -> Value {
+        let mut text = Map::new();
+        text.insert(self.text.clone(), segments);
+        Value::Object(text)
+    }
+
+    /// Returns the segments of the difference of two strings if `obj` is
+    /// one.
+    pub(crate) fn as_text<'a>(&self, obj: &'a Map<String, Value>) -> Option<&'a [Value]> {
+        if obj.len() == 1 {
+            obj.get(&self.text)?.as_array().map(Vec::as_slice)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the old and new strings of the segments of the difference of
+    /// two strings.
+    pub(crate) fn text_sides(segments: &[Value]) -> (String, String) {
+        let (mut old, mut new) = (String::new(), String::new());
+        for segment in segments {
+            let text = segment.get(1).and_then(Value::as_str).unwrap_or_default();
+            match segment.get(0).and_then(Value::as_str) {
+                Some(" ") => {
+                    old.push_str(text);
+                    new.push_str(text);
+                }
+                Some("-") => old.push_str(text),
+                Some("+") => new.push_str(text),
+                _ => {}
+            }
+        }
+        (old, new)
+    }
+
+    /// Returns the match score of two array items.
+    pub(crate) fn score(&self, score: f64) -> Value {
+        let mut obj = Map::new();
+        obj.insert(self.score.clone(), Value::from(score));
+        Value::Object(obj)
+    }
+
+    /// Returns an entry of the difference of two arrays without its match
+    /// score, if any.
+    pub(crate) fn without_score<'a>(&self, entry: &'a [Value]) -> &'a [Value] {
+        match entry.split_last() {
+            Some((Value::Object(obj), rest))
+                if rest.len() >= 2 && obj.len() == 1 && obj.contains_key(&self.score) =>
+            {
+                rest
+            }
+            _ => entry,
+        }
+    }
+
+    /// Parses a key of an object of a JSON structural difference.
+    pub(crate) fn parse_key<'a>(&self, key: &'a str) -> MarkedKey<'a> {
+        if let Some(key) = key.strip_suffix(self.deleted.as_str()) {
+            MarkedKey::Deleted(self.unescape(key))
+        } else if let Some(key) = key.strip_suffix(self.added.as_str()) {
+            MarkedKey::Added(self.unescape(key))
+        } else if let Some(key) = key.strip_suffix(self.unchanged.as_str()) {
+            MarkedKey::Unchanged(self.unescape(key))
+        } else if let Some(key) = key.strip_suffix(self.escaped.as_str()) {
+            MarkedKey::Shared(key)
+        } else if let Some((old_key, new_key)) = key.split_once(self.renamed.as_str()) {
+            MarkedKey::Renamed(old_key, new_key)
+        } else {
+            MarkedKey::Shared(key)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use alloc::borrow::ToOwned;
+    use alloc::format;
+    use alloc::string::String;
+
+    use super::{DiffMarkers, MarkedKey};
+
+    #[test]
+    fn test_escape_keys() {
+        let markers = DiffMarkers::default();
+        let parse = |key: &str| -> (char, String) {
+            match markers.parse_key(key) {
+                MarkedKey::Added(key) => ('+', key.to_owned()),
+                MarkedKey::Deleted(key) => ('-', key.to_owned()),
+                MarkedKey::Unchanged(key) => ('=', key.to_owned()),
+                MarkedKey::Shared(key) => (' ', key.to_owned()),
+                MarkedKey::Renamed(old_key, new_key) => ('>', format!("{old_key}/{new_key}")),
+            }
+        };
+
+        for key in [
+            "foo",
+            "__old",
+            "__new",
+            "__old_",
+            "__embedded",
+            "__text",
+            "count__added",
+            "count__deleted",
+            "count__unchanged",
+            "count__escaped",
+            "count__deleted__escaped",
+            "a->b",
+        ] {
+            assert_eq!(parse(&markers.added_key(key)), ('+', key.to_owned()));
+            assert_eq!(parse(&markers.deleted_key(key)), ('-', key.to_owned()));
+            assert_eq!(parse(&markers.unchanged_key(key)), ('=', key.to_owned()));
+            assert_eq!(parse(&markers.shared_key(key)), (' ', key.to_owned()));
+        }
+        assert_eq!(markers.shared_key("foo"), "foo");
+        assert_eq!(markers.added_key("foo"), "foo__added");
+        assert_eq!(
+            markers.deleted_key("count__deleted"),
+            "count__deleted__escaped__deleted"
+        );
+        assert_eq!(markers.shared_key("__old"), "__old__escaped");
+        assert_eq!(markers.shared_key("a->b"), "a->b__escaped");
+
+        let renamed_key = markers.renamed_key("color", "colour");
+        assert_eq!(renamed_key, "color->colour");
+        assert_eq!(parse(&renamed_key), ('>', "color/colour".to_owned()));
+        assert!(markers.can_rename("color", "colour"));
+        assert!(!markers.can_rename("a->b", "c"));
+        assert!(!markers.can_rename("a", "count__added"));
+    }
+}
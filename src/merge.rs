@@ -0,0 +1,518 @@
+use alloc::collections::BTreeSet;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use serde_json::{Map, Value};
+
+use crate::diff::JsonDiff;
+use crate::filter::DiffFilter;
+use crate::options::DiffOptions;
+use crate::pointer::JsonPointer;
+use crate::tree::{ArrayOp, DiffNode};
+
+/// A change made on both sides of a three-way merge which cannot be
+/// combined.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Conflict {
+    /// JSON Pointer to the conflicting value in the base JSON file, or to
+    /// the key added on both sides.
+    pub path: JsonPointer,
+    /// The value on our side.
+    ///
+    /// If `None`: the value has been removed on our side.
+    pub ours: Option<Value>,
+    /// The value on their side.
+    ///
+    /// If `None`: the value has been removed on their side.
+    pub theirs: Option<Value>,
+}
+
+/// The outcome of a three-way merge.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Merge3Result {
+    /// The merged JSON file.
+    ///
+    /// Where a conflict occurs, it contains the value on our side.
+    pub merged: Value,
+    /// The conflicts found while merging, in the order of their keys and
+    /// items.
+    ///
+    /// If empty: the two sides have been merged cleanly.
+    pub conflicts: Vec<Conflict>,
+}
+
+impl Merge3Result {
+    /// Returns `true` if the two sides have been merged without conflicts.
+    #[must_use]
+    pub fn is_clean(&self) -> bool {
+        self.conflicts.is_empty()
+    }
+}
+
+/// The base, our side and their side of a three-way merge.
+type Sides<'a, T> = (&'a T, &'a T, &'a T);
+
+/// What a side has done with an item of the base array.
+enum ItemEdit<'a> {
+    /// Kept at the given index of the side.
+    Kept(usize),
+    /// Changed by the difference, and kept at the given index of the side.
+    Changed(&'a DiffNode, usize),
+    Removed,
+}
+
+/// The edits made by a side to the base array, item by item.
+struct ArrayEdits<'a> {
+    items: Vec<ItemEdit<'a>>,
+    /// The indices of the items of the side inserted before every item of
+    /// the base array, the last ones being appended.
+    inserted: Vec<Vec<usize>>,
+}
+
+impl<'a> ArrayEdits<'a> {
+    /// Maps the operations of the difference between the base array and a
+    /// side to the items of the base array, or returns `None` if they do
+    /// not match the arrays.
+    fn new(ops: &'a [ArrayOp], base_len: usize, side_len: usize) -> Option<Self> {
+        let mut items: Vec<Option<ItemEdit<'a>>> = (0..base_len).map(|_| None).collect();
+        let mut inserted = vec![Vec::new(); base_len + 1];
+        let (mut old_index, mut new_index) = (0, 0);
+        // The indices included in the operations take precedence over the
+        // counted ones.
+        for op in ops {
+            match op {
+                ArrayOp::Unchanged(_) => {
+                    *items.get_mut(old_index)? = Some(ItemEdit::Kept(new_index));
+                    old_index += 1;
+                    new_index += 1;
+                }
+                ArrayOp::Added { index, .. } => {
+                    new_index = index.unwrap_or(new_index);
+                    inserted.get_mut(old_index)?.push(new_index);
+                    new_index += 1;
+                }
+                ArrayOp::Removed { index, .. } => {
+                    old_index = index.unwrap_or(old_index);
+                    *items.get_mut(old_index)? = Some(ItemEdit::Removed);
+                    old_index += 1;
+                }
+                ArrayOp::Changed {
+                    diff,
+                    old_index: old,
+                    new_index: new,
+                } => {
+                    old_index = old.unwrap_or(old_index);
+                    new_index = new.unwrap_or(new_index);
+                    *items.get_mut(old_index)? = Some(ItemEdit::Changed(diff, new_index));
+                    old_index += 1;
+                    new_index += 1;
+                }
+                ArrayOp::Moved { .. } => return None,
+            }
+        }
+        let items = items.into_iter().collect::<Option<Vec<_>>>()?;
+        let is_valid = |index: usize| index < side_len;
+        let all_valid = items.iter().all(|edit| match edit {
+            ItemEdit::Kept(index) | ItemEdit::Changed(_, index) => is_valid(*index),
+            ItemEdit::Removed => true,
+        }) && inserted.iter().flatten().all(|index| is_valid(*index));
+        all_valid.then_some(Self { items, inserted })
+    }
+}
+
+/// Two sides of a three-way merge along with the options comparing them.
+struct Merger<'a> {
+    options: &'a DiffOptions,
+    conflicts: Vec<Conflict>,
+}
+
+impl Merger<'_> {
+    fn conflict(&mut self, path: &JsonPointer, ours: Option<&Value>, theirs: Option<&Value>) {
+        self.conflicts.push(Conflict {
+            path: path.clone(),
+            ours: ours.cloned(),
+            theirs: theirs.cloned(),
+        });
+    }
+
+    /// Returns `true` if the two values are equal with the options.
+    fn are_equal(&self, ours: &Value, theirs: &Value) -> bool {
+        JsonDiff::diff_with_options(ours, theirs, self.options)
+            .diff
+            .is_none()
+    }
+
+    /// Merges a value changed on both sides.
+    fn merge_value(
+        &mut self,
+        (base, ours, theirs): Sides<'_, Value>,
+        (node_ours, node_theirs): (&DiffNode, &DiffNode),
+        path: &JsonPointer,
+    ) -> Value {
+        match (node_ours, node_theirs) {
+            (DiffNode::Unchanged, _) => return theirs.clone(),
+            (_, DiffNode::Unchanged) => return ours.clone(),
+            (DiffNode::Object { .. }, DiffNode::Object { .. }) => {
+                if let (Value::Object(base), Value::Object(ours), Value::Object(theirs)) =
+                    (base, ours, theirs)
+                {
+                    return Value::Object(self.merge_objects(
+                        (base, ours, theirs),
+                        (node_ours, node_theirs),
+                        path,
+                    ));
+                }
+            }
+            (DiffNode::Array(ops_ours), DiffNode::Array(ops_theirs)) => {
+                if let (Value::Array(base), Value::Array(ours), Value::Array(theirs)) =
+                    (base, ours, theirs)
+                {
+                    if let (Some(edits_ours), Some(edits_theirs)) = (
+                        ArrayEdits::new(ops_ours, base.len(), ours.len()),
+                        ArrayEdits::new(ops_theirs, base.len(), theirs.len()),
+                    ) {
+                        return Value::Array(self.merge_arrays(
+                            (base, ours, theirs),
+                            (&edits_ours, &edits_theirs),
+                            path,
+                        ));
+                    }
+                }
+            }
+            _ => {}
+        }
+        if !self.are_equal(ours, theirs) {
+            self.conflict(path, Some(ours), Some(theirs));
+        }
+        ours.clone()
+    }
+
+    fn merge_objects(
+        &mut self,
+        (base, ours, theirs): Sides<'_, Map<String, Value>>,
+        (node_ours, node_theirs): (&DiffNode, &DiffNode),
+        path: &JsonPointer,
+    ) -> Map<String, Value> {
+        let (
+            DiffNode::Object {
+                added: added_ours,
+                deleted: deleted_ours,
+                changed: changed_ours,
+                ..
+            },
+            DiffNode::Object {
+                added: added_theirs,
+                deleted: deleted_theirs,
+                changed: changed_theirs,
+                ..
+            },
+        ) = (node_ours, node_theirs)
+        else {
+            return ours.clone();
+        };
+
+        // Our side is kept, except where only their side has changed. The
+        // keys changed on their side are visited in order, so that the
+        // conflicts are too.
+        let mut merged = ours.clone();
+        let keys: BTreeSet<&String> = added_theirs
+            .keys()
+            .chain(deleted_theirs.keys())
+            .chain(changed_theirs.keys())
+            .collect();
+        for key in keys {
+            let key_path = path.join_key(key);
+            if let Some(value) = added_theirs.get(key) {
+                match added_ours.get(key) {
+                    Some(ours) if !self.are_equal(ours, value) => {
+                        self.conflict(&key_path, Some(ours), Some(value));
+                    }
+                    Some(_) => {}
+                    None => {
+                        merged.insert(key.clone(), value.clone());
+                    }
+                }
+            } else if deleted_theirs.contains_key(key) {
+                if changed_ours.contains_key(key) {
+                    self.conflict(&key_path, ours.get(key), None);
+                } else {
+                    merged.remove(key);
+                }
+            } else if let Some(node_theirs) = changed_theirs.get(key) {
+                match (base.get(key), ours.get(key), theirs.get(key)) {
+                    (_, None, theirs) if deleted_ours.contains_key(key) => {
+                        self.conflict(&key_path, None, theirs);
+                    }
+                    (Some(base), Some(ours), Some(theirs)) => {
+                        let node_ours = changed_ours.get(key).unwrap_or(&DiffNode::Unchanged);
+                        let value = self.merge_value(
+                            (base, ours, theirs),
+                            (node_ours, node_theirs),
+                            &key_path,
+                        );
+                        merged.insert(key.clone(), value);
+                    }
+                    // The keys are not matched exactly, e.g. with
+                    // `DiffOptions::case_insensitive_keys`, so our side is
+                    // kept.
+                    (_, ours, theirs) => self.conflict(&key_path, ours, theirs),
+                }
+            }
+        }
+        merged
+    }
+
+    fn merge_arrays(
+        &mut self,
+        (base, ours, theirs): Sides<'_, [Value]>,
+        (edits_ours, edits_theirs): (&ArrayEdits<'_>, &ArrayEdits<'_>),
+        path: &JsonPointer,
+    ) -> Vec<Value> {
+        let mut merged = Vec::new();
+        for index in 0..=base.len() {
+            // The items inserted at the same place are all kept, once if
+            // they are the same.
+            let inserted_ours: Vec<&Value> = edits_ours.inserted[index]
+                .iter()
+                .map(|&index| &ours[index])
+                .collect();
+            let inserted_theirs: Vec<&Value> = edits_theirs.inserted[index]
+                .iter()
+                .map(|&index| &theirs[index])
+                .collect();
+            merged.extend(inserted_ours.iter().copied().cloned());
+            if inserted_ours != inserted_theirs {
+                merged.extend(inserted_theirs.into_iter().cloned());
+            }
+
+            let Some(item) = base.get(index) else {
+                break;
+            };
+            let item_path = path.join_index(index);
+            match (&edits_ours.items[index], &edits_theirs.items[index]) {
+                (ItemEdit::Kept(index), ItemEdit::Kept(_)) => merged.push(ours[*index].clone()),
+                (ItemEdit::Kept(_), ItemEdit::Changed(_, index)) => {
+                    merged.push(theirs[*index].clone());
+                }
+                (ItemEdit::Changed(_, index), ItemEdit::Kept(_)) => {
+                    merged.push(ours[*index].clone());
+                }
+                (
+                    ItemEdit::Changed(node_ours, index_ours),
+                    ItemEdit::Changed(node_theirs, index_theirs),
+                ) => {
+                    merged.push(self.merge_value(
+                        (item, &ours[*index_ours], &theirs[*index_theirs]),
+                        (node_ours, node_theirs),
+                        &item_path,
+                    ));
+                }
+                (ItemEdit::Changed(_, index), ItemEdit::Removed) => {
+                    self.conflict(&item_path, Some(&ours[*index]), None);
+                    merged.push(ours[*index].clone());
+                }
+                (ItemEdit::Removed, ItemEdit::Changed(_, index)) => {
+                    self.conflict(&item_path, None, Some(&theirs[*index]));
+                }
+                (ItemEdit::Removed, _) | (_, ItemEdit::Removed) => {}
+            }
+        }
+        merged
+    }
+}
+
+impl JsonDiff {
+    /// Merges the changes made to `base` by `ours` and `theirs`, found by
+    /// comparing `base` with each of them using the given options.
+    ///
+    /// A value changed on a single side takes that change. A value changed
+    /// on both sides is merged recursively if it is an object, or an array
+    /// whose items are matched as their differences match them. Any other
+    /// value changed differently on both sides is a conflict. The items
+    /// inserted at the same place on both sides are all kept, ours first.
+    ///
+    /// The arrays are merged item by item, so they are compared in order,
+    /// whatever [`DiffOptions::array_unordered`] and
+    /// [`DiffOptions::unordered_paths`], and their moved items and renamed
+    /// keys are not detected. The differences hidden by the options, e.g.
+    /// the values with [`DiffOptions::keys_only`], are taken from our side.
+    ///
+    /// The JSON files nested deeper than [`DiffOptions::max_nesting`] are
+    /// compared as a whole, as [`JsonDiff::diff_with_options`] does.
+    #[must_use]
+    pub fn merge3(
+        base: &Value,
+        ours: &Value,
+        theirs: &Value,
+        options: &DiffOptions,
+    ) -> Merge3Result {
+        let (base, ours, theirs) = (
+            options.normalize(base),
+            options.normalize(ours),
+            options.normalize(theirs),
+        );
+        let options = DiffOptions {
+            detect_moves: false,
+            detect_renames: false,
+            max_changes: None,
+            filter: DiffFilter::All,
+            include_indices: true,
+            array_unordered: false,
+            unordered_paths: Vec::new(),
+            normalizers: Vec::new(),
+            ..options.clone()
+        };
+        let tree = |side: &Value| {
+            Self::diff_with_options(&base, side, &options)
+                .tree()
+                .unwrap_or_else(|| DiffNode::Scalar {
+                    old: base.as_ref().clone(),
+                    new: side.clone(),
+                })
+        };
+        let (node_ours, node_theirs) = (tree(&ours), tree(&theirs));
+
+        let mut state = Merger {
+            options: &options,
+            conflicts: Vec::new(),
+        };
+        let merged = state.merge_value(
+            (&base, &ours, &theirs),
+            (&node_ours, &node_theirs),
+            &JsonPointer::new(),
+        );
+        Merge3Result {
+            merged,
+            conflicts: state.conflicts,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::Conflict;
+    use crate::diff::JsonDiff;
+    use crate::options::DiffOptions;
+    use crate::pointer::JsonPointer;
+
+    #[test]
+    fn test_merge3() {
+        let options = DiffOptions::default();
+        let base = json!({"foo": 1, "bar": [1, 2, 3], "baz": {"qux": true}});
+
+        // Disjoint edits
+        let result = JsonDiff::merge3(
+            &base,
+            &json!({"foo": 2, "bar": [1, 2, 3], "baz": {"qux": true}}),
+            &json!({"foo": 1, "bar": [1, 5, 3], "baz": {"qux": true, "quux": null}}),
+            &options,
+        );
+        assert!(result.is_clean());
+        assert_eq!(
+            result.merged,
+            json!({"foo": 2, "bar": [1, 5, 3], "baz": {"qux": true, "quux": null}})
+        );
+
+        // Identical edits
+        let edited = json!({"foo": 2, "bar": [1, 2], "baz": {"qux": false}});
+        let result = JsonDiff::merge3(&base, &edited, &edited, &options);
+        assert!(result.is_clean());
+        assert_eq!(result.merged, edited);
+
+        // Conflicting edits
+        let result = JsonDiff::merge3(
+            &base,
+            &json!({"foo": 2, "bar": [1, 2, 3], "baz": {"qux": false}}),
+            &json!({"foo": 3, "bar": [1, 2, 3, 4], "baz": {}}),
+            &options,
+        );
+        let conflict = |path, ours, theirs| Conflict {
+            path: JsonPointer::parse(path).unwrap(),
+            ours,
+            theirs,
+        };
+        assert_eq!(
+            result.conflicts,
+            [
+                conflict("/baz/qux", Some(json!(false)), None),
+                conflict("/foo", Some(json!(2)), Some(json!(3))),
+            ]
+        );
+        assert_eq!(
+            result.merged,
+            json!({"foo": 2, "bar": [1, 2, 3, 4], "baz": {"qux": false}})
+        );
+    }
+
+    #[test]
+    fn test_merge3_arrays() {
+        let options = DiffOptions::default();
+
+        // Insertions at different places of an array are both kept.
+        let result = JsonDiff::merge3(
+            &json!([1, 2, 3]),
+            &json!([0, 1, 2, 3]),
+            &json!([1, 2, 3, 4]),
+            &options,
+        );
+        assert!(result.is_clean());
+        assert_eq!(result.merged, json!([0, 1, 2, 3, 4]));
+
+        // The items are matched as the differences match them.
+        let base = json!([{"id": 1, "a": 1, "b": 1}, {"id": 2, "a": 2, "b": 2}]);
+        let result = JsonDiff::merge3(
+            &base,
+            &json!([{"id": 0}, {"id": 1, "a": 9, "b": 1}, {"id": 2, "a": 2, "b": 2}]),
+            &json!([{"id": 1, "a": 1, "b": 8}, {"id": 2, "a": 2, "b": 2}]),
+            &options,
+        );
+        assert!(result.is_clean());
+        assert_eq!(
+            result.merged,
+            json!([{"id": 0}, {"id": 1, "a": 9, "b": 8}, {"id": 2, "a": 2, "b": 2}])
+        );
+
+        // An item removed on one side and changed on the other one.
+        let result = JsonDiff::merge3(
+            &base,
+            &json!([{"id": 2, "a": 2, "b": 2}]),
+            &json!([{"id": 1, "a": 1, "b": 8}, {"id": 2, "a": 2, "b": 2}]),
+            &options,
+        );
+        assert_eq!(result.merged, json!([{"id": 2, "a": 2, "b": 2}]));
+        assert_eq!(
+            result.conflicts,
+            [Conflict {
+                path: JsonPointer::parse("/0").unwrap(),
+                ours: None,
+                theirs: Some(json!({"id": 1, "a": 1, "b": 8})),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_merge3_options() {
+        // The values equal with the options do not conflict.
+        let options = DiffOptions {
+            epsilon: Some(0.01),
+            ..DiffOptions::default()
+        };
+        let result = JsonDiff::merge3(
+            &json!({"a": 1.0}),
+            &json!({"a": 2.0}),
+            &json!({"a": 2.001}),
+            &options,
+        );
+        assert!(result.is_clean());
+        assert_eq!(result.merged, json!({"a": 2.0}));
+        assert!(!JsonDiff::merge3(
+            &json!({"a": 1.0}),
+            &json!({"a": 2.0}),
+            &json!({"a": 2.1}),
+            &options,
+        )
+        .is_clean());
+    }
+}
@@ -0,0 +1,382 @@
+use alloc::collections::BTreeSet;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use serde_json::{Map, Value};
+
+use crate::diff::JsonDiff;
+use crate::tree::{ArrayOp, DiffNode, TextSegment};
+
+impl DiffNode {
+    /// Returns the JSON Merge Patch, as defined by RFC 7386, which applies
+    /// the difference represented by the node to `original`, as
+    /// [`JsonDiff::to_merge_patch`] builds it.
+    ///
+    /// The changed arrays are replaced as a whole, so they are rebuilt from
+    /// `original`. Returns `None` if the node does not apply to `original`,
+    /// if it changes the JSON embedded in a string, or if the patch would
+    /// have to set a `null` member.
+    #[must_use]
+    pub fn to_merge_patch(&self, original: &Value) -> Option<Value> {
+        let (
+            Self::Object {
+                added,
+                deleted,
+                changed,
+                renamed,
+            },
+            Value::Object(original),
+        ) = (self, original)
+        else {
+            // The values replaced as a whole may still be objects.
+            return JsonDiff::to_merge_patch(original, &self.apply(original)?);
+        };
+
+        let mut patch = Map::new();
+        for key in deleted.keys().chain(renamed.keys()) {
+            patch.insert(key.clone(), Value::Null);
+        }
+        let mut set = |key: &String, value: Value| {
+            // A `null` patch would delete the key.
+            (!value.is_null()).then(|| patch.insert(key.clone(), value))
+        };
+        for (key, node) in changed {
+            set(key, node.to_merge_patch(original.get(key)?)?)?;
+        }
+        for (old_key, (new_key, node)) in renamed {
+            let value = node.apply(original.get(old_key)?)?;
+            if JsonDiff::has_null_member(&value) {
+                return None;
+            }
+            set(new_key, value)?;
+        }
+        for (key, value) in added {
+            if JsonDiff::has_null_member(value) {
+                return None;
+            }
+            set(key, value.clone())?;
+        }
+        Some(Value::Object(patch))
+    }
+
+    /// Returns the new value of the difference represented by the node,
+    /// given the old one, or `None` if the node does not apply to it.
+    fn apply(&self, old: &Value) -> Option<Value> {
+        let new = match (self, old) {
+            (Self::Unchanged, _) => old.clone(),
+            (Self::Scalar { new, .. }, _) => new.clone(),
+            (
+                Self::Object {
+                    added,
+                    deleted,
+                    changed,
+                    renamed,
+                },
+                Value::Object(old),
+            ) => {
+                let mut new = old.clone();
+                for key in deleted.keys() {
+                    new.remove(key)?;
+                }
+                for (key, node) in changed {
+                    let value = node.apply(old.get(key)?)?;
+                    new.insert(key.clone(), value);
+                }
+                for (old_key, (new_key, node)) in renamed {
+                    let value = node.apply(&new.remove(old_key)?)?;
+                    new.insert(new_key.clone(), value);
+                }
+                new.extend(added.clone());
+                Value::Object(new)
+            }
+            (Self::Array(ops), Value::Array(old)) => Value::Array(apply_ops(ops, old)?),
+            (Self::Text(segments), Value::String(_)) => Value::String(
+                segments
+                    .iter()
+                    .filter_map(|segment| match segment {
+                        TextSegment::Kept(text) | TextSegment::Added(text) => Some(text.as_str()),
+                        TextSegment::Removed(_) => None,
+                    })
+                    .collect(),
+            ),
+            _ => return None,
+        };
+        Some(new)
+    }
+}
+
+/// Returns the new items of the difference of two arrays, given the old
+/// ones, or `None` if the operations do not apply to them.
+///
+/// The moved items are placed at their new index, and the other items
+/// follow each other around them.
+fn apply_ops(ops: &[ArrayOp], old: &[Value]) -> Option<Vec<Value>> {
+    let mut moved: Vec<(usize, usize)> = Vec::new();
+    for op in ops {
+        if let ArrayOp::Moved { from, to } = op {
+            moved.push((*to, *from));
+        }
+    }
+    let moved_from: BTreeSet<usize> = moved.iter().map(|&(_, from)| from).collect();
+    let mut old_items = (0..old.len())
+        .filter(|index| !moved_from.contains(index))
+        .map(|index| &old[index]);
+
+    let mut items = Vec::new();
+    for op in ops {
+        match op {
+            ArrayOp::Unchanged(_) => items.push(old_items.next()?.clone()),
+            ArrayOp::Changed { diff, .. } => items.push(diff.apply(old_items.next()?)?),
+            ArrayOp::Removed { .. } => {
+                old_items.next()?;
+            }
+            ArrayOp::Added { value, .. } => items.push(value.clone()),
+            ArrayOp::Moved { .. } => {}
+        }
+    }
+    if old_items.next().is_some() {
+        return None;
+    }
+    moved.sort_unstable();
+    for (to, from) in moved {
+        if to > items.len() {
+            return None;
+        }
+        items.insert(to, old.get(from)?.clone());
+    }
+    Some(items)
+}
+
+impl JsonDiff {
+    /// Builds a JSON Merge Patch, as defined by RFC 7386, which turns
+    /// `original` into `target`.
+    ///
+    /// Deleted keys are set to `null`, while changed objects are patched
+    /// recursively. A merge patch cannot express a partial change of an
+    /// array, so a changed array is replaced as a whole, as any other
+    /// changed value.
+    ///
+    /// A merge patch cannot express a `null` value of an object either,
+    /// since `null` deletes a key: `None` is returned if the patch would
+    /// have to set one.
+    #[must_use]
+    pub fn to_merge_patch(original: &Value, target: &Value) -> Option<Value> {
+        let (Value::Object(original), Value::Object(target)) = (original, target) else {
+            return (!Self::has_null_member(target)).then(|| target.clone());
+        };
+
+        let mut patch = Map::new();
+        for key in original.keys() {
+            if !target.contains_key(key) {
+                patch.insert(key.clone(), Value::Null);
+            }
+        }
+        for (key, value) in target {
+            match original.get(key) {
+                Some(old_value) if old_value == value => {}
+                _ if value.is_null() => return None,
+                Some(old_value) => {
+                    patch.insert(key.clone(), Self::to_merge_patch(old_value, value)?);
+                }
+                None => {
+                    if Self::has_null_member(value) {
+                        return None;
+                    }
+                    patch.insert(key.clone(), value.clone());
+                }
+            }
+        }
+        Some(Value::Object(patch))
+    }
+
+    /// Returns the JSON Merge Patch, as defined by RFC 7386, which applies
+    /// the JSON structural difference to `original`, without comparing the
+    /// JSON files again, as [`DiffNode::to_merge_patch`] does.
+    ///
+    /// Returns `None` when the difference cannot be parsed, when it does not
+    /// apply to `original`, or when the patch would have to set a `null`
+    /// member.
+    #[must_use]
+    pub fn merge_patch(&self, original: &Value) -> Option<Value> {
+        self.tree()?.to_merge_patch(original)
+    }
+
+    /// Returns `true` if an object of `value`, outside of its arrays, has a
+    /// `null` member, which a merge patch would delete.
+    pub(crate) fn has_null_member(value: &Value) -> bool {
+        match value {
+            Value::Object(map) => map
+                .values()
+                .any(|value| value.is_null() || Self::has_null_member(value)),
+            _ => false,
+        }
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+
+    use serde_json::{Map, Value};
+
+    use crate::diff::JsonDiff;
+    use crate::options::DiffOptions;
+
+    // The `MergePatch` function of RFC 7386.
+    fn apply_merge_patch(target: &Value, patch: &Value) -> Value {
+        let Value::Object(patch) = patch else {
+            return patch.clone();
+        };
+        let mut target = match target {
+            Value::Object(target) => target.clone(),
+            _ => Map::new(),
+        };
+        for (key, value) in patch {
+            if value.is_null() {
+                target.remove(key);
+            } else {
+                let old_value = target.get(key).cloned().unwrap_or(Value::Null);
+                target.insert(key.clone(), apply_merge_patch(&old_value, value));
+            }
+        }
+        Value::Object(target)
+    }
+
+    #[test]
+    fn test_to_merge_patch() {
+        let original = json!({
+            "title": "Goodbye!",
+            "author": {"givenName": "John", "familyName": "Doe"},
+            "tags": ["example", "sample"],
+            "content": "This will be unchanged",
+            "metadata": {"version": 1, "draft": true}
+        });
+        let target = json!({
+            "title": "Hello!",
+            "author": {"givenName": "John"},
+            "tags": ["example"],
+            "content": "This will be unchanged",
+            "metadata": [1],
+            "phoneNumber": "+01-123-456-7890",
+            "address": {"city": "Rome", "country": {"code": "IT"}}
+        });
+
+        let patch = JsonDiff::to_merge_patch(&original, &target).unwrap();
+        assert_eq!(
+            patch,
+            json!({
+                "title": "Hello!",
+                "author": {"familyName": null},
+                "tags": ["example"],
+                "metadata": [1],
+                "phoneNumber": "+01-123-456-7890",
+                "address": {"city": "Rome", "country": {"code": "IT"}}
+            })
+        );
+        assert_eq!(apply_merge_patch(&original, &patch), target);
+
+        let original =
+            serde_json::from_str(&std::fs::read_to_string("data/a.json").unwrap()).unwrap();
+        let target =
+            serde_json::from_str(&std::fs::read_to_string("data/b.json").unwrap()).unwrap();
+        let patch = JsonDiff::to_merge_patch(&original, &target).unwrap();
+        assert_eq!(
+            patch,
+            json!({
+                "boz": [0, 1, 4, 5, 6, 7],
+                "fubar": {"kaboom": {"afoo": {"aboz": "zozoba"}}}
+            })
+        );
+        assert_eq!(apply_merge_patch(&original, &patch), target);
+
+        // Identical values need no patch, while non-objects are replaced.
+        assert_eq!(
+            JsonDiff::to_merge_patch(&original, &original),
+            Some(json!({}))
+        );
+        assert_eq!(
+            JsonDiff::to_merge_patch(&json!([1]), &json!(2)),
+            Some(json!(2))
+        );
+    }
+
+    #[test]
+    fn test_to_merge_patch_null() {
+        // The `null` values of arrays and unchanged keys are kept.
+        let original = json!({"foo": null, "bar": {"baz": 1}});
+        let target = json!({"foo": null, "bar": [null, {"baz": null}]});
+        let patch = JsonDiff::to_merge_patch(&original, &target).unwrap();
+        assert_eq!(patch, json!({"bar": [null, {"baz": null}]}));
+        assert_eq!(apply_merge_patch(&original, &patch), target);
+
+        // A changed, added or nested key set to `null` cannot be patched.
+        let original = json!({"foo": 1, "bar": {"baz": 1}});
+        for target in [
+            json!({"foo": null, "bar": {"baz": 1}}),
+            json!({"foo": 1, "bar": {"baz": 1}, "qux": null}),
+            json!({"foo": 1, "bar": {"baz": null}}),
+            json!({"foo": {"qux": null}, "bar": {"baz": 1}}),
+        ] {
+            assert_eq!(JsonDiff::to_merge_patch(&original, &target), None);
+            let json_diff = JsonDiff::diff(&original, &target, false);
+            assert_eq!(json_diff.merge_patch(&original), None);
+        }
+        assert_eq!(
+            JsonDiff::to_merge_patch(&json!(1), &json!({"foo": null})),
+            None
+        );
+    }
+
+    #[test]
+    fn test_merge_patch() {
+        let original = json!({
+            "title": "Goodbye!",
+            "author": {"givenName": "John", "familyName": "Doe"},
+            "tags": ["example", "sample", "draft", "old"],
+            "content": "This will be unchanged",
+            "metadata": {"version": 1, "draft": true},
+            "history": [{"at": 1, "by": "foo"}, {"at": 2, "by": "bar"}]
+        });
+        let target = json!({
+            "title": "Hello!",
+            "writer": {"givenName": "John", "familyName": "Doe"},
+            "tags": ["old", "example", "sample", "new"],
+            "content": "This will be unchanged",
+            "metadata": {"version": 2},
+            "history": [{"at": 1, "by": "foo"}, {"at": 3, "by": "bar"}],
+            "phoneNumber": "+01-123-456-7890"
+        });
+        let options = [
+            DiffOptions::default(),
+            DiffOptions {
+                detect_moves: true,
+                detect_renames: true,
+                include_indices: true,
+                ..DiffOptions::default()
+            },
+            DiffOptions {
+                max_depth: Some(1),
+                text_diff: Some(100),
+                ..DiffOptions::default()
+            },
+        ];
+
+        // The merge patch built from the difference is the same as the one
+        // built from the JSON files.
+        let expected = JsonDiff::to_merge_patch(&original, &target);
+        for options in &options {
+            let json_diff = JsonDiff::diff_with_options(&original, &target, options);
+            let patch = json_diff.merge_patch(&original);
+            assert_eq!(patch, expected);
+            assert_eq!(apply_merge_patch(&original, &patch.unwrap()), target);
+        }
+        assert_eq!(
+            JsonDiff::diff(&original, &original, false).merge_patch(&original),
+            Some(json!({}))
+        );
+
+        // The difference must apply to the original JSON file.
+        let json_diff = JsonDiff::diff(&original, &target, false);
+        assert_eq!(json_diff.merge_patch(&json!({"title": "Goodbye!"})), None);
+        assert_eq!(json_diff.merge_patch(&json!([1])), None);
+    }
+}
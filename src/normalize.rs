@@ -0,0 +1,317 @@
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::fmt;
+
+use serde_json::{Map, Value};
+
+use crate::diff::JsonDiff;
+use crate::error::DiffError;
+use crate::options::DiffOptions;
+
+/// Rewrites a JSON file into a canonical form before it is compared, so
+/// that semantically equal JSON files have no difference.
+///
+/// It is implemented for every `Fn(&mut Value)` closure.
+pub trait Normalizer: Send + Sync {
+    /// Rewrites `value` in place.
+    fn normalize(&self, value: &mut Value);
+}
+
+impl<F: Fn(&mut Value) + Send + Sync> Normalizer for F {
+    fn normalize(&self, value: &mut Value) {
+        self(value);
+    }
+}
+
+impl fmt::Debug for dyn Normalizer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Normalizer")
+    }
+}
+
+/// Sorts the arrays which only contain scalars, at any depth.
+///
+/// Scalars are sorted by type, i.e. `null`, booleans, numbers and strings,
+/// then by value.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SortScalarArrays;
+
+fn type_rank(value: &Value) -> u8 {
+    match value {
+        Value::Null => 0,
+        Value::Bool(_) => 1,
+        Value::Number(_) => 2,
+        Value::String(_) => 3,
+        Value::Array(_) => 4,
+        Value::Object(_) => 5,
+    }
+}
+
+fn compare_scalars(value1: &Value, value2: &Value) -> Ordering {
+    match (value1, value2) {
+        (Value::Bool(bool1), Value::Bool(bool2)) => bool1.cmp(bool2),
+        (Value::Number(number1), Value::Number(number2)) => number1
+            .as_f64()
+            .partial_cmp(&number2.as_f64())
+            .unwrap_or(Ordering::Equal),
+        (Value::String(string1), Value::String(string2)) => string1.cmp(string2),
+        _ => type_rank(value1).cmp(&type_rank(value2)),
+    }
+}
+
+impl Normalizer for SortScalarArrays {
+    fn normalize(&self, value: &mut Value) {
+        match value {
+            Value::Array(array) => {
+                if array
+                    .iter()
+                    .all(|item| !(item.is_array() || item.is_object()))
+                {
+                    array.sort_by(compare_scalars);
+                } else {
+                    for item in array {
+                        self.normalize(item);
+                    }
+                }
+            }
+            Value::Object(obj) => {
+                for item in obj.values_mut() {
+                    self.normalize(item);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Drops the keys whose value is equal to their default value.
+///
+/// The defaults follow the structure of the JSON file: a default object
+/// provides the defaults of the object associated to the same key.
+#[derive(Clone, Debug, Default)]
+pub struct DropDefaults(pub Map<String, Value>);
+
+fn drop_defaults(obj: &mut Map<String, Value>, defaults: &Map<String, Value>) {
+    for (key, default) in defaults {
+        if obj.get(key) == Some(default) {
+            obj.remove(key);
+        } else if let (Some(Value::Object(subobj)), Value::Object(subdefaults)) =
+            (obj.get_mut(key), default)
+        {
+            drop_defaults(subobj, subdefaults);
+        }
+    }
+}
+
+impl Normalizer for DropDefaults {
+    fn normalize(&self, value: &mut Value) {
+        if let Value::Object(obj) = value {
+            drop_defaults(obj, &self.0);
+        }
+    }
+}
+
+/// Adds the keys missing from the JSON file with their default value.
+///
+/// The defaults follow the structure of the JSON file: a default object
+/// provides the defaults of the object associated to the same key, while
+/// arrays and scalars are never merged, the values of the JSON file
+/// winning over the defaults.
+#[derive(Clone, Debug, Default)]
+pub struct FillDefaults(pub Map<String, Value>);
+
+fn fill_defaults(obj: &mut Map<String, Value>, defaults: &Map<String, Value>) {
+    for (key, default) in defaults {
+        match (obj.get_mut(key), default) {
+            (Some(Value::Object(subobj)), Value::Object(subdefaults)) => {
+                fill_defaults(subobj, subdefaults);
+            }
+            (Some(_), _) => {}
+            (None, _) => {
+                obj.insert(key.clone(), default.clone());
+            }
+        }
+    }
+}
+
+impl Normalizer for FillDefaults {
+    fn normalize(&self, value: &mut Value) {
+        if let Value::Object(obj) = value {
+            fill_defaults(obj, &self.0);
+        }
+    }
+}
+
+impl JsonDiff {
+    /// Finds the JSON structural difference of two JSON files using the
+    /// given options, after merging the defaults under each of them, as
+    /// [`FillDefaults`] does, before the other normalizers.
+    ///
+    /// A key omitted by one JSON file is then only reported if the other
+    /// one overrides its default value. If `defaults` is not an object,
+    /// nothing is merged.
+    ///
+    /// If a JSON file is nested deeper than [`DiffOptions::max_nesting`],
+    /// the first one is replaced as a whole by the second one, as
+    /// [`JsonDiff::diff_with_options`] does, without merging the defaults.
+    /// Use [`JsonDiff::try_diff_with_defaults`] to get an error instead.
+    #[must_use]
+    pub fn diff_with_defaults(
+        json1: &Value,
+        json2: &Value,
+        defaults: &Value,
+        options: &DiffOptions,
+    ) -> Self {
+        Self::try_diff_with_defaults(json1, json2, defaults, options)
+            .unwrap_or_else(|_| Self::replaced(json1, json2, options))
+    }
+
+    /// Finds the JSON structural difference of two JSON files using the
+    /// given options, after merging the defaults under each of them, as
+    /// [`JsonDiff::diff_with_defaults`] does.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DiffError::TooDeep`] if a JSON file is nested deeper than
+    /// [`DiffOptions::max_nesting`].
+    pub fn try_diff_with_defaults(
+        json1: &Value,
+        json2: &Value,
+        defaults: &Value,
+        options: &DiffOptions,
+    ) -> Result<Self, DiffError> {
+        let Value::Object(defaults) = defaults else {
+            return Self::try_diff_with_options(json1, json2, options);
+        };
+        let mut normalizers: Vec<Arc<dyn Normalizer>> =
+            vec![Arc::new(FillDefaults(defaults.clone()))];
+        normalizers.extend(options.normalizers.iter().cloned());
+        let options = DiffOptions {
+            normalizers,
+            ..options.clone()
+        };
+        Self::try_diff_with_options(json1, json2, &options)
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+
+    use std::sync::Arc;
+
+    use serde_json::Value;
+
+    use super::{DropDefaults, FillDefaults, Normalizer, SortScalarArrays};
+    use crate::diff::JsonDiff;
+    use crate::options::DiffOptions;
+
+    #[test]
+    fn test_normalizers() {
+        let options = DiffOptions {
+            normalizers: vec![Arc::new(SortScalarArrays)],
+            ..DiffOptions::default()
+        };
+        let json1 = json!({"foo": [3, 1, 2], "bar": [{"baz": ["b", null, "a", true]}]});
+        let json2 = json!({"foo": [1, 2, 3], "bar": [{"baz": [null, true, "a", "b"]}]});
+        assert_eq!(
+            JsonDiff::diff_with_options(&json1, &json2, &options).diff,
+            None
+        );
+        // The inputs are left untouched.
+        assert_eq!(json1["foo"], json!([3, 1, 2]));
+        assert!(JsonDiff::diff(&json1, &json2, false).diff.is_some());
+
+        let defaults = json!({"retries": 3, "server": {"port": 80}});
+        let options = DiffOptions {
+            normalizers: vec![Arc::new(DropDefaults(
+                defaults.as_object().unwrap().clone(),
+            ))],
+            ..DiffOptions::default()
+        };
+        assert_eq!(
+            JsonDiff::diff_with_options(
+                &json!({"retries": 3, "server": {"host": "a", "port": 80}}),
+                &json!({"server": {"host": "b"}}),
+                &options
+            )
+            .diff,
+            Some(json!({"server": {"host": {"__old": "a", "__new": "b"}}}))
+        );
+
+        // Closures are normalizers too.
+        let options = DiffOptions {
+            normalizers: vec![Arc::new(|value: &mut Value| {
+                if let Some(obj) = value.as_object_mut() {
+                    obj.remove("timestamp");
+                }
+            })],
+            ..DiffOptions::default()
+        };
+        assert_eq!(
+            JsonDiff::diff_with_options(
+                &json!({"foo": 1, "timestamp": 1}),
+                &json!({"foo": 1, "timestamp": 2}),
+                &options
+            )
+            .diff,
+            None
+        );
+    }
+
+    #[test]
+    fn test_diff_with_defaults() {
+        let defaults = json!({
+            "retries": 3,
+            "server": {"host": "localhost", "port": 80},
+            "tags": ["a", "b"]
+        });
+        let json1 = json!({"server": {"port": 80}});
+        let json2 = json!({"retries": 3, "server": {"host": "localhost"}, "tags": ["a", "b"]});
+
+        // The keys omitted by one side are not reported as added or deleted.
+        assert_eq!(
+            JsonDiff::diff_with_defaults(&json1, &json2, &defaults, &DiffOptions::default()).diff,
+            None
+        );
+        assert!(JsonDiff::diff(&json1, &json2, false).diff.is_some());
+
+        // Only the genuine overrides are reported, and arrays are not merged.
+        assert_eq!(
+            JsonDiff::diff_with_defaults(
+                &json1,
+                &json!({"server": {"port": 8080}, "tags": ["c"]}),
+                &defaults,
+                &DiffOptions::default()
+            )
+            .diff,
+            Some(json!({
+                "server": {"port": {"__old": 80, "__new": 8080}},
+                "tags": [["-", "a"], ["-", "b"], ["+", "c"]]
+            }))
+        );
+
+        // A value which is not an object is not merged with a default object.
+        assert_eq!(
+            JsonDiff::diff_with_defaults(
+                &json!({"server": null}),
+                &json!({}),
+                &defaults,
+                &DiffOptions::default()
+            )
+            .diff,
+            Some(json!({
+                "server": {"__old": null, "__new": {"host": "localhost", "port": 80}}
+            }))
+        );
+
+        let mut value = json!({"server": {"port": 1}});
+        FillDefaults(defaults.as_object().unwrap().clone()).normalize(&mut value);
+        assert_eq!(
+            value,
+            json!({"retries": 3, "server": {"host": "localhost", "port": 1}, "tags": ["a", "b"]})
+        );
+    }
+}
@@ -0,0 +1,482 @@
+use alloc::borrow::Cow;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use serde_json::{Number, Value};
+
+use crate::compare::ScalarComparator;
+use crate::error::DiffError;
+use crate::filter::DiffFilter;
+use crate::markers::DiffMarkers;
+use crate::normalize::Normalizer;
+
+/// Options to customize how the JSON structural difference is computed.
+#[derive(Clone, Debug)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct DiffOptions {
+    /// Compare only the keys, ignore the differences in values.
+    pub keys_only: bool,
+    /// Compare only the keys and the types of the values, ignore the other
+    /// differences in values, e.g. `42` is equal to `10`, but not to
+    /// `"42"`.
+    ///
+    /// An object or an array replaced by a value of another type is
+    /// reported too. It has no effect with [`DiffOptions::keys_only`].
+    pub types_only: bool,
+    /// Compare only the values of the keys present in both objects, ignore
+    /// the added and deleted keys, e.g. `{"a": 1, "b": 2}` is equal to
+    /// `{"a": 1, "c": 3}`.
+    ///
+    /// The shared keys are still compared recursively, and the items of
+    /// arrays are still added and deleted.
+    pub values_only: bool,
+    /// Tolerance used to compare two numbers.
+    ///
+    /// If `Some(epsilon)`: two numbers `a` and `b` are considered equal when
+    /// `(a - b).abs() <= epsilon`, regardless of their integer or float
+    /// representation. Two integers are subtracted exactly, even beyond the
+    /// integers which `f64` can represent.
+    /// If `None`: two numbers are compared exactly.
+    pub epsilon: Option<f64>,
+    /// Compare numbers by their mathematical value, regardless of their
+    /// integer or float representation, e.g. `1` is equal to `1.0`.
+    ///
+    /// The comparison is exact, even beyond `2^53` where not every integer
+    /// has a float representation: `9007199254740993` is not equal to
+    /// `9007199254740992.0`.
+    ///
+    /// Defaults to `false`, i.e. numbers are equal only if they have the
+    /// same representation, so `1` is not equal to `1.0`.
+    pub numbers_by_value: bool,
+    /// Compare a string with a number by the number it contains, e.g. `"1"`
+    /// is equal to `1`, in both directions.
+    ///
+    /// The string has to be a JSON number without any surrounding space, so
+    /// `"01"`, `"+1"` and `" 1"` are still different from `1`. The numbers
+    /// are compared by their mathematical value, so `"1.0"` is equal to `1`,
+    /// or within [`DiffOptions::epsilon`], if any.
+    pub coerce_numeric_strings: bool,
+    /// Keys to skip while comparing objects.
+    ///
+    /// An entry starting with `/` is a JSON Pointer and only ignores the
+    /// value at that exact path, e.g. `/metadata/timestamp`.
+    /// Any other entry is a key name and is ignored at any depth.
+    pub ignore_keys: Vec<String>,
+    /// Show the unchanged keys of the objects which have changed, under
+    /// [`DiffMarkers::unchanged`], e.g. `{"id__unchanged": 1, "name":
+    /// {"__old": "foo", "__new": "bar"}}`, so that the changes can be
+    /// located by their sibling keys. The ignored keys are not shown.
+    pub show_context_keys: bool,
+    /// Report the array items deleted at one index and added with the same
+    /// value at another index as moves, i.e. `['>', old_index, new_index]`.
+    ///
+    /// The largest set of items in the same order in both arrays is kept in
+    /// place, e.g. only `1` is moved from `[1, 2, 3]` to `[2, 3, 1]`.
+    pub detect_moves: bool,
+    /// Report the keys deleted with a value and added with an equal or
+    /// similar value under another name as renames, i.e.
+    /// `{"old->new": change}`, where `change` is the difference of the
+    /// values, or `null` if they are equal.
+    ///
+    /// Each deleted key is paired with the first added key having an equal
+    /// value, otherwise with the added key whose object or array is the
+    /// most similar, if its score is greater than
+    /// [`DiffOptions::array_match_threshold`]. The keys which would need to
+    /// be escaped are never paired. The score is the same as without
+    /// renames.
+    pub detect_renames: bool,
+    /// Compare strings ignoring the case of their letters, e.g. `"OK"` is
+    /// equal to `"ok"`.
+    pub case_insensitive: bool,
+    /// Match the keys of objects ignoring the case of their letters, e.g.
+    /// the key `"Name"` is matched with the key `"name"`.
+    ///
+    /// This applies independently of [`DiffOptions::case_insensitive`].
+    pub case_insensitive_keys: bool,
+    /// Consider a key set to `null` equal to a missing key, e.g. `{"a": null}`
+    /// is equal to `{}`.
+    pub null_equals_missing: bool,
+    /// Consider a key set to an empty array or object equal to a missing
+    /// key, e.g. `{"tags": []}` is equal to `{}`.
+    pub empty_equals_missing: bool,
+    /// Compare the strings containing an embedded JSON object or array,
+    /// e.g. `"{\"a\": 1}"`, by the structural difference of their JSON
+    /// instead of their text.
+    ///
+    /// The difference is reported under [`DiffMarkers::embedded`], e.g.
+    /// `{"__embedded": {"a": {"__old": 1, "__new": 2}}}`, and strings whose
+    /// JSON is equal are equal. If either string does not contain a JSON
+    /// object or array, the strings are compared as usual.
+    ///
+    /// [`DiffMarkers::embedded`]: field@DiffMarkers::embedded
+    pub parse_embedded_json: bool,
+    /// Maximum length, in characters, of the strings compared character by
+    /// character.
+    ///
+    /// If `Some(len)`: the change of a string into another string, both of
+    /// at most `len` characters, is reported under [`DiffMarkers::text`] as
+    /// the runs of characters kept, deleted and added, e.g.
+    /// `{"__text": [[" ", "hello "], ["+", "brave "], [" ", "world"]]}`.
+    /// This shows changes which are hard to spot, such as a trailing space.
+    /// Longer strings are reported as a whole, as any other value.
+    /// If `None`: every string is reported as a whole.
+    ///
+    /// [`DiffMarkers::text`]: field@DiffMarkers::text
+    pub text_diff: Option<usize>,
+    /// Maximum depth at which the JSON files are compared structurally.
+    ///
+    /// If `Some(depth)`: the values at `depth` are not compared
+    /// structurally, and a difference between them is reported as a whole,
+    /// i.e. `{"__old": ..., "__new": ...}`. The roots are at depth `0`, so
+    /// `Some(0)` treats them as opaque values.
+    /// If `None`: the JSON files are compared at any depth.
+    pub max_depth: Option<usize>,
+    /// Maximum number of objects and arrays nested into each other in the
+    /// JSON files, beyond which they are not compared, since the
+    /// comparison is recursive and would overflow the stack.
+    ///
+    /// If `Some(nesting)`: the JSON files nested deeper than `nesting` are
+    /// not compared. The fallible functions, e.g.
+    /// [`JsonDiff::try_diff_with_options`], return [`DiffError::TooDeep`],
+    /// while the other ones report the first JSON file as replaced as a
+    /// whole by the second one, i.e. `{"__old": ..., "__new": ...}`, unless
+    /// they are equal.
+    /// If `None`: the JSON files are compared at any depth, which may
+    /// overflow the stack.
+    ///
+    /// Defaults to `Some(128)`, which accepts every JSON file parsed by
+    /// `serde_json::from_str`, since its parser rejects deeper ones.
+    ///
+    /// [`DiffError::TooDeep`]: crate::DiffError::TooDeep
+    /// [`JsonDiff::try_diff_with_options`]: crate::JsonDiff::try_diff_with_options
+    pub max_nesting: Option<usize>,
+    /// Maximum number of changes reported, to bound the size of the JSON
+    /// structural difference of two wildly different JSON files.
+    ///
+    /// If `Some(count)`: the comparison stops once `count` changes have
+    /// been found, and [`JsonDiff::truncated`] is set if any change has been
+    /// left out. Every deleted or added key or item, and every changed
+    /// value, counts as one change. The remaining values are then assumed
+    /// to be equal, so they are only reported as truncated if they are not
+    /// strictly equal. Objects are not compared in parallel.
+    /// If `None`: every change is reported.
+    ///
+    /// [`JsonDiff::truncated`]: crate::JsonDiff::truncated
+    pub max_changes: Option<usize>,
+    /// Sort the keys of every object of the JSON structural difference,
+    /// including the deleted and added values, so that it is the same
+    /// whether the `preserve_order` feature of `serde_json` is enabled or
+    /// not.
+    pub sort_keys: bool,
+    /// The kinds of changes kept in the JSON structural difference, e.g.
+    /// only the added keys and array items with [`DiffFilter::AddedOnly`].
+    /// A difference without any change left is `None`.
+    ///
+    /// The changes left out do not count towards
+    /// [`DiffOptions::max_changes`], while the score and the similarity are
+    /// still those of the whole difference.
+    ///
+    /// Defaults to [`DiffFilter::All`].
+    pub filter: DiffFilter,
+    /// Append the indices of the array items to their changes, i.e.
+    /// `['~', change, old_index, new_index]`, `['-', value, old_index]`
+    /// and `['+', value, new_index]`.
+    pub include_indices: bool,
+    /// Append the score of the match of two array items to their changes,
+    /// i.e. `['~', change, {"__score": score}]`, after their indices if
+    /// any, to show why they were paired.
+    ///
+    /// The score is the one compared with
+    /// [`DiffOptions::array_match_threshold`], so the items paired by their
+    /// position, which are not matched by their score, have no score.
+    pub include_match_scores: bool,
+    /// Compare arrays as multisets, ignoring the order of their items.
+    ///
+    /// Every item of the first array is paired with the first equal item of
+    /// the second one. The remaining objects and arrays are then paired
+    /// with the most similar remaining item of the same type, ties being
+    /// broken in favour of the lowest index in the second array. Unpaired
+    /// items are reported as deleted or added, the added ones last.
+    pub array_unordered: bool,
+    /// JSON Pointers of the arrays compared as multisets, as with
+    /// [`DiffOptions::array_unordered`], e.g. `/meta/tags`. The other arrays
+    /// keep the order of their items.
+    pub unordered_paths: Vec<String>,
+    /// Fields identifying the objects of arrays, e.g. `["region", "name"]`:
+    /// two objects having equal values for all of them are matched, even if
+    /// they differ otherwise, and two objects whose values differ are never
+    /// matched.
+    ///
+    /// An entry starting with `/` is a JSON Pointer relative to the object,
+    /// e.g. `/meta/id`. Any other entry is a key of the object. The objects
+    /// missing any of the fields are matched with their most similar
+    /// counterpart, as usual.
+    pub array_keys: Vec<String>,
+    /// Maximum length of the arrays whose items are matched with their most
+    /// similar counterpart, which takes a time quadratic in their length.
+    ///
+    /// If `Some(len)`: when an array is longer than `len`, the items of the
+    /// two arrays are compared by index instead, and the items beyond the
+    /// length of the shortest array are reported as deleted or added.
+    /// If `None`: arrays of any length are matched.
+    pub max_fuzzy_array_len: Option<usize>,
+    /// Match the arrays nested in arrays with their most similar
+    /// counterpart, as the objects are, so that a changed nested array is
+    /// reported as `['~', change]` instead of deleted and added.
+    ///
+    /// The score of two arrays is the percentage of their similarity, e.g.
+    /// `50.` if half of their items are equal, and it is compared with
+    /// [`DiffOptions::array_match_threshold`].
+    ///
+    /// Defaults to `false`, i.e. nested arrays are only matched with equal
+    /// arrays.
+    pub fuzzy_match_arrays: bool,
+    /// Match the objects of ordered arrays only with equal objects, instead
+    /// of their most similar counterpart, so that a changed object is
+    /// reported as deleted and added instead of `['~', change]`.
+    ///
+    /// The objects identified by [`DiffOptions::array_keys`] are still
+    /// matched by their identity, and unordered arrays are still matched
+    /// fuzzily.
+    pub strict_arrays: bool,
+    /// Minimum score for an object of the second array to be matched with
+    /// an object of the first array, and reported as changed instead of
+    /// deleted and added. The same goes for the nested arrays with
+    /// [`DiffOptions::fuzzy_match_arrays`], whose score is a percentage.
+    ///
+    /// Defaults to `40.`.
+    pub array_match_threshold: f64,
+    /// Score added for every key present in both objects.
+    ///
+    /// Defaults to `20.`.
+    pub key_match_bonus: f64,
+    /// Score subtracted for every key present in only one of the objects.
+    ///
+    /// Defaults to `30.`.
+    pub key_missing_penalty: f64,
+    /// Divisor applied to the score of the value of a shared key before it
+    /// is added to the score of its object.
+    ///
+    /// Defaults to `5.`.
+    pub value_score_divisor: f64,
+    /// Lower bound of the divided score of the value of a shared key.
+    ///
+    /// Must not be greater than [`DiffOptions::value_score_max`].
+    ///
+    /// Defaults to `-10.`.
+    pub value_score_min: f64,
+    /// Upper bound of the divided score of the value of a shared key.
+    ///
+    /// Defaults to `20.`.
+    pub value_score_max: f64,
+    /// Normalizers applied, in order, to copies of both JSON files before
+    /// they are compared.
+    pub normalizers: Vec<Arc<dyn Normalizer>>,
+    /// Comparators consulted, in order, to decide whether two scalars are
+    /// equal, before the default comparison.
+    pub scalar_comparators: Vec<Arc<dyn ScalarComparator>>,
+    /// Keys and suffixes which mark the changes of the JSON structural
+    /// difference.
+    pub markers: DiffMarkers,
+    /// Compare the keys of large objects, and the candidates of the fuzzy
+    /// matching of large arrays, in parallel.
+    ///
+    /// The JSON structural difference is the same as the serial one. It has
+    /// no effect without the `parallel` feature.
+    ///
+    /// Defaults to `true`.
+    pub parallel: bool,
+}
+
+impl Default for DiffOptions {
+    fn default() -> Self {
+        Self {
+            keys_only: false,
+            values_only: false,
+            types_only: false,
+            epsilon: None,
+            numbers_by_value: false,
+            coerce_numeric_strings: false,
+            ignore_keys: Vec::new(),
+            show_context_keys: false,
+            detect_moves: false,
+            detect_renames: false,
+            case_insensitive: false,
+            case_insensitive_keys: false,
+            null_equals_missing: false,
+            empty_equals_missing: false,
+            parse_embedded_json: false,
+            text_diff: None,
+            max_depth: None,
+            max_nesting: Some(128),
+            max_changes: None,
+            sort_keys: false,
+            filter: DiffFilter::All,
+            include_indices: false,
+            include_match_scores: false,
+            array_unordered: false,
+            unordered_paths: Vec::new(),
+            array_keys: Vec::new(),
+            max_fuzzy_array_len: None,
+            fuzzy_match_arrays: false,
+            strict_arrays: false,
+            array_match_threshold: 40.,
+            key_match_bonus: 20.,
+            key_missing_penalty: 30.,
+            value_score_divisor: 5.,
+            value_score_min: -10.,
+            value_score_max: 20.,
+            normalizers: Vec::new(),
+            scalar_comparators: Vec::new(),
+            markers: DiffMarkers::default(),
+            parallel: true,
+        }
+    }
+}
+
+impl DiffOptions {
+    /// Creates the default options, optionally comparing only the keys.
+    #[must_use]
+    pub fn new(keys_only: bool) -> Self {
+        Self {
+            keys_only,
+            ..Self::default()
+        }
+    }
+
+    /// Returns `true` if some differences in values are ignored, i.e.
+    /// comparing only the keys or the types of the values.
+    pub(crate) fn ignores_values(&self) -> bool {
+        self.keys_only || self.types_only
+    }
+
+    pub(crate) fn normalize<'a>(&self, value: &'a Value) -> Cow<'a, Value> {
+        if self.normalizers.is_empty() {
+            return Cow::Borrowed(value);
+        }
+        // The normalizers rewrite a deep copy, never the caller's value.
+        let mut value = value.clone();
+        for normalizer in &self.normalizers {
+            normalizer.normalize(&mut value);
+        }
+        Cow::Owned(value)
+    }
+
+    /// Returns the JSON embedded in both strings, if it is to be compared.
+    pub(crate) fn embedded_json(&self, json1: &Value, json2: &Value) -> Option<(Value, Value)> {
+        if !self.parse_embedded_json {
+            return None;
+        }
+        let parse = |json: &Value| {
+            serde_json::from_str::<Value>(json.as_str()?)
+                .ok()
+                .filter(|value| value.is_object() || value.is_array())
+        };
+        Some((parse(json1)?, parse(json2)?))
+    }
+
+    /// Returns the numbers of a string and a number, in their order, if the
+    /// string is to be compared as the number it contains.
+    pub(crate) fn numeric_strings(&self, json1: &Value, json2: &Value) -> Option<(Value, Value)> {
+        if !self.coerce_numeric_strings {
+            return None;
+        }
+        // The parser skips the space around a number, which is rejected here.
+        let parse = |string: &str| {
+            (string.trim() == string)
+                .then(|| serde_json::from_str::<Number>(string).ok())
+                .flatten()
+                .map(Value::Number)
+        };
+        match (json1, json2) {
+            (Value::String(string), Value::Number(_)) => Some((parse(string)?, json2.clone())),
+            (Value::Number(_), Value::String(string)) => Some((json1.clone(), parse(string)?)),
+            _ => None,
+        }
+    }
+
+    /// Returns the values of [`DiffOptions::array_keys`] of an object, if
+    /// it has all of them.
+    pub(crate) fn array_identity<'a>(&self, item: &'a Value) -> Option<Vec<&'a Value>> {
+        if self.array_keys.is_empty() || !item.is_object() {
+            return None;
+        }
+        self.array_keys
+            .iter()
+            .map(|key| {
+                if key.starts_with('/') {
+                    item.pointer(key)
+                } else {
+                    item.get(key)
+                }
+            })
+            .collect()
+    }
+
+    /// Returns both strings, if their change is to be reported character by
+    /// character.
+    pub(crate) fn text_diff<'a>(
+        &self,
+        json1: &'a Value,
+        json2: &'a Value,
+    ) -> Option<(&'a str, &'a str)> {
+        let max_len = self.text_diff?;
+        let (Value::String(string1), Value::String(string2)) = (json1, json2) else {
+            return None;
+        };
+        let is_short = |string: &str| string.chars().count() <= max_len;
+        (is_short(string1) && is_short(string2)).then_some((string1, string2))
+    }
+
+    /// Returns an error if `json` is nested deeper than
+    /// [`DiffOptions::max_nesting`], without any recursion.
+    pub(crate) fn check_nesting(&self, json: &Value) -> Result<(), DiffError> {
+        let Some(max_nesting) = self.max_nesting else {
+            return Ok(());
+        };
+        let mut stack = vec![(json, 0)];
+        while let Some((value, nesting)) = stack.pop() {
+            let children: &mut dyn Iterator<Item = &Value> = match value {
+                Value::Object(obj) => &mut obj.values(),
+                Value::Array(array) => &mut array.iter(),
+                _ => continue,
+            };
+            if nesting >= max_nesting {
+                return Err(DiffError::TooDeep(max_nesting));
+            }
+            stack.extend(children.map(|child| (child, nesting + 1)));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn is_beyond_max_depth(&self, path: &str) -> bool {
+        // Every segment of a JSON Pointer increases the depth by one.
+        self.max_depth
+            .is_some_and(|max_depth| path.matches('/').count() >= max_depth)
+    }
+
+    pub(crate) fn is_beyond_fuzzy_array_len(&self, array1: &[Value], array2: &[Value]) -> bool {
+        self.max_fuzzy_array_len
+            .is_some_and(|max_len| array1.len().max(array2.len()) > max_len)
+    }
+
+    pub(crate) fn is_unordered(&self, path: &str) -> bool {
+        self.array_unordered
+            || self
+                .unordered_paths
+                .iter()
+                .any(|unordered| unordered == path)
+    }
+
+    pub(crate) fn is_ignored(&self, key: &str, path: &str) -> bool {
+        self.ignore_keys.iter().any(|ignored| {
+            if ignored.starts_with('/') {
+                ignored == path
+            } else {
+                ignored == key
+            }
+        })
+    }
+}
@@ -0,0 +1,223 @@
+use alloc::vec::Vec;
+
+#[cfg(feature = "parallel")]
+use serde_json::Value;
+
+#[cfg(feature = "parallel")]
+use crate::diff::JsonDiff;
+#[cfg(feature = "parallel")]
+use crate::error::DiffError;
+#[cfg(feature = "parallel")]
+use crate::options::DiffOptions;
+
+/// Minimum number of items worth mapping in parallel.
+#[cfg(feature = "parallel")]
+const PARALLEL_MIN_LEN: usize = 64;
+
+/// Maps every item with `f`, preserving their order, in parallel when
+/// `parallel` is set and there are enough items.
+///
+/// Every item is always mapped, so that the results, including the first
+/// error, do not depend on the scheduling.
+#[cfg(feature = "parallel")]
+pub(crate) fn map_in_order<T, R, F>(items: &[T], parallel: bool, f: F) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> R + Sync + Send,
+{
+    use rayon::prelude::*;
+
+    if parallel && items.len() >= PARALLEL_MIN_LEN {
+        items.par_iter().map(f).collect()
+    } else {
+        items.iter().map(f).collect()
+    }
+}
+
+/// Maps every item with `f`, preserving their order.
+#[cfg(not(feature = "parallel"))]
+pub(crate) fn map_in_order<T, R, F>(items: &[T], _parallel: bool, f: F) -> Vec<R>
+where
+    F: Fn(&T) -> R,
+{
+    items.iter().map(f).collect()
+}
+
+#[cfg(feature = "parallel")]
+impl JsonDiff {
+    /// Finds the JSON structural differences of many pairs of JSON files
+    /// using the given options, spreading the pairs across the threads of
+    /// the global `rayon` pool.
+    ///
+    /// The differences follow the order of the pairs.
+    ///
+    /// The JSON files nested deeper than [`DiffOptions::max_nesting`] are
+    /// compared as [`JsonDiff::diff_with_options`] does.
+    /// Use [`JsonDiff::try_diff_batch`] to get an error instead.
+    #[must_use]
+    pub fn diff_batch(pairs: &[(Value, Value)], options: &DiffOptions) -> Vec<Self> {
+        Self::diff_batch_with_progress(pairs, options, &|_, _| {})
+    }
+
+    /// Finds the JSON structural differences of many pairs of JSON files,
+    /// as [`JsonDiff::diff_batch`] does, calling `progress` with the number
+    /// of pairs already compared and the total number of pairs after each
+    /// pair is compared.
+    ///
+    /// `progress` is called from the threads of the pool, so the pairs may
+    /// be reported out of order, but the numbers of compared pairs are
+    /// distinct and the last call reports all of them.
+    #[must_use]
+    pub fn diff_batch_with_progress(
+        pairs: &[(Value, Value)],
+        options: &DiffOptions,
+        progress: &(dyn Fn(usize, usize) + Send + Sync),
+    ) -> Vec<Self> {
+        Self::map_batch(pairs, progress, |json1, json2| {
+            Self::diff_with_options(json1, json2, options)
+        })
+    }
+
+    /// Finds the JSON structural differences of many pairs of JSON files,
+    /// as [`JsonDiff::diff_batch`] does.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DiffError::TooDeep`] if a JSON file is nested deeper than
+    /// [`DiffOptions::max_nesting`]. The other pairs are still compared, so
+    /// that the error does not depend on the scheduling.
+    pub fn try_diff_batch(
+        pairs: &[(Value, Value)],
+        options: &DiffOptions,
+    ) -> Result<Vec<Self>, DiffError> {
+        Self::try_diff_batch_with_progress(pairs, options, &|_, _| {})
+    }
+
+    /// Finds the JSON structural differences of many pairs of JSON files,
+    /// calling `progress` as [`JsonDiff::diff_batch_with_progress`] does.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DiffError::TooDeep`] if a JSON file is nested deeper than
+    /// [`DiffOptions::max_nesting`]. The other pairs are still compared, so
+    /// that the error does not depend on the scheduling.
+    pub fn try_diff_batch_with_progress(
+        pairs: &[(Value, Value)],
+        options: &DiffOptions,
+        progress: &(dyn Fn(usize, usize) + Send + Sync),
+    ) -> Result<Vec<Self>, DiffError> {
+        Self::map_batch(pairs, progress, |json1, json2| {
+            Self::try_diff_with_options(json1, json2, options)
+        })
+        .into_iter()
+        .collect()
+    }
+
+    fn map_batch<R, F>(
+        pairs: &[(Value, Value)],
+        progress: &(dyn Fn(usize, usize) + Send + Sync),
+        diff: F,
+    ) -> Vec<R>
+    where
+        R: Send,
+        F: Fn(&Value, &Value) -> R + Sync + Send,
+    {
+        use core::sync::atomic::{AtomicUsize, Ordering};
+        use rayon::prelude::*;
+
+        let compared = AtomicUsize::new(0);
+        pairs
+            .par_iter()
+            .map(|(json1, json2)| {
+                let result = diff(json1, json2);
+                progress(compared.fetch_add(1, Ordering::Relaxed) + 1, pairs.len());
+                result
+            })
+            .collect()
+    }
+}
+
+#[cfg(all(test, feature = "parallel"))]
+mod tests {
+
+    use crate::diff::JsonDiff;
+    use crate::error::DiffError;
+    use crate::options::DiffOptions;
+
+    #[test]
+    fn test_diff_batch() {
+        let pairs: Vec<_> = (0..100)
+            .map(|index| {
+                (
+                    json!({"id": index, "items": [1, 2, index % 7], "name": "foo"}),
+                    json!({
+                        "id": index,
+                        "items": [index % 5, 2, 3],
+                        "name": format!("foo{}", index % 3)
+                    }),
+                )
+            })
+            .collect();
+        let options = DiffOptions {
+            include_indices: true,
+            ..DiffOptions::default()
+        };
+
+        let batch = JsonDiff::diff_batch(&pairs, &options);
+        assert_eq!(batch.len(), pairs.len());
+        for ((json1, json2), json_diff) in pairs.iter().zip(&batch) {
+            let serial = JsonDiff::diff_with_options(json1, json2, &options);
+            assert_eq!(json_diff.diff, serial.diff);
+            assert_eq!(json_diff.score.to_bits(), serial.score.to_bits());
+        }
+        assert!(JsonDiff::diff_batch(&[], &options).is_empty());
+        assert_eq!(JsonDiff::try_diff_batch(&pairs, &options).unwrap(), batch);
+    }
+
+    #[test]
+    fn test_try_diff_batch() {
+        let options = DiffOptions {
+            max_nesting: Some(1),
+            ..DiffOptions::default()
+        };
+        let pairs = [
+            (json!({"id": 1}), json!({"id": 2})),
+            (json!({"id": [1]}), json!({"id": [2]})),
+        ];
+        assert!(matches!(
+            JsonDiff::try_diff_batch(&pairs, &options),
+            Err(DiffError::TooDeep(1))
+        ));
+
+        // The pairs nested too deep are reported as replaced as a whole.
+        let batch = JsonDiff::diff_batch(&pairs, &options);
+        assert_eq!(batch[0].diff, Some(json!({"id": {"__old": 1, "__new": 2}})));
+        assert_eq!(
+            batch[1].diff,
+            Some(json!({"__old": {"id": [1]}, "__new": {"id": [2]}}))
+        );
+    }
+
+    #[test]
+    fn test_diff_batch_with_progress() {
+        use std::sync::Mutex;
+
+        let pairs: Vec<_> = (0..20)
+            .map(|index| (json!({"id": index}), json!({"id": index + 1})))
+            .collect();
+        let reports = Mutex::new(Vec::new());
+
+        let batch = JsonDiff::diff_batch_with_progress(
+            &pairs,
+            &DiffOptions::default(),
+            &|compared, total| reports.lock().unwrap().push((compared, total)),
+        );
+        assert_eq!(batch.len(), pairs.len());
+
+        let mut reports = reports.into_inner().unwrap();
+        reports.sort_unstable();
+        let expected: Vec<_> = (1..=pairs.len()).map(|compared| (compared, 20)).collect();
+        assert_eq!(reports, expected);
+    }
+}
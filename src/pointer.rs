@@ -0,0 +1,178 @@
+use alloc::borrow::Cow;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::error::DiffError;
+
+/// A JSON Pointer, as defined by RFC 6901, e.g. `/spec/containers/0`.
+///
+/// Its reference tokens are stored unescaped, and escaped only when it is
+/// displayed: `~` becomes `~0` and `/` becomes `~1`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct JsonPointer {
+    tokens: Vec<String>,
+}
+
+impl JsonPointer {
+    /// Creates a JSON Pointer to the root of a JSON file.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parses a JSON Pointer, e.g. `/a~1b/0` into the tokens `a/b` and `0`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DiffError::InvalidPointer`] if the JSON Pointer is neither
+    /// empty nor starts with `/`, or if a `~` is not followed by `0` or `1`.
+    pub fn parse(pointer: &str) -> Result<Self, DiffError> {
+        if pointer.is_empty() {
+            return Ok(Self::new());
+        }
+        let Some(tokens) = pointer.strip_prefix('/') else {
+            return Err(DiffError::InvalidPointer(pointer.to_string()));
+        };
+        let tokens = tokens
+            .split('/')
+            .map(|token| {
+                unescape(token).ok_or_else(|| DiffError::InvalidPointer(pointer.to_string()))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { tokens })
+    }
+
+    /// Appends the key of an object.
+    pub fn push_key(&mut self, key: &str) {
+        self.tokens.push(key.to_string());
+    }
+
+    /// Appends the index of an array.
+    pub fn push_index(&mut self, index: usize) {
+        self.tokens.push(index.to_string());
+    }
+
+    /// Removes the last reference token and returns it, or `None` if the
+    /// JSON Pointer points to the root.
+    pub fn pop(&mut self) -> Option<String> {
+        self.tokens.pop()
+    }
+
+    /// Returns the unescaped reference tokens.
+    #[must_use]
+    pub fn tokens(&self) -> &[String] {
+        &self.tokens
+    }
+
+    /// Returns the JSON Pointer to the value of `key` in the object this
+    /// one points to.
+    pub(crate) fn join_key(&self, key: &str) -> Self {
+        let mut pointer = self.clone();
+        pointer.push_key(key);
+        pointer
+    }
+
+    /// Returns the JSON Pointer to the item at `index` in the array this
+    /// one points to.
+    pub(crate) fn join_index(&self, index: usize) -> Self {
+        let mut pointer = self.clone();
+        pointer.push_index(index);
+        pointer
+    }
+
+    /// Returns the JSON Pointer to the object or array containing the value
+    /// this one points to, or `None` if it points to the root.
+    #[must_use]
+    pub fn parent(&self) -> Option<Self> {
+        let mut pointer = self.clone();
+        pointer.pop()?;
+        Some(pointer)
+    }
+
+    /// Returns `true` if the JSON Pointer points to the root.
+    #[must_use]
+    pub fn is_root(&self) -> bool {
+        self.tokens.is_empty()
+    }
+}
+
+impl fmt::Display for JsonPointer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.tokens
+            .iter()
+            .try_for_each(|token| write!(f, "/{}", escape(token)))
+    }
+}
+
+/// Escapes a reference token of a JSON Pointer.
+pub(crate) fn escape(token: &str) -> Cow<'_, str> {
+    if token.contains(['~', '/']) {
+        Cow::Owned(token.replace('~', "~0").replace('/', "~1"))
+    } else {
+        Cow::Borrowed(token)
+    }
+}
+
+/// Unescapes a reference token of a JSON Pointer, or returns `None` if a
+/// `~` is not followed by `0` or `1`.
+fn unescape(token: &str) -> Option<String> {
+    let mut unescaped = String::with_capacity(token.len());
+    let mut chars = token.chars();
+    while let Some(c) = chars.next() {
+        unescaped.push(match c {
+            '~' => match chars.next()? {
+                '0' => '~',
+                '1' => '/',
+                _ => return None,
+            },
+            c => c,
+        });
+    }
+    Some(unescaped)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use alloc::string::ToString;
+
+    use super::JsonPointer;
+    use crate::error::DiffError;
+
+    #[test]
+    fn test_escape() {
+        let mut pointer = JsonPointer::new();
+        assert!(pointer.is_root());
+        assert_eq!(pointer.to_string(), "");
+
+        pointer.push_key("a/b");
+        pointer.push_index(0);
+        pointer.push_key("c~d");
+        pointer.push_key("~1");
+        pointer.push_key("");
+        assert_eq!(pointer.tokens(), ["a/b", "0", "c~d", "~1", ""]);
+        assert_eq!(pointer.to_string(), "/a~1b/0/c~0d/~01/");
+
+        assert_eq!(pointer.pop().as_deref(), Some(""));
+        assert_eq!(pointer.to_string(), "/a~1b/0/c~0d/~01");
+    }
+
+    #[test]
+    fn test_parse() {
+        for pointer in ["", "/", "/foo", "/a~1b/0/c~0d", "/~01/~10", "//x/"] {
+            assert_eq!(JsonPointer::parse(pointer).unwrap().to_string(), pointer);
+        }
+        assert_eq!(
+            JsonPointer::parse("/~01/~10").unwrap().tokens(),
+            ["~1", "/0"]
+        );
+
+        for pointer in ["foo", "/a~2b", "/a~"] {
+            assert!(matches!(
+                JsonPointer::parse(pointer),
+                Err(DiffError::InvalidPointer(invalid)) if invalid == pointer
+            ));
+        }
+    }
+}
@@ -0,0 +1,363 @@
+use alloc::collections::BTreeMap;
+use alloc::vec;
+use alloc::vec::Vec;
+
+// A port of the `SequenceMatcher` of the `difflib` crate, which only
+// depends on `core` and `alloc`. It produces the same opcodes.
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct Match {
+    first_start: usize,
+    second_start: usize,
+    size: usize,
+}
+
+/// Describes how to turn a slice of the first sequence into a slice
+/// of the second one.
+pub(crate) struct Opcode {
+    /// One of `equal`, `delete`, `insert` and `replace`.
+    pub(crate) tag: &'static str,
+    pub(crate) first_start: usize,
+    pub(crate) first_end: usize,
+    pub(crate) second_start: usize,
+    pub(crate) second_end: usize,
+}
+
+pub(crate) struct SequenceMatcher<'a, T: Ord> {
+    first_sequence: &'a [T],
+    second_sequence: &'a [T],
+    second_sequence_elements: BTreeMap<&'a T, Vec<usize>>,
+}
+
+impl<'a, T: Ord> SequenceMatcher<'a, T> {
+    pub(crate) fn new(first_sequence: &'a [T], second_sequence: &'a [T]) -> Self {
+        let mut second_sequence_elements: BTreeMap<&'a T, Vec<usize>> = BTreeMap::new();
+        for (index, item) in second_sequence.iter().enumerate() {
+            second_sequence_elements
+                .entry(item)
+                .or_default()
+                .push(index);
+        }
+        // Same heuristic on long sequences as `difflib`: the popular
+        // elements are not matched.
+        let len = second_sequence.len();
+        if len >= 200 {
+            let test_len = len / 100 + 1;
+            second_sequence_elements.retain(|_, indexes| indexes.len() <= test_len);
+        }
+        Self {
+            first_sequence,
+            second_sequence,
+            second_sequence_elements,
+        }
+    }
+
+    fn find_longest_match(
+        &self,
+        first_start: usize,
+        first_end: usize,
+        second_start: usize,
+        second_end: usize,
+    ) -> Match {
+        let first_sequence = self.first_sequence;
+        let second_sequence = self.second_sequence;
+        let (mut best_i, mut best_j, mut best_size) = (first_start, second_start, 0);
+        let mut j2len: BTreeMap<usize, usize> = BTreeMap::new();
+        for (i, item) in first_sequence
+            .iter()
+            .enumerate()
+            .take(first_end)
+            .skip(first_start)
+        {
+            let mut new_j2len: BTreeMap<usize, usize> = BTreeMap::new();
+            if let Some(indexes) = self.second_sequence_elements.get(item) {
+                for &j in indexes {
+                    if j < second_start {
+                        continue;
+                    }
+                    if j >= second_end {
+                        break;
+                    }
+                    let size = j
+                        .checked_sub(1)
+                        .and_then(|previous| j2len.get(&previous))
+                        .copied()
+                        .unwrap_or(0)
+                        + 1;
+                    new_j2len.insert(j, size);
+                    if size > best_size {
+                        best_i = i + 1 - size;
+                        best_j = j + 1 - size;
+                        best_size = size;
+                    }
+                }
+            }
+            j2len = new_j2len;
+        }
+        for _ in 0..2 {
+            while best_i > first_start
+                && best_j > second_start
+                && first_sequence.get(best_i - 1) == second_sequence.get(best_j - 1)
+            {
+                best_i -= 1;
+                best_j -= 1;
+                best_size += 1;
+            }
+            while best_i + best_size < first_end
+                && best_j + best_size < second_end
+                && first_sequence.get(best_i + best_size) == second_sequence.get(best_j + best_size)
+            {
+                best_size += 1;
+            }
+        }
+        Match {
+            first_start: best_i,
+            second_start: best_j,
+            size: best_size,
+        }
+    }
+
+    fn get_matching_blocks(&self) -> Vec<Match> {
+        let (first_length, second_length) = (self.first_sequence.len(), self.second_sequence.len());
+        let mut matches = Vec::new();
+        let mut queue = vec![(0, first_length, 0, second_length)];
+        while let Some((first_start, first_end, second_start, second_end)) = queue.pop() {
+            let m = self.find_longest_match(first_start, first_end, second_start, second_end);
+            if m.size > 0 {
+                if first_start < m.first_start && second_start < m.second_start {
+                    queue.push((first_start, m.first_start, second_start, m.second_start));
+                }
+                if m.first_start + m.size < first_end && m.second_start + m.size < second_end {
+                    queue.push((
+                        m.first_start + m.size,
+                        first_end,
+                        m.second_start + m.size,
+                        second_end,
+                    ));
+                }
+                matches.push(m);
+            }
+        }
+        matches.sort();
+
+        // Collapse adjacent blocks.
+        let (mut first_start, mut second_start, mut size) = (0, 0, 0);
+        let mut non_adjacent = Vec::new();
+        for m in &matches {
+            if first_start + size == m.first_start && second_start + size == m.second_start {
+                size += m.size;
+            } else {
+                if size != 0 {
+                    non_adjacent.push(Match {
+                        first_start,
+                        second_start,
+                        size,
+                    });
+                }
+                first_start = m.first_start;
+                second_start = m.second_start;
+                size = m.size;
+            }
+        }
+        if size != 0 {
+            non_adjacent.push(Match {
+                first_start,
+                second_start,
+                size,
+            });
+        }
+        non_adjacent.push(Match {
+            first_start: first_length,
+            second_start: second_length,
+            size: 0,
+        });
+        non_adjacent
+    }
+
+    pub(crate) fn get_opcodes(&self) -> Vec<Opcode> {
+        let mut opcodes = Vec::new();
+        let (mut i, mut j) = (0, 0);
+        for m in self.get_matching_blocks() {
+            let tag = if i < m.first_start && j < m.second_start {
+                Some("replace")
+            } else if i < m.first_start {
+                Some("delete")
+            } else if j < m.second_start {
+                Some("insert")
+            } else {
+                None
+            };
+            if let Some(tag) = tag {
+                opcodes.push(Opcode {
+                    tag,
+                    first_start: i,
+                    first_end: m.first_start,
+                    second_start: j,
+                    second_end: m.second_start,
+                });
+            }
+            i = m.first_start + m.size;
+            j = m.second_start + m.size;
+            if m.size != 0 {
+                opcodes.push(Opcode {
+                    tag: "equal",
+                    first_start: m.first_start,
+                    first_end: i,
+                    second_start: m.second_start,
+                    second_end: j,
+                });
+            }
+        }
+        opcodes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::format;
+    use alloc::vec::Vec;
+
+    use super::SequenceMatcher;
+
+    fn chars(sequence: &str) -> Vec<char> {
+        sequence.chars().collect()
+    }
+
+    fn matching_blocks(first: &str, second: &str) -> Vec<(usize, usize, usize)> {
+        let (first, second) = (chars(first), chars(second));
+        SequenceMatcher::new(&first, &second)
+            .get_matching_blocks()
+            .iter()
+            .map(|m| (m.first_start, m.second_start, m.size))
+            .collect()
+    }
+
+    fn opcodes(first: &str, second: &str) -> Vec<(&'static str, usize, usize, usize, usize)> {
+        let (first, second) = (chars(first), chars(second));
+        SequenceMatcher::new(&first, &second)
+            .get_opcodes()
+            .iter()
+            .map(|opcode| {
+                (
+                    opcode.tag,
+                    opcode.first_start,
+                    opcode.first_end,
+                    opcode.second_start,
+                    opcode.second_end,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_empty() {
+        assert_eq!(matching_blocks("", ""), [(0, 0, 0)]);
+        assert!(opcodes("", "").is_empty());
+        assert_eq!(opcodes("", "ab"), [("insert", 0, 0, 0, 2)]);
+        assert_eq!(opcodes("ab", ""), [("delete", 0, 2, 0, 0)]);
+    }
+
+    #[test]
+    fn test_all_equal() {
+        assert_eq!(matching_blocks("abc", "abc"), [(0, 0, 3), (3, 3, 0)]);
+        assert_eq!(opcodes("abc", "abc"), [("equal", 0, 3, 0, 3)]);
+    }
+
+    #[test]
+    fn test_all_different() {
+        assert_eq!(matching_blocks("abc", "xyz"), [(3, 3, 0)]);
+        assert_eq!(opcodes("abc", "xyz"), [("replace", 0, 3, 0, 3)]);
+    }
+
+    #[test]
+    fn test_duplicates() {
+        // The longest match starting earliest in the first sequence, then
+        // in the second one, is preferred.
+        assert_eq!(matching_blocks("aa", "aaa"), [(0, 0, 2), (2, 3, 0)]);
+        assert_eq!(
+            opcodes("aa", "aaa"),
+            [("equal", 0, 2, 0, 2), ("insert", 2, 2, 2, 3)]
+        );
+        assert_eq!(
+            opcodes("abab", "ba"),
+            [
+                ("delete", 0, 1, 0, 0),
+                ("equal", 1, 3, 0, 2),
+                ("delete", 3, 4, 2, 2)
+            ]
+        );
+    }
+
+    // The cases below are those of the documentation and of the test suite
+    // of `difflib`.
+
+    #[test]
+    fn test_find_longest_match() {
+        let (first, second) = (chars(" abcd"), chars("abcd abcd"));
+        let m = SequenceMatcher::new(&first, &second).find_longest_match(0, 5, 0, 9);
+        assert_eq!((m.first_start, m.second_start, m.size), (0, 4, 5));
+    }
+
+    #[test]
+    fn test_get_matching_blocks() {
+        assert_eq!(
+            matching_blocks("abxcd", "abcd"),
+            [(0, 0, 2), (3, 2, 2), (5, 4, 0)]
+        );
+    }
+
+    #[test]
+    fn test_get_opcodes() {
+        assert_eq!(
+            opcodes("qabxcd", "abycdf"),
+            [
+                ("delete", 0, 1, 0, 0),
+                ("equal", 1, 3, 0, 2),
+                ("replace", 3, 4, 2, 3),
+                ("equal", 4, 6, 3, 5),
+                ("insert", 6, 6, 5, 6),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_one_insert() {
+        let (first, second) = ("b".repeat(100), format!("a{}", "b".repeat(100)));
+        assert_eq!(
+            opcodes(&first, &second),
+            [("insert", 0, 0, 0, 1), ("equal", 0, 100, 1, 101)]
+        );
+        let second = format!("{}a{}", "b".repeat(50), "b".repeat(50));
+        assert_eq!(
+            opcodes(&first, &second),
+            [
+                ("equal", 0, 50, 0, 50),
+                ("insert", 50, 50, 50, 51),
+                ("equal", 50, 100, 51, 101),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_one_delete() {
+        let first = format!("{}c{}", "a".repeat(40), "b".repeat(40));
+        let second = format!("{}{}", "a".repeat(40), "b".repeat(40));
+        assert_eq!(
+            opcodes(&first, &second),
+            [
+                ("equal", 0, 40, 0, 40),
+                ("delete", 40, 41, 40, 40),
+                ("equal", 41, 81, 40, 80),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_popular_elements() {
+        // In a second sequence of at least 200 elements, the elements
+        // occurring in more than 1% of it are not matched.
+        let (first, second) = ("b".repeat(200), format!("a{}", "b".repeat(200)));
+        assert_eq!(matching_blocks(&first, &second), [(200, 201, 0)]);
+        assert_eq!(opcodes(&first, &second), [("replace", 0, 200, 0, 201)]);
+    }
+}
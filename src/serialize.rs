@@ -0,0 +1,260 @@
+use alloc::string::ToString;
+
+use serde::de::{self, Deserialize, Deserializer, Unexpected};
+use serde::ser::{Serialize, SerializeMap, SerializeSeq, SerializeStruct, Serializer};
+use serde_json::{Map, Value};
+
+use crate::diff::JsonDiff;
+use crate::flatten::{Change, ChangeKind};
+use crate::markers::DiffMarkers;
+use crate::pointer::JsonPointer;
+
+// A JSON structural difference whose changes are serialized with their old
+// side first, whatever the order of the keys kept by `Map`.
+struct OrderedDiff<'a> {
+    diff: &'a Value,
+    markers: &'a DiffMarkers,
+}
+
+impl Serialize for OrderedDiff<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let ordered = |diff| OrderedDiff {
+            diff,
+            markers: self.markers,
+        };
+        match self.diff {
+            Value::Object(obj) => {
+                let mut state = serializer.serialize_map(Some(obj.len()))?;
+                if let Some((old, new)) = self.markers.as_change(obj) {
+                    state.serialize_entry(&self.markers.old, old)?;
+                    state.serialize_entry(&self.markers.new, new)?;
+                } else {
+                    for (key, value) in obj {
+                        state.serialize_entry(key, &ordered(value))?;
+                    }
+                }
+                state.end()
+            }
+            Value::Array(array) => {
+                let mut state = serializer.serialize_seq(Some(array.len()))?;
+                for value in array {
+                    state.serialize_element(&ordered(value))?;
+                }
+                state.end()
+            }
+            _ => self.diff.serialize(serializer),
+        }
+    }
+}
+
+impl Serialize for JsonDiff {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        // Complete differences are serialized as before `truncated` existed.
+        let len = if self.truncated { 4 } else { 3 };
+        let mut state = serializer.serialize_struct("JsonDiff", len)?;
+        state.serialize_field("score", &self.score)?;
+        let markers = self.markers();
+        let diff = self.diff.as_ref().map(|diff| OrderedDiff {
+            diff,
+            markers: &markers,
+        });
+        state.serialize_field("diff", &diff)?;
+        state.serialize_field("similarity", &self.similarity)?;
+        if self.truncated {
+            state.serialize_field("truncated", &self.truncated)?;
+        }
+        state.end()
+    }
+}
+
+impl Serialize for ChangeKind {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let (index, name) = match self {
+            Self::Added => (0, "added"),
+            Self::Removed => (1, "removed"),
+            Self::Changed => (2, "changed"),
+            Self::Moved => (3, "moved"),
+            Self::Renamed => (4, "renamed"),
+        };
+        serializer.serialize_unit_variant("ChangeKind", index, name)
+    }
+}
+
+impl Serialize for JsonPointer {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+impl Serialize for Change {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut state = serializer.serialize_struct("Change", 4)?;
+        state.serialize_field("path", &self.path)?;
+        state.serialize_field("kind", &self.kind)?;
+        state.serialize_field("old", &self.old)?;
+        state.serialize_field("new", &self.new)?;
+        state.end()
+    }
+}
+
+fn as_number<E: de::Error>(value: &Value) -> Result<f64, E> {
+    value
+        .as_f64()
+        .ok_or_else(|| E::invalid_type(Unexpected::Other(&value.to_string()), &"a number"))
+}
+
+impl<'de> Deserialize<'de> for JsonDiff {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let mut fields = Map::deserialize(deserializer)?;
+
+        let score = fields
+            .remove("score")
+            .ok_or_else(|| de::Error::missing_field("score"))?;
+        let score = as_number(&score)?;
+
+        let diff = match fields.remove("diff") {
+            None | Some(Value::Null) => None,
+            Some(diff) => Some(diff),
+        };
+
+        // Differences serialized without a similarity are approximated
+        // through their score.
+        let similarity = match fields.remove("similarity") {
+            Some(similarity) => as_number(&similarity)?,
+            None if diff.is_none() => 1.,
+            None => (score / 100.).clamp(0., 1.),
+        };
+
+        let truncated = match fields.remove("truncated") {
+            Some(truncated) => truncated.as_bool().ok_or_else(|| {
+                de::Error::invalid_type(Unexpected::Other(&truncated.to_string()), &"a boolean")
+            })?,
+            None => false,
+        };
+
+        Ok(Self {
+            score,
+            diff,
+            truncated,
+            similarity,
+            filtered: false,
+            markers: None,
+        })
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+
+    use crate::diff::JsonDiff;
+    use crate::markers::DiffMarkers;
+    use crate::options::DiffOptions;
+
+    #[test]
+    fn test_serialize() {
+        let json_diff = JsonDiff::diff(
+            &json!({"foo": 42, "bar": [1, 2, 3], "baz": {"qux": true}}),
+            &json!({"foo": 10, "bar": [1, 3], "quux": null}),
+            false,
+        );
+
+        let serialized = serde_json::to_value(&json_diff).unwrap();
+        assert_eq!(
+            serialized,
+            json!({
+                "score": json_diff.score,
+                "diff": json_diff.diff,
+                "similarity": json_diff.similarity()
+            })
+        );
+
+        let deserialized: JsonDiff =
+            serde_json::from_str(&serde_json::to_string(&json_diff).unwrap()).unwrap();
+        assert!((deserialized.score - json_diff.score).abs() < f64::EPSILON);
+        assert_eq!(deserialized.diff, json_diff.diff);
+        assert!((deserialized.similarity() - json_diff.similarity()).abs() < f64::EPSILON);
+
+        let identical = JsonDiff::diff(&json!([1, 2]), &json!([1, 2]), false);
+        assert_eq!(
+            serde_json::to_string(&identical).unwrap(),
+            r#"{"score":100.0,"diff":null,"similarity":1.0}"#
+        );
+        let deserialized: JsonDiff = serde_json::from_str(r#"{"score":100.0}"#).unwrap();
+        assert_eq!(deserialized.diff, None);
+        assert!((deserialized.similarity() - 1.).abs() < f64::EPSILON);
+
+        let truncated = JsonDiff::diff_with_options(
+            &json!({"foo": 1, "bar": 2}),
+            &json!({"foo": 3, "bar": 4}),
+            &DiffOptions {
+                max_changes: Some(1),
+                ..DiffOptions::default()
+            },
+        );
+        let serialized = serde_json::to_value(&truncated).unwrap();
+        assert_eq!(serialized["truncated"], json!(true));
+        let deserialized: JsonDiff = serde_json::from_value(serialized).unwrap();
+        assert!(deserialized.truncated);
+
+        // The old side of a change always comes first, even when its marker
+        // is sorted after the one of the new side.
+        let options = DiffOptions {
+            markers: DiffMarkers {
+                old: "b".to_owned(),
+                new: "a".to_owned(),
+                ..DiffMarkers::default()
+            },
+            ..DiffOptions::default()
+        };
+        let json_diff = JsonDiff::diff_with_options(&json!({"x": 1}), &json!({"x": 2}), &options);
+        assert_eq!(
+            serde_json::to_string(&json_diff).unwrap(),
+            format!(
+                r#"{{"score":{:?},"diff":{{"x":{{"b":1,"a":2}}}},"similarity":{:?}}}"#,
+                json_diff.score,
+                json_diff.similarity()
+            )
+        );
+
+        assert!(serde_json::from_str::<JsonDiff>(r#"{"diff":null}"#).is_err());
+        assert!(serde_json::from_str::<JsonDiff>(r#"{"score":100.0,"truncated":"yes"}"#).is_err());
+        assert!(serde_json::from_str::<JsonDiff>(r#"{"score":"high"}"#).is_err());
+        assert!(
+            serde_json::from_str::<JsonDiff>(r#"{"score":100.0,"similarity":"high"}"#).is_err()
+        );
+    }
+
+    #[test]
+    fn test_serialize_changes() {
+        let json_diff = JsonDiff::diff(
+            &json!({"foo": [1], "bar": 2}),
+            &json!({"foo": [1, 3]}),
+            false,
+        );
+        assert_eq!(
+            serde_json::to_value(json_diff.flatten()).unwrap(),
+            json!([
+                {"path": "/bar", "kind": "removed", "old": 2, "new": null},
+                {"path": "/foo/1", "kind": "added", "old": null, "new": 3}
+            ])
+        );
+    }
+}
@@ -0,0 +1,234 @@
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use core::fmt;
+
+use serde_json::Value;
+
+use crate::diff::JsonDiff;
+use crate::markers::{DiffMarkers, MarkedKey};
+
+/// Counts of the changes contained in a JSON structural difference.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DiffStats {
+    /// Number of keys only present in the new JSON file.
+    pub keys_added: usize,
+    /// Number of keys only present in the old JSON file.
+    pub keys_removed: usize,
+    /// Number of keys renamed, whether their value has changed or not.
+    pub keys_renamed: usize,
+    /// Number of values replaced by another value, at any depth.
+    pub values_changed: usize,
+    /// Number of array items only present in the new JSON file.
+    pub items_inserted: usize,
+    /// Number of array items only present in the old JSON file.
+    pub items_deleted: usize,
+    /// Number of array items moved to another index.
+    pub items_moved: usize,
+}
+
+impl DiffStats {
+    /// Returns `true` if no change has been counted.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Counts the changes contained in a JSON structural difference, as
+    /// spent from a change budget.
+    pub(crate) fn count(diff: &Value, markers: &DiffMarkers) -> usize {
+        let mut stats = Self::default();
+        stats.tally(diff, markers);
+        stats.keys_added
+            + stats.keys_removed
+            + stats.keys_renamed
+            + stats.values_changed
+            + stats.items_inserted
+            + stats.items_deleted
+            + stats.items_moved
+    }
+
+    fn tally(&mut self, diff: &Value, markers: &DiffMarkers) {
+        match diff {
+            Value::Object(obj) => {
+                if markers.as_change(obj).is_some() || markers.as_text(obj).is_some() {
+                    self.values_changed += 1;
+                } else if let Some(embedded) = markers.as_embedded(obj) {
+                    self.tally(embedded, markers);
+                } else {
+                    for (key, value) in obj {
+                        self.tally_key(&markers.parse_key(key), value, markers);
+                    }
+                }
+            }
+            Value::Array(entries) => {
+                for entry in entries {
+                    match entry.get(0).and_then(Value::as_str) {
+                        Some("+") => self.items_inserted += 1,
+                        Some("-") => self.items_deleted += 1,
+                        Some(">") => self.items_moved += 1,
+                        Some("~") => {
+                            if let Some(change) = entry.get(1) {
+                                self.tally(change, markers);
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn tally_key(&mut self, key: &MarkedKey, value: &Value, markers: &DiffMarkers) {
+        match key {
+            MarkedKey::Added(_) => self.keys_added += 1,
+            MarkedKey::Deleted(_) => self.keys_removed += 1,
+            MarkedKey::Unchanged(_) => {}
+            MarkedKey::Shared(_) => self.tally(value, markers),
+            MarkedKey::Renamed(..) => {
+                self.keys_renamed += 1;
+                self.tally(value, markers);
+            }
+        }
+    }
+}
+
+impl fmt::Display for DiffStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let counts = [
+            (self.keys_added, "key", "keys", "added"),
+            (self.keys_removed, "key", "keys", "removed"),
+            (self.keys_renamed, "key", "keys", "renamed"),
+            (self.values_changed, "value", "values", "changed"),
+            (self.items_inserted, "item", "items", "inserted"),
+            (self.items_deleted, "item", "items", "deleted"),
+            (self.items_moved, "item", "items", "moved"),
+        ];
+        let mut separator = "";
+        for (count, singular, plural, action) in counts {
+            if count > 0 {
+                let noun = if count == 1 { singular } else { plural };
+                write!(f, "{separator}{count} {noun} {action}")?;
+                separator = ", ";
+            }
+        }
+        if self.is_empty() {
+            write!(f, "no changes")?;
+        }
+        Ok(())
+    }
+}
+
+impl JsonDiff {
+    /// Counts the changes contained in the JSON structural difference.
+    #[must_use]
+    pub fn stats(&self) -> DiffStats {
+        let mut stats = DiffStats::default();
+        if let Some(diff) = &self.diff {
+            stats.tally(diff, &self.markers());
+        }
+        stats
+    }
+
+    /// Counts the changes contained in the JSON structural difference for
+    /// each top-level key whose value has changed.
+    ///
+    /// If the two JSON files are not both objects, the map is empty.
+    #[must_use]
+    pub fn stats_by_key(&self) -> BTreeMap<String, DiffStats> {
+        let markers = self.markers();
+        let mut stats_by_key = BTreeMap::new();
+        if let Some(Value::Object(obj)) = &self.diff {
+            if markers.as_change(obj).is_none()
+                && markers.as_embedded(obj).is_none()
+                && markers.as_text(obj).is_none()
+            {
+                for (key, value) in obj {
+                    let key = markers.parse_key(key);
+                    // A renamed key is counted under its old name.
+                    let (MarkedKey::Added(name)
+                    | MarkedKey::Deleted(name)
+                    | MarkedKey::Shared(name)
+                    | MarkedKey::Renamed(name, _)) = key
+                    else {
+                        continue;
+                    };
+                    let stats: &mut DiffStats = stats_by_key.entry(name.to_string()).or_default();
+                    stats.tally_key(&key, value, &markers);
+                }
+            }
+        }
+        stats_by_key
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+
+    use serde_json::Value;
+
+    use super::DiffStats;
+    use crate::diff::JsonDiff;
+    use crate::options::DiffOptions;
+
+    #[test]
+    fn test_stats() {
+        let read = |filename| -> Value {
+            serde_json::from_str(&std::fs::read_to_string(filename).unwrap()).unwrap()
+        };
+        let json_diff = JsonDiff::diff(&read("data/a.json"), &read("data/b.json"), false);
+
+        let boz = DiffStats {
+            items_inserted: 2,
+            items_deleted: 2,
+            ..DiffStats::default()
+        };
+        let fubar = DiffStats {
+            values_changed: 1,
+            ..DiffStats::default()
+        };
+        assert_eq!(
+            json_diff.stats(),
+            DiffStats {
+                values_changed: 1,
+                items_inserted: 2,
+                items_deleted: 2,
+                ..DiffStats::default()
+            }
+        );
+        assert_eq!(
+            json_diff.stats_by_key().into_iter().collect::<Vec<_>>(),
+            [("boz".to_owned(), boz), ("fubar".to_owned(), fubar)]
+        );
+        assert_eq!(
+            json_diff.stats().to_string(),
+            "1 value changed, 2 items inserted, 2 items deleted"
+        );
+
+        let json_diff = JsonDiff::diff_with_options(
+            &json!({"foo": 1, "bar": [1, 2, 3], "baz": {"x": 1}, "qux": true}),
+            &json!({"foo": 2, "bar": [3, 1], "baz": {"x": 1, "y": 2}, "corge": null}),
+            &DiffOptions {
+                detect_moves: true,
+                ..DiffOptions::default()
+            },
+        );
+        assert_eq!(
+            json_diff.stats(),
+            DiffStats {
+                keys_added: 2,
+                keys_removed: 1,
+                keys_renamed: 0,
+                values_changed: 1,
+                items_inserted: 0,
+                items_deleted: 1,
+                items_moved: 1,
+            }
+        );
+
+        let json_diff = JsonDiff::diff(&json!(1), &json!(1), false);
+        assert!(json_diff.stats().is_empty());
+        assert_eq!(json_diff.stats().to_string(), "no changes");
+        assert!(json_diff.stats_by_key().is_empty());
+    }
+}
@@ -0,0 +1,838 @@
+use alloc::boxed::Box;
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::mem;
+
+use serde_json::{Map, Value};
+
+use crate::diff::JsonDiff;
+use crate::markers::{DiffMarkers, MarkedKey};
+
+/// A node of a JSON structural difference.
+#[derive(Clone, Debug, PartialEq)]
+pub enum DiffNode {
+    /// Values without any difference.
+    Unchanged,
+    /// A value replaced by another value.
+    Scalar {
+        /// The old value.
+        old: Value,
+        /// The new value.
+        new: Value,
+    },
+    /// The differences between two objects, keyed by their unescaped keys.
+    Object {
+        /// The keys only present in the new object.
+        added: BTreeMap<String, Value>,
+        /// The keys only present in the old object.
+        deleted: BTreeMap<String, Value>,
+        /// The keys present in both objects, whose values differ.
+        changed: BTreeMap<String, DiffNode>,
+        /// The keys renamed, keyed by their old name, along with their new
+        /// name and the difference of their values.
+        renamed: BTreeMap<String, (String, DiffNode)>,
+    },
+    /// The differences between two arrays, item by item.
+    Array(Vec<ArrayOp>),
+    /// The difference between the JSON embedded in two strings.
+    Embedded(Box<DiffNode>),
+    /// The difference between two strings compared character by character.
+    Text(Vec<TextSegment>),
+}
+
+/// A run of characters of the difference between two strings.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TextSegment {
+    /// Characters present in both strings.
+    Kept(String),
+    /// Characters only present in the old string.
+    Removed(String),
+    /// Characters only present in the new string.
+    Added(String),
+}
+
+impl TextSegment {
+    fn parse(segment: &Value) -> Option<Self> {
+        let text = String::from(segment.get(1)?.as_str()?);
+        match segment.get(0)?.as_str()? {
+            " " => Some(Self::Kept(text)),
+            "-" => Some(Self::Removed(text)),
+            "+" => Some(Self::Added(text)),
+            _ => None,
+        }
+    }
+
+    fn to_value(&self) -> Value {
+        match self {
+            Self::Kept(text) => json!([" ", text]),
+            Self::Removed(text) => json!(["-", text]),
+            Self::Added(text) => json!(["+", text]),
+        }
+    }
+
+    fn invert(&self) -> Self {
+        match self {
+            Self::Kept(text) => Self::Kept(text.clone()),
+            Self::Removed(text) => Self::Added(text.clone()),
+            Self::Added(text) => Self::Removed(text.clone()),
+        }
+    }
+}
+
+/// An item of the difference between two arrays.
+///
+/// The indices are only present with [`DiffOptions::include_indices`].
+///
+/// [`DiffOptions::include_indices`]: crate::DiffOptions::include_indices
+#[derive(Clone, Debug, PartialEq)]
+pub enum ArrayOp {
+    /// An unchanged item, along with its value if it is a scalar.
+    Unchanged(Option<Value>),
+    /// An item only present in the new array.
+    Added {
+        /// The added item.
+        value: Value,
+        /// Its index in the new array.
+        index: Option<usize>,
+    },
+    /// An item only present in the old array.
+    Removed {
+        /// The removed item.
+        value: Value,
+        /// Its index in the old array.
+        index: Option<usize>,
+    },
+    /// An item present in both arrays, whose value differs.
+    Changed {
+        /// The difference of the item.
+        diff: DiffNode,
+        /// Its index in the old array.
+        old_index: Option<usize>,
+        /// Its index in the new array.
+        new_index: Option<usize>,
+    },
+    /// An item moved to another index.
+    Moved {
+        /// Its index in the old array.
+        from: usize,
+        /// Its index in the new array.
+        to: usize,
+    },
+}
+
+/// An item of the difference between two arrays, without its indices.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ArrayChange {
+    /// An unchanged item, along with its value if it is a scalar.
+    Kept(Option<Value>),
+    /// An item present in both arrays, whose value differs.
+    Changed(DiffNode),
+    /// An item only present in the old array.
+    Removed(Value),
+    /// An item only present in the new array.
+    Added(Value),
+    /// An item moved to another index.
+    Moved {
+        /// Its index in the old array.
+        from: usize,
+        /// Its index in the new array.
+        to: usize,
+    },
+}
+
+impl From<ArrayOp> for ArrayChange {
+    fn from(op: ArrayOp) -> Self {
+        match op {
+            ArrayOp::Unchanged(value) => Self::Kept(value),
+            ArrayOp::Changed { diff, .. } => Self::Changed(diff),
+            ArrayOp::Removed { value, .. } => Self::Removed(value),
+            ArrayOp::Added { value, .. } => Self::Added(value),
+            ArrayOp::Moved { from, to } => Self::Moved { from, to },
+        }
+    }
+}
+
+/// Parses the difference between two arrays, i.e. an array of
+/// `[' ', value]`, `['~', change]`, `['-', value]`, `['+', value]` and
+/// `['>', from, to]` entries, the indices being ignored.
+///
+/// The match scores are recognized by the default [`DiffMarkers::score`](field@DiffMarkers::score).
+/// Returns `None` if it is not the difference between two arrays.
+#[must_use]
+pub fn parse_array_diff(diff: &Value) -> Option<Vec<ArrayChange>> {
+    let markers = DiffMarkers::default();
+    diff.as_array()?
+        .iter()
+        .map(|entry| parse_entry(entry, &markers).map(ArrayChange::from))
+        .collect()
+}
+
+fn parse_entry(entry: &Value, markers: &DiffMarkers) -> Option<ArrayOp> {
+    // The match scores are left out.
+    let entry = markers.without_score(entry.as_array()?);
+    let indices = |start: usize| -> Option<Vec<usize>> {
+        entry[start..]
+            .iter()
+            .map(|index| usize::try_from(index.as_u64()?).ok())
+            .collect()
+    };
+    let op = match (entry.first()?.as_str()?, entry.len()) {
+        (" ", 1 | 2) => ArrayOp::Unchanged(entry.get(1).cloned()),
+        ("+", 2 | 3) => ArrayOp::Added {
+            value: entry[1].clone(),
+            index: indices(2)?.first().copied(),
+        },
+        ("-", 2 | 3) => ArrayOp::Removed {
+            value: entry[1].clone(),
+            index: indices(2)?.first().copied(),
+        },
+        ("~", 2 | 4) => {
+            let indices = indices(2)?;
+            ArrayOp::Changed {
+                diff: parse_node(&entry[1], markers)?,
+                old_index: indices.first().copied(),
+                new_index: indices.get(1).copied(),
+            }
+        }
+        (">", 3) => {
+            let indices = indices(1)?;
+            ArrayOp::Moved {
+                from: indices[0],
+                to: indices[1],
+            }
+        }
+        _ => return None,
+    };
+    Some(op)
+}
+
+pub(crate) fn parse_node(diff: &Value, markers: &DiffMarkers) -> Option<DiffNode> {
+    match diff {
+        Value::Object(obj) => {
+            if let Some((old, new)) = markers.as_change(obj) {
+                return Some(DiffNode::Scalar {
+                    old: old.clone(),
+                    new: new.clone(),
+                });
+            }
+            if let Some(embedded) = markers.as_embedded(obj) {
+                return Some(DiffNode::Embedded(Box::new(parse_node(embedded, markers)?)));
+            }
+            if let Some(segments) = markers.as_text(obj) {
+                return segments
+                    .iter()
+                    .map(TextSegment::parse)
+                    .collect::<Option<_>>()
+                    .map(DiffNode::Text);
+            }
+            let mut added = BTreeMap::new();
+            let mut deleted = BTreeMap::new();
+            let mut changed = BTreeMap::new();
+            let mut renamed = BTreeMap::new();
+            for (key, value) in obj {
+                match markers.parse_key(key) {
+                    MarkedKey::Added(key) => {
+                        added.insert(String::from(key), value.clone());
+                    }
+                    MarkedKey::Deleted(key) => {
+                        deleted.insert(String::from(key), value.clone());
+                    }
+                    // The unchanged keys shown for context are left out.
+                    MarkedKey::Unchanged(_) => {}
+                    MarkedKey::Shared(key) => {
+                        changed.insert(String::from(key), parse_node(value, markers)?);
+                    }
+                    MarkedKey::Renamed(old_key, new_key) => {
+                        // The values of a renamed key may be equal.
+                        let node = if value.is_null() {
+                            DiffNode::Unchanged
+                        } else {
+                            parse_node(value, markers)?
+                        };
+                        renamed.insert(String::from(old_key), (String::from(new_key), node));
+                    }
+                }
+            }
+            Some(DiffNode::Object {
+                added,
+                deleted,
+                changed,
+                renamed,
+            })
+        }
+        Value::Array(entries) => entries
+            .iter()
+            .map(|entry| parse_entry(entry, markers))
+            .collect::<Option<_>>()
+            .map(DiffNode::Array),
+        _ => None,
+    }
+}
+
+fn entry_value(op: char, value: Value, indices: &[Option<usize>]) -> Value {
+    let mut entry = vec![Value::from(String::from(op)), value];
+    entry.extend(indices.iter().flatten().map(|&index| Value::from(index)));
+    Value::Array(entry)
+}
+
+impl ArrayOp {
+    fn value_with(&self, markers: &DiffMarkers) -> Value {
+        match self {
+            Self::Unchanged(None) => json!([" "]),
+            Self::Unchanged(Some(value)) => json!([" ", value]),
+            Self::Added { value, index } => entry_value('+', value.clone(), &[*index]),
+            Self::Removed { value, index } => entry_value('-', value.clone(), &[*index]),
+            Self::Changed {
+                diff,
+                old_index,
+                new_index,
+            } => entry_value(
+                '~',
+                diff.value_with(markers).unwrap_or(Value::Null),
+                &[*old_index, *new_index],
+            ),
+            Self::Moved { from, to } => json!([">", from, to]),
+        }
+    }
+
+    fn invert(&self) -> Self {
+        match self {
+            Self::Unchanged(value) => Self::Unchanged(value.clone()),
+            Self::Added { value, index } => Self::Removed {
+                value: value.clone(),
+                index: *index,
+            },
+            Self::Removed { value, index } => Self::Added {
+                value: value.clone(),
+                index: *index,
+            },
+            Self::Changed {
+                diff,
+                old_index,
+                new_index,
+            } => Self::Changed {
+                diff: diff.invert(),
+                old_index: *new_index,
+                new_index: *old_index,
+            },
+            Self::Moved { from, to } => Self::Moved {
+                from: *to,
+                to: *from,
+            },
+        }
+    }
+}
+
+fn invert_ops(ops: &[ArrayOp]) -> Vec<ArrayOp> {
+    let (mut moved_from, mut moved_to) = (BTreeSet::new(), BTreeSet::new());
+    for op in ops {
+        if let ArrayOp::Moved { from, to } = op {
+            moved_from.insert(*from);
+            moved_to.insert(*to);
+        }
+    }
+    if moved_from.is_empty() {
+        return invert_unmoved_ops(ops);
+    }
+    invert_moved_ops(ops, &moved_from, &moved_to)
+}
+
+/// Inverts the operations of an array difference without moves.
+fn invert_unmoved_ops(ops: &[ArrayOp]) -> Vec<ArrayOp> {
+    let is_removed = |op: &&ArrayOp| matches!(op, ArrayOp::Removed { .. });
+    let is_added = |op: &&ArrayOp| matches!(op, ArrayOp::Added { .. });
+
+    let mut inverted = Vec::with_capacity(ops.len());
+    let mut position = 0;
+    while position < ops.len() {
+        // A run of removed items followed by added items replaces the
+        // former with the latter, so it is inverted as a whole to keep the
+        // removed items first.
+        let removed = ops[position..].iter().take_while(is_removed).count();
+        let added = ops[position + removed..]
+            .iter()
+            .take_while(is_added)
+            .count();
+        if removed + added == 0 {
+            inverted.push(ops[position].invert());
+            position += 1;
+        } else {
+            let (removed, added) = ops[position..position + removed + added].split_at(removed);
+            inverted.extend(added.iter().chain(removed).map(ArrayOp::invert));
+            position += removed.len() + added.len();
+        }
+    }
+    inverted
+}
+
+/// Inverts the operations of an array difference with moves, given the
+/// old and new indices of the moved items.
+///
+/// The items kept in place are in the same order in both arrays, and the
+/// other items are placed between them, the removed ones first by their old
+/// index, then the added and moved ones by their new index. The indices
+/// being swapped, the other items are placed again around the kept ones.
+fn invert_moved_ops(
+    ops: &[ArrayOp],
+    moved_from: &BTreeSet<usize>,
+    moved_to: &BTreeSet<usize>,
+) -> Vec<ArrayOp> {
+    // The indices of the items which are not moved follow each other.
+    let next = |index: &mut usize, moved: &BTreeSet<usize>| {
+        while moved.contains(index) {
+            *index += 1;
+        }
+        *index += 1;
+        *index - 1
+    };
+
+    // The kept items, along with their inverted old and new indices, and
+    // the other items, keyed by their inverted old or new index.
+    let mut kept: Vec<(usize, usize, ArrayOp)> = Vec::new();
+    let mut removed: BTreeMap<usize, ArrayOp> = BTreeMap::new();
+    let mut added: BTreeMap<usize, ArrayOp> = BTreeMap::new();
+    let (mut old_index, mut new_index) = (0, 0);
+    for op in ops {
+        match op {
+            ArrayOp::Removed { .. } => {
+                added.insert(next(&mut old_index, moved_from), op.invert());
+            }
+            ArrayOp::Added { .. } => {
+                removed.insert(next(&mut new_index, moved_to), op.invert());
+            }
+            ArrayOp::Moved { from, .. } => {
+                added.insert(*from, op.invert());
+            }
+            ArrayOp::Unchanged(_) | ArrayOp::Changed { .. } => {
+                let old = next(&mut old_index, moved_from);
+                let new = next(&mut new_index, moved_to);
+                kept.push((new, old, op.invert()));
+            }
+        }
+    }
+
+    let mut inverted = Vec::with_capacity(ops.len());
+    for (old_index, new_index, op) in kept {
+        let later_removed = removed.split_off(&old_index);
+        inverted.extend(mem::replace(&mut removed, later_removed).into_values());
+        let later_added = added.split_off(&new_index);
+        inverted.extend(mem::replace(&mut added, later_added).into_values());
+        inverted.push(op);
+    }
+    inverted.extend(removed.into_values());
+    inverted.extend(added.into_values());
+    inverted
+}
+
+impl DiffNode {
+    fn value_with(&self, markers: &DiffMarkers) -> Option<Value> {
+        let value = match self {
+            Self::Unchanged => return None,
+            Self::Scalar { old, new } => markers.change(old, new),
+            Self::Object {
+                added,
+                deleted,
+                changed,
+                renamed,
+            } => {
+                let mut obj = Map::new();
+                for (key, value) in deleted {
+                    obj.insert(markers.deleted_key(key), value.clone());
+                }
+                for (key, node) in changed {
+                    if let Some(value) = node.value_with(markers) {
+                        obj.insert(markers.shared_key(key).into_owned(), value);
+                    }
+                }
+                for (key, value) in added {
+                    obj.insert(markers.added_key(key), value.clone());
+                }
+                for (old_key, (new_key, node)) in renamed {
+                    obj.insert(
+                        markers.renamed_key(old_key, new_key),
+                        node.value_with(markers).unwrap_or(Value::Null),
+                    );
+                }
+                Value::Object(obj)
+            }
+            Self::Array(ops) => Value::Array(ops.iter().map(|op| op.value_with(markers)).collect()),
+            Self::Embedded(node) => markers.embedded(node.value_with(markers)?),
+            Self::Text(segments) => {
+                markers.text(segments.iter().map(TextSegment::to_value).collect())
+            }
+        };
+        Some(value)
+    }
+
+    /// Returns the node of the reverse difference, i.e. from the new JSON
+    /// file to the old one.
+    ///
+    /// Inverting a node twice gives back the same node.
+    #[must_use]
+    pub fn invert(&self) -> Self {
+        match self {
+            Self::Unchanged => Self::Unchanged,
+            Self::Scalar { old, new } => Self::Scalar {
+                old: new.clone(),
+                new: old.clone(),
+            },
+            Self::Object {
+                added,
+                deleted,
+                changed,
+                renamed,
+            } => Self::Object {
+                added: deleted.clone(),
+                deleted: added.clone(),
+                changed: changed
+                    .iter()
+                    .map(|(key, node)| (key.clone(), node.invert()))
+                    .collect(),
+                renamed: renamed
+                    .iter()
+                    .map(|(old_key, (new_key, node))| {
+                        (new_key.clone(), (old_key.clone(), node.invert()))
+                    })
+                    .collect(),
+            },
+            Self::Array(ops) => Self::Array(invert_ops(ops)),
+            Self::Embedded(node) => Self::Embedded(Box::new(node.invert())),
+            Self::Text(segments) => Self::Text(segments.iter().map(TextSegment::invert).collect()),
+        }
+    }
+
+    /// Returns the JSON structural difference represented by the node, using
+    /// the default [`DiffMarkers`], or `None` if it is
+    /// [`DiffNode::Unchanged`].
+    #[must_use]
+    pub fn to_value(&self) -> Option<Value> {
+        self.value_with(&DiffMarkers::default())
+    }
+}
+
+impl JsonDiff {
+    /// Returns the JSON structural difference as a tree of typed nodes.
+    ///
+    /// It is [`DiffNode::Unchanged`] when there is no difference, and `None`
+    /// when the difference cannot be parsed.
+    #[must_use]
+    pub fn tree(&self) -> Option<DiffNode> {
+        match &self.diff {
+            Some(diff) => parse_node(diff, &self.markers()),
+            None => Some(DiffNode::Unchanged),
+        }
+    }
+
+    /// Returns the reverse JSON structural difference, i.e. from the second
+    /// JSON file to the first one, without comparing them again.
+    ///
+    /// It is equal to the difference of the swapped JSON files, the moved
+    /// items being placed at their new index, as long as the array items are
+    /// matched the same way in both directions. The best matching is not
+    /// always symmetric, e.g. from `[1, 2, 3]` to `[3, 1]`, and the items of
+    /// unordered arrays are not reordered, but the reverse difference still
+    /// undoes the difference, and inverting it gives the difference back.
+    ///
+    /// It uses the same markers as the difference. Returns `None` when
+    /// there is no difference, or when the difference cannot be parsed.
+    #[must_use]
+    pub fn invert(&self) -> Option<Value> {
+        self.tree()?.invert().value_with(&self.markers())
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+
+    use std::collections::BTreeMap;
+    use std::fs::File;
+    use std::io::BufReader;
+
+    use serde_json::Value;
+
+    use super::{parse_array_diff, ArrayChange, ArrayOp, DiffNode};
+    use crate::diff::JsonDiff;
+    use crate::options::DiffOptions;
+
+    fn read_json_file(path: &str) -> Value {
+        serde_json::from_reader(BufReader::new(File::open(path).unwrap())).unwrap()
+    }
+
+    #[test]
+    fn test_parse_array_diff() {
+        let diff = |json1: Value, json2: Value| JsonDiff::diff(&json1, &json2, false).diff.unwrap();
+
+        // The cases of `test_array_of_scalars`.
+        assert_eq!(
+            parse_array_diff(&diff(json!([10, 20, 30]), json!([10, 30]))),
+            Some(vec![
+                ArrayChange::Kept(Some(json!(10))),
+                ArrayChange::Removed(json!(20)),
+                ArrayChange::Kept(Some(json!(30))),
+            ])
+        );
+        assert_eq!(
+            parse_array_diff(&diff(json!([10, 30]), json!([10, 20, 30]))),
+            Some(vec![
+                ArrayChange::Kept(Some(json!(10))),
+                ArrayChange::Added(json!(20)),
+                ArrayChange::Kept(Some(json!(30))),
+            ])
+        );
+
+        // The cases of `test_array_of_objects`.
+        assert_eq!(
+            parse_array_diff(&diff(
+                json!([{"foo": 10}, {"foo": 20}, {"foo": 30}]),
+                json!([{"foo": 10}, {"foo": 30}])
+            )),
+            Some(vec![
+                ArrayChange::Kept(None),
+                ArrayChange::Removed(json!({"foo": 20})),
+                ArrayChange::Kept(None),
+            ])
+        );
+        assert_eq!(
+            parse_array_diff(&diff(
+                json!([
+                    {"foo": 10, "bar": {"bbbar": 10, "bbboz": 11}},
+                    {"foo": 20, "bar": {"bbbar": 50, "bbboz": 25}},
+                    {"foo": 30, "bar": {"bbbar": 92, "bbboz": 34}}
+                ]),
+                json!([
+                    {"foo": 10, "bar": {"bbbar": 10, "bbboz": 11}},
+                    {"foo": 21, "bar": {"bbbar": 50, "bbboz": 25}},
+                    {"foo": 30, "bar": {"bbbar": 92, "bbboz": 34}}
+                ])
+            )),
+            Some(vec![
+                ArrayChange::Kept(None),
+                ArrayChange::Changed(DiffNode::Object {
+                    added: BTreeMap::new(),
+                    deleted: BTreeMap::new(),
+                    changed: BTreeMap::from([(
+                        "foo".to_owned(),
+                        DiffNode::Scalar {
+                            old: json!(20),
+                            new: json!(21)
+                        }
+                    )]),
+                    renamed: BTreeMap::new(),
+                }),
+                ArrayChange::Kept(None),
+            ])
+        );
+
+        // The indices are ignored.
+        let options = DiffOptions {
+            include_indices: true,
+            detect_moves: true,
+            ..DiffOptions::default()
+        };
+        assert_eq!(
+            parse_array_diff(
+                &JsonDiff::diff_with_options(&json!([1, 2, 3]), &json!([3, 1, 4]), &options)
+                    .diff
+                    .unwrap()
+            ),
+            Some(vec![
+                ArrayChange::Moved { from: 2, to: 0 },
+                ArrayChange::Kept(Some(json!(1))),
+                ArrayChange::Removed(json!(2)),
+                ArrayChange::Added(json!(4)),
+            ])
+        );
+
+        assert_eq!(parse_array_diff(&json!({"foo": 1})), None);
+        assert_eq!(parse_array_diff(&json!([["?", 1]])), None);
+    }
+
+    #[test]
+    fn test_tree() {
+        let json_diff = JsonDiff::diff(
+            &json!({"foo": [1, {"bar": 2, "a": true, "b": null}], "__old": true, "baz": 3}),
+            &json!({"foo": [{"bar": 3, "a": true, "b": null}, 4], "__old": false, "qux": null}),
+            false,
+        );
+        assert_eq!(
+            json_diff.tree(),
+            Some(DiffNode::Object {
+                added: BTreeMap::from([("qux".to_owned(), json!(null))]),
+                deleted: BTreeMap::from([("baz".to_owned(), json!(3))]),
+                changed: BTreeMap::from([
+                    (
+                        "__old".to_owned(),
+                        DiffNode::Scalar {
+                            old: json!(true),
+                            new: json!(false),
+                        }
+                    ),
+                    (
+                        "foo".to_owned(),
+                        DiffNode::Array(vec![
+                            ArrayOp::Removed {
+                                value: json!(1),
+                                index: None,
+                            },
+                            ArrayOp::Changed {
+                                diff: DiffNode::Object {
+                                    added: BTreeMap::new(),
+                                    deleted: BTreeMap::new(),
+                                    changed: BTreeMap::from([(
+                                        "bar".to_owned(),
+                                        DiffNode::Scalar {
+                                            old: json!(2),
+                                            new: json!(3),
+                                        }
+                                    )]),
+                                    renamed: BTreeMap::new(),
+                                },
+                                old_index: None,
+                                new_index: None,
+                            },
+                            ArrayOp::Added {
+                                value: json!(4),
+                                index: None,
+                            },
+                        ])
+                    ),
+                ]),
+                renamed: BTreeMap::new(),
+            })
+        );
+
+        assert_eq!(
+            JsonDiff::diff(&json!(1), &json!(1), false).tree(),
+            Some(DiffNode::Unchanged)
+        );
+        assert_eq!(DiffNode::Unchanged.to_value(), None);
+        let malformed = JsonDiff {
+            score: 0.,
+            diff: Some(json!({"foo": 1})),
+            truncated: false,
+            filtered: false,
+            similarity: 0.,
+            markers: None,
+        };
+        assert_eq!(malformed.tree(), None);
+    }
+
+    #[test]
+    fn test_invert() {
+        let fixtures = [
+            (read_json_file("data/a.json"), read_json_file("data/b.json")),
+            (
+                json!({"foo": {"bar": 1, "baz__added": [1, 2]}, "qux": true}),
+                json!({"foo": {"bar": "1", "__old": null}, "quux": false}),
+            ),
+            (json!([1, 2, 3, 4, 5]), json!([0, 2, 6, 7, 4])),
+            (
+                json!([{"a": 1, "b": 2, "c": 3}, "x", [1, 2]]),
+                json!(["y", {"a": 1, "b": 2, "c": 4}, [1, 3], "z"]),
+            ),
+            (json!({"foo": 1}), json!([1])),
+            (json!([1, 2, 3, 4]), json!([2, 3, 1, 5])),
+            (json!([1, 2, 3, 4, 5]), json!([5, 1, 2, 3, 4])),
+            (json!([1, 2]), json!([3, 4])),
+            (
+                json!({"a": {"x": 1, "y": 2, "z": 3}}),
+                json!({"b": {"x": 1, "y": 2, "z": 4}}),
+            ),
+            (
+                json!({"a": {"x": 1, "y": 2}, "c": 1}),
+                json!({"b": {"x": 1, "y": 2}, "d": 2}),
+            ),
+            (
+                json!([{"id": 1, "v": 1}, {"id": 2, "v": 2}]),
+                json!([7, {"id": 1, "v": 1}, {"id": 2, "v": 3}, 8]),
+            ),
+        ];
+        let options = [
+            DiffOptions::default(),
+            DiffOptions {
+                include_indices: true,
+                ..DiffOptions::default()
+            },
+            DiffOptions {
+                max_fuzzy_array_len: Some(0),
+                ..DiffOptions::default()
+            },
+            DiffOptions {
+                detect_moves: true,
+                ..DiffOptions::default()
+            },
+            DiffOptions {
+                detect_moves: true,
+                include_indices: true,
+                ..DiffOptions::default()
+            },
+            DiffOptions {
+                detect_renames: true,
+                ..DiffOptions::default()
+            },
+        ];
+
+        for (json1, json2) in &fixtures {
+            for options in &options {
+                let json_diff = JsonDiff::diff_with_options(json1, json2, options);
+                let reversed = JsonDiff::diff_with_options(json2, json1, options);
+                assert_eq!(json_diff.invert(), reversed.diff, "{json1} -> {json2}");
+
+                let tree = json_diff.tree().unwrap();
+                assert_eq!(tree.invert().invert(), tree);
+            }
+        }
+        assert_eq!(JsonDiff::diff(&json!(1), &json!(1), false).invert(), None);
+    }
+
+    #[test]
+    fn test_invert_round_trip() {
+        // The items are matched differently in the swapped arrays.
+        let (json1, json2) = (json!([1, 2, 3]), json!([3, 1]));
+        let json_diff = JsonDiff::diff(&json1, &json2, false);
+        let inverted = json_diff.invert();
+        assert_eq!(
+            inverted,
+            Some(json!([["-", 3], [" ", 1], ["+", 2], ["+", 3]]))
+        );
+        assert_ne!(inverted, JsonDiff::diff(&json2, &json1, false).diff);
+
+        let inverted = JsonDiff::from_diff_value(inverted.unwrap());
+        assert_eq!(inverted.invert(), json_diff.diff);
+    }
+
+    #[test]
+    fn test_tree_round_trip() {
+        let fixtures = [
+            (read_json_file("data/a.json"), read_json_file("data/b.json")),
+            (
+                json!({"count__added": 1, "__old": 2, "foo": {"bar__deleted": 4}}),
+                json!({"__old": 5, "foo": {"bar__deleted": 7}, "baz__added": 8}),
+            ),
+            (json!([1, 2, 3, 4]), json!([2, 3, 1, 5])),
+            (json!([{"a": 1}, "b", [1]]), json!(["b", {"a": 2}, [2], 3])),
+            (json!({"foo": 1}), json!([1])),
+        ];
+        let options = [
+            DiffOptions::default(),
+            DiffOptions {
+                include_indices: true,
+                detect_moves: true,
+                ..DiffOptions::default()
+            },
+            DiffOptions {
+                array_unordered: true,
+                ..DiffOptions::default()
+            },
+        ];
+
+        for (json1, json2) in &fixtures {
+            for options in &options {
+                let json_diff = JsonDiff::diff_with_options(json1, json2, options);
+                assert_eq!(json_diff.tree().unwrap().to_value(), json_diff.diff);
+            }
+        }
+    }
+}
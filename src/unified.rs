@@ -0,0 +1,515 @@
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+use serde_json::{Map, Value};
+
+use crate::diff::JsonDiff;
+use crate::tree::{ArrayOp, DiffNode};
+
+struct Hunk {
+    start: usize,
+    end: usize,
+}
+
+fn is_change(line: &str) -> bool {
+    line.starts_with('-') || line.starts_with('+')
+}
+
+fn hunk_range(start: usize, count: usize) -> String {
+    // GNU diff omits the count of single-line ranges and, for empty ranges,
+    // reports the line preceding the range.
+    match count {
+        0 => format!("{},0", start.saturating_sub(1)),
+        1 => format!("{start}"),
+        _ => format!("{start},{count}"),
+    }
+}
+
+fn find_hunks(lines: &[String], context_lines: usize) -> Vec<Hunk> {
+    let mut hunks: Vec<Hunk> = Vec::new();
+
+    for (index, _) in lines.iter().enumerate().filter(|(_, line)| is_change(line)) {
+        let start = index.saturating_sub(context_lines);
+        let end = (index + 1 + context_lines).min(lines.len());
+        match hunks.last_mut() {
+            // Merge the hunks whose context lines overlap or are adjacent.
+            Some(hunk) if start <= hunk.end => hunk.end = end,
+            _ => hunks.push(Hunk { start, end }),
+        }
+    }
+
+    hunks
+}
+
+/// The position of a value in the pretty-printed JSON files: its
+/// indentation, the key introducing it and whether it is followed by a
+/// comma in the first and in the second file.
+#[derive(Clone, Copy)]
+struct Slot<'a> {
+    depth: usize,
+    key: Option<&'a str>,
+    comma1: bool,
+    comma2: bool,
+}
+
+impl Slot<'_> {
+    /// Returns the indentation of the value, followed by its key if any.
+    fn prefix(&self) -> String {
+        let indent = "  ".repeat(self.depth);
+        match self.key {
+            Some(key) => format!("{indent}{}: ", Value::String(String::from(key))),
+            None => indent,
+        }
+    }
+
+    /// Returns the lines of `value` pretty-printed at this position.
+    fn lines(&self, value: &Value, comma: bool) -> Vec<String> {
+        let indent = "  ".repeat(self.depth);
+        let pretty = format!("{value:#}");
+        let mut lines: Vec<String> = pretty
+            .split('\n')
+            .enumerate()
+            .map(|(index, line)| {
+                if index == 0 {
+                    format!("{}{line}", self.prefix())
+                } else {
+                    format!("{indent}{line}")
+                }
+            })
+            .collect();
+        if comma {
+            if let Some(last) = lines.last_mut() {
+                last.push(',');
+            }
+        }
+        lines
+    }
+
+    /// Returns the position of a member or an item, whose comma depends on
+    /// whether it is the last one of the first and of the second file.
+    fn child<'a>(&self, key: Option<&'a str>, comma1: bool, comma2: bool) -> Slot<'a> {
+        Slot {
+            depth: self.depth + 1,
+            key,
+            comma1,
+            comma2,
+        }
+    }
+
+    /// Returns the opening line of a non-empty container.
+    fn open(&self, bracket: char) -> String {
+        format!("{}{bracket}", self.prefix())
+    }
+
+    /// Returns the closing lines of a non-empty container in both files.
+    fn close(&self, bracket: char) -> (String, String) {
+        let close = |comma: bool| {
+            format!(
+                "{}{bracket}{}",
+                "  ".repeat(self.depth),
+                if comma { "," } else { "" }
+            )
+        };
+        (close(self.comma1), close(self.comma2))
+    }
+}
+
+/// The lines of a unified diff, each prefixed by ` `, `-` or `+`.
+#[derive(Default)]
+struct Lines(Vec<String>);
+
+impl Lines {
+    fn push(&mut self, op: char, line: &str) {
+        self.0.push(format!("{op}{line}"));
+    }
+
+    fn removed(&mut self, value: &Value, slot: &Slot<'_>) {
+        for line in slot.lines(value, slot.comma1) {
+            self.push('-', &line);
+        }
+    }
+
+    fn added(&mut self, value: &Value, slot: &Slot<'_>) {
+        for line in slot.lines(value, slot.comma2) {
+            self.push('+', &line);
+        }
+    }
+
+    /// Returns the lines, the deleted lines of each run of changed lines
+    /// coming before the added ones, as in the output of GNU diff.
+    fn grouped(self) -> Vec<String> {
+        let mut lines = Vec::with_capacity(self.0.len());
+        let mut added = Vec::new();
+        for line in self.0 {
+            if line.starts_with('+') {
+                added.push(line);
+            } else {
+                if !line.starts_with('-') {
+                    lines.append(&mut added);
+                }
+                lines.push(line);
+            }
+        }
+        lines.append(&mut added);
+        lines
+    }
+
+    /// Pushes the lines of two values, which are unchanged if they are
+    /// equal. Values only differing by their trailing comma share all their
+    /// lines but the last one.
+    fn pair(&mut self, lines1: &[String], lines2: &[String]) {
+        let shared = if lines1 == lines2 {
+            lines1.len()
+        } else if lines1.len() == lines2.len()
+            && lines1[..lines1.len() - 1] == lines2[..lines2.len() - 1]
+        {
+            lines1.len() - 1
+        } else {
+            0
+        };
+        for line in &lines1[..shared] {
+            self.push(' ', line);
+        }
+        for line in &lines1[shared..] {
+            self.push('-', line);
+        }
+        for line in &lines2[shared..] {
+            self.push('+', line);
+        }
+    }
+
+    /// Pushes the lines of two values, aligned along their difference.
+    fn values(&mut self, value1: &Value, value2: &Value, node: &DiffNode, slot: &Slot<'_>) {
+        match (value1, value2, node) {
+            (Value::Object(obj1), Value::Object(obj2), DiffNode::Unchanged)
+                if !obj1.is_empty() && !obj2.is_empty() =>
+            {
+                self.objects(obj1, obj2, None, slot);
+            }
+            (Value::Object(obj1), Value::Object(obj2), DiffNode::Object { changed, .. })
+                if !obj1.is_empty() && !obj2.is_empty() =>
+            {
+                self.objects(obj1, obj2, Some(changed), slot);
+            }
+            (Value::Array(array1), Value::Array(array2), DiffNode::Array(ops))
+                if !array1.is_empty() && !array2.is_empty() =>
+            {
+                if self.arrays(array1, array2, ops, slot).is_none() {
+                    self.pair_values(value1, value2, slot);
+                }
+            }
+            (Value::Array(array1), Value::Array(array2), DiffNode::Unchanged)
+                if !array1.is_empty() && array1.len() == array2.len() =>
+            {
+                let ops: Vec<ArrayOp> = array1.iter().map(|_| ArrayOp::Unchanged(None)).collect();
+                if self.arrays(array1, array2, &ops, slot).is_none() {
+                    self.pair_values(value1, value2, slot);
+                }
+            }
+            _ => self.pair_values(value1, value2, slot),
+        }
+    }
+
+    fn pair_values(&mut self, value1: &Value, value2: &Value, slot: &Slot<'_>) {
+        self.pair(
+            &slot.lines(value1, slot.comma1),
+            &slot.lines(value2, slot.comma2),
+        );
+    }
+
+    fn objects(
+        &mut self,
+        obj1: &Map<String, Value>,
+        obj2: &Map<String, Value>,
+        changed: Option<&BTreeMap<String, DiffNode>>,
+        slot: &Slot<'_>,
+    ) {
+        self.push(' ', &slot.open('{'));
+        let names1: Vec<&String> = obj1.keys().collect();
+        let names2: Vec<&String> = obj2.keys().collect();
+        let (last1, last2) = (names1.len() - 1, names2.len() - 1);
+        // The shared keys found in another order in the second object are
+        // deleted, then added back where they are found.
+        let mut moved: BTreeSet<&str> = BTreeSet::new();
+        let (mut index1, mut index2) = (0, 0);
+        while index1 < names1.len() || index2 < names2.len() {
+            let key1 = names1.get(index1).copied();
+            let key2 = names2.get(index2).copied();
+            if let Some(key2) =
+                key2.filter(|key2| !obj1.contains_key(*key2) || moved.contains(key2.as_str()))
+            {
+                let child = slot.child(Some(key2), false, index2 < last2);
+                self.added(&obj2[key2], &child);
+                index2 += 1;
+            } else if let Some(key1) = key1 {
+                let child = slot.child(Some(key1), index1 < last1, index2 < last2);
+                if key2 == Some(key1) {
+                    let node = changed
+                        .and_then(|changed| changed.get(key1))
+                        .unwrap_or(&DiffNode::Unchanged);
+                    self.values(&obj1[key1], &obj2[key1], node, &child);
+                    index2 += 1;
+                } else {
+                    if obj2.contains_key(key1) {
+                        moved.insert(key1);
+                    }
+                    self.removed(&obj1[key1], &child);
+                }
+                index1 += 1;
+            }
+        }
+        let (close1, close2) = slot.close('}');
+        self.pair(&[close1], &[close2]);
+    }
+
+    /// Pushes the lines of two arrays, aligned along the operations of
+    /// their difference, or returns `None` if the operations do not match the
+    /// arrays, e.g. if some items are moved.
+    fn arrays(
+        &mut self,
+        array1: &[Value],
+        array2: &[Value],
+        ops: &[ArrayOp],
+        slot: &Slot<'_>,
+    ) -> Option<()> {
+        let (last1, last2) = (array1.len() - 1, array2.len() - 1);
+        let child = |index1: usize, index2: usize| slot.child(None, index1 < last1, index2 < last2);
+        let mut lines = Self::default();
+        lines.push(' ', &slot.open('['));
+        let (mut index1, mut index2) = (0, 0);
+        for op in ops {
+            match op {
+                ArrayOp::Unchanged(_) => {
+                    let child = child(index1, index2);
+                    lines.values(
+                        array1.get(index1)?,
+                        array2.get(index2)?,
+                        &DiffNode::Unchanged,
+                        &child,
+                    );
+                    index1 += 1;
+                    index2 += 1;
+                }
+                ArrayOp::Changed { diff, .. } => {
+                    let child = child(index1, index2);
+                    lines.values(array1.get(index1)?, array2.get(index2)?, diff, &child);
+                    index1 += 1;
+                    index2 += 1;
+                }
+                ArrayOp::Removed { .. } => {
+                    lines.removed(array1.get(index1)?, &child(index1, index2));
+                    index1 += 1;
+                }
+                ArrayOp::Added { .. } => {
+                    lines.added(array2.get(index2)?, &child(index1, index2));
+                    index2 += 1;
+                }
+                ArrayOp::Moved { .. } => return None,
+            }
+        }
+        if index1 < array1.len() || index2 < array2.len() {
+            return None;
+        }
+        let (close1, close2) = slot.close(']');
+        lines.pair(&[close1], &[close2]);
+        self.0.append(&mut lines.0);
+        Some(())
+    }
+}
+impl JsonDiff {
+    /// Returns the difference between the pretty-printed JSON files `json1`
+    /// and `json2` formatted as a GNU-style unified diff.
+    ///
+    /// The lines of the JSON files are aligned along the JSON structural
+    /// difference, which must be the difference of `json1` and `json2`,
+    /// rather than compared again. Values which are not aligned, e.g. arrays
+    /// with moved items, are deleted and added as a whole. Each run of
+    /// unchanged lines is collapsed to at most `context_lines` lines around
+    /// the changes, and the hunks whose context overlaps are merged
+    /// together. Every line, the last one included, is terminated by a
+    /// newline.
+    ///
+    /// If `None`, there is no JSON structural difference to be formatted.
+    #[must_use]
+    pub fn to_unified(&self, json1: &Value, json2: &Value, context_lines: usize) -> Option<String> {
+        self.diff.as_ref()?;
+        let slot = Slot {
+            depth: 0,
+            key: None,
+            comma1: false,
+            comma2: false,
+        };
+        let mut lines = Lines::default();
+        let node = self.tree().unwrap_or(DiffNode::Unchanged);
+        lines.values(json1, json2, &node, &slot);
+        let lines = lines.grouped();
+
+        // Line numbers of the old and new sides before each line.
+        let mut old_lines = Vec::with_capacity(lines.len() + 1);
+        let mut new_lines = Vec::with_capacity(lines.len() + 1);
+        let (mut old_line, mut new_line) = (1, 1);
+        for line in &lines {
+            old_lines.push(old_line);
+            new_lines.push(new_line);
+            if !line.starts_with('+') {
+                old_line += 1;
+            }
+            if !line.starts_with('-') {
+                new_line += 1;
+            }
+        }
+        old_lines.push(old_line);
+        new_lines.push(new_line);
+
+        let mut output = String::new();
+        for Hunk { start, end } in find_hunks(&lines, context_lines) {
+            let old_count = old_lines[end] - old_lines[start];
+            let new_count = new_lines[end] - new_lines[start];
+            // Writing into a `String` never fails.
+            let _ = writeln!(
+                output,
+                "@@ -{} +{} @@",
+                hunk_range(old_lines[start], old_count),
+                hunk_range(new_lines[start], new_count)
+            );
+            for line in &lines[start..end] {
+                output.push_str(line);
+                output.push('\n');
+            }
+        }
+
+        Some(output)
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+
+    use std::fs::File;
+    use std::io::BufReader;
+
+    use serde_json::Value;
+
+    use crate::diff::JsonDiff;
+    use crate::options::DiffOptions;
+
+    // Checks that the lines of each side of a unified diff with all the
+    // context lines are the pretty-printed JSON file.
+    fn assert_sides(json_diff: &JsonDiff, json1: &Value, json2: &Value) {
+        let unified = json_diff.to_unified(json1, json2, 1000).unwrap();
+        let side = |skipped: char| {
+            unified
+                .lines()
+                .filter(|line| !line.starts_with("@@") && !line.starts_with(skipped))
+                .map(|line| &line[1..])
+                .collect::<Vec<_>>()
+                .join("\n")
+        };
+        assert_eq!(side('+'), format!("{json1:#}"));
+        assert_eq!(side('-'), format!("{json2:#}"));
+    }
+
+    #[test]
+    fn test_to_unified() {
+        let json_diff = JsonDiff::diff(&json!(42), &json!(42), false);
+        assert_eq!(json_diff.to_unified(&json!(42), &json!(42), 3), None);
+
+        let (json1, json2) = (json!({"bar": 1, "foo": 42}), json!({"bar": 1, "foo": 10}));
+        let json_diff = JsonDiff::diff(&json1, &json2, false);
+        assert_eq!(
+            json_diff.to_unified(&json1, &json2, 3).unwrap(),
+            "@@ -1,4 +1,4 @@\n {\n   \"bar\": 1,\n-  \"foo\": 42\n+  \"foo\": 10\n }\n"
+        );
+        assert_eq!(
+            json_diff.to_unified(&json1, &json2, 0).unwrap(),
+            "@@ -3 +3 @@\n-  \"foo\": 42\n+  \"foo\": 10\n"
+        );
+
+        let (json1, json2) = (json!([1, 2, 3, 4, 5]), json!([1, 3, 4, 5]));
+        let json_diff = JsonDiff::diff(&json1, &json2, false);
+        assert_eq!(
+            json_diff.to_unified(&json1, &json2, 0).unwrap(),
+            "@@ -3 +2,0 @@\n-  2,\n"
+        );
+
+        // The trailing commas of the last items are changed too.
+        let (json1, json2) = (
+            json!([1, 2, 3, 4, 5, 6, 7, 8]),
+            json!([0, 1, 2, 3, 4, 5, 6, 7]),
+        );
+        let json_diff = JsonDiff::diff(&json1, &json2, false);
+        assert_eq!(
+            json_diff.to_unified(&json1, &json2, 1).unwrap(),
+            "@@ -1,2 +1,3 @@\n [\n+  0,\n   1,\n@@ -7,4 +8,3 @@\n   6,\n-  7,\n-  8\n+  7\n ]\n"
+        );
+        // Hunks whose context overlaps are merged.
+        assert_eq!(
+            json_diff.to_unified(&json1, &json2, 4).unwrap(),
+            concat!(
+                "@@ -1,10 +1,10 @@\n [\n+  0,\n   1,\n   2,\n   3,\n   4,\n   5,\n   6,\n",
+                "-  7,\n-  8\n+  7\n ]\n"
+            )
+        );
+        assert_sides(&json_diff, &json1, &json2);
+    }
+
+    #[test]
+    fn test_to_unified_sides() {
+        let json1 = json!({
+            "a": {"b": [1, {"c": 2}, []], "d": {}},
+            "e": "f",
+            "g": [{"h": 1}, {"h": 2}]
+        });
+        let json2 = json!({
+            "a": {"b": [1, {"c": 3}, [4]], "d": {"i": null}},
+            "g": [{"h": 2}],
+            "j": true
+        });
+        assert_sides(&JsonDiff::diff(&json1, &json2, false), &json1, &json2);
+
+        // Values which are not aligned are deleted and added as a whole.
+        let (json1, json2) = (json!([1, 2, 3, 4]), json!([4, 1, 2, 3]));
+        let options = DiffOptions {
+            detect_moves: true,
+            ..DiffOptions::default()
+        };
+        let json_diff = JsonDiff::diff_with_options(&json1, &json2, &options);
+        assert_eq!(
+            json_diff.to_unified(&json1, &json2, 0).unwrap(),
+            concat!(
+                "@@ -1,6 +1,6 @@\n",
+                "-[\n-  1,\n-  2,\n-  3,\n-  4\n-]\n",
+                "+[\n+  4,\n+  1,\n+  2,\n+  3\n+]\n"
+            )
+        );
+        assert_sides(&json_diff, &json1, &json2);
+
+        // The values compared as equal are changed if they differ.
+        let (json1, json2) = (json!({"a": 1, "b": 2}), json!({"a": 1, "b": 3}));
+        let json_diff = JsonDiff::diff(&json!({"a": 1}), &json!({"a": 2}), false);
+        assert_sides(&json_diff, &json1, &json2);
+    }
+
+    #[test]
+    fn test_to_unified_fixture() {
+        let read_json_file = |filename: &str| -> serde_json::Value {
+            let file = File::open(filename).unwrap();
+            serde_json::from_reader(BufReader::new(file)).unwrap()
+        };
+
+        let json1 = read_json_file("data/a.json");
+        let json2 = read_json_file("data/b.json");
+
+        let json_diff = JsonDiff::diff(&json1, &json2, false);
+        assert_eq!(
+            json_diff.to_unified(&json1, &json2, 3).unwrap(),
+            std::fs::read_to_string("data/result.udiff")
+                .unwrap()
+                .replace("\r\n", "\n")
+        );
+        assert_sides(&json_diff, &json1, &json2);
+    }
+}
@@ -0,0 +1,286 @@
+use alloc::format;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use serde_json::Value;
+
+use crate::budget::ChangeBudget;
+use crate::diff::JsonDiff;
+use crate::error::DiffError;
+use crate::flatten::ChangeKind;
+use crate::options::DiffOptions;
+use crate::stats::DiffStats;
+
+/// The items of two arrays paired regardless of their order.
+pub(crate) struct UnorderedPairs {
+    /// The index of every item of the first array in the second one, or
+    /// `None` if it has been deleted, with the difference between them.
+    pub(crate) matches: Vec<Option<(usize, JsonDiff)>>,
+    /// The indices of the items only present in the second array.
+    pub(crate) added: Vec<usize>,
+}
+
+impl JsonDiff {
+    /// Pairs the items of two arrays compared as multisets.
+    ///
+    /// Every item of the first array is paired, in order, with the first
+    /// unpaired item of the second array equal to it. Then, every remaining
+    /// object or array of the first array is paired with the most similar
+    /// remaining item of the same type, provided that their score is
+    /// greater than [`DiffOptions::array_match_threshold`]. Ties are broken
+    /// in favour of the lowest index in the second array. The objects
+    /// identified by [`DiffOptions::array_keys`] are only paired with the
+    /// first unpaired object of the same identity.
+    pub(crate) fn pair_unordered(
+        array1: &[Value],
+        array2: &[Value],
+        options: &DiffOptions,
+        path: &str,
+    ) -> Result<UnorderedPairs, DiffError> {
+        let mut matches: Vec<Option<(usize, Self)>> = Vec::new();
+        let mut paired = vec![false; array2.len()];
+        // The differences of the unpaired objects and arrays, computed while
+        // looking for an equal item, so that they are not computed again
+        // while looking for the most similar one.
+        let mut candidates: Vec<Vec<(usize, Self)>> = Vec::new();
+
+        for (index1, item1) in array1.iter().enumerate() {
+            let item_path = format!("{path}/{index1}");
+            let mut found = None;
+            let mut item_candidates = Vec::new();
+            if let Some(identity) = options.array_identity(item1) {
+                // An identified object is only paired with an object of the
+                // same identity, whatever their score.
+                let same_identity = |index2: &usize| {
+                    !paired[*index2]
+                        && options.array_identity(&array2[*index2]).as_ref() == Some(&identity)
+                };
+                if let Some(index2) = (0..array2.len()).find(same_identity) {
+                    paired[index2] = true;
+                    let json_diff =
+                        Self::diff_with_score(item1, &array2[index2], options, &item_path)?;
+                    found = Some((index2, json_diff));
+                }
+                matches.push(found);
+                candidates.push(item_candidates);
+                continue;
+            }
+            for (index2, item2) in array2.iter().enumerate() {
+                if paired[index2] {
+                    continue;
+                }
+                let json_diff = Self::diff_with_score(item1, item2, options, &item_path)?;
+                if json_diff.diff.is_none() {
+                    paired[index2] = true;
+                    found = Some((index2, json_diff));
+                    break;
+                }
+                if Self::are_same_containers(item1, item2) {
+                    item_candidates.push((index2, json_diff));
+                }
+            }
+            if found.is_some() {
+                item_candidates.clear();
+            }
+            matches.push(found);
+            candidates.push(item_candidates);
+        }
+
+        for (index1, item_candidates) in candidates.into_iter().enumerate() {
+            let mut best: Option<(usize, Self)> = None;
+            for (index2, json_diff) in item_candidates {
+                if !paired[index2]
+                    && json_diff.score > options.array_match_threshold
+                    && best
+                        .as_ref()
+                        .map_or(true, |(_, best)| json_diff.score > best.score)
+                {
+                    best = Some((index2, json_diff));
+                }
+            }
+            if let Some((index2, _)) = &best {
+                paired[*index2] = true;
+                matches[index1] = best;
+            }
+        }
+
+        let added = (0..array2.len()).filter(|index| !paired[*index]).collect();
+
+        Ok(UnorderedPairs { matches, added })
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    pub(crate) fn unordered_array_diff(
+        array1: &[Value],
+        array2: &[Value],
+        options: &DiffOptions,
+        path: &str,
+        budget: &ChangeBudget,
+    ) -> Result<Self, DiffError> {
+        let UnorderedPairs { matches, added } =
+            Self::pair_unordered(array1, array2, options, path)?;
+
+        let mut result: Vec<Value> = Vec::new();
+        let mut score: f64 = 0.;
+        let mut all_equal = true;
+        let mut similarity = 0.;
+        let mut entries: usize = 0;
+        let mut filtered = false;
+
+        for (index1, (item1, found)) in array1.iter().zip(matches).enumerate() {
+            if let Some((index2, json_diff)) = found {
+                // The pairs are scored with their whole changes, which are
+                // only then left out by the filter.
+                let change = json_diff.diff.map(|change| {
+                    let kept = budget.filter(change, &options.markers);
+                    filtered |= kept.is_none();
+                    kept
+                });
+                if let Some(Some(change)) = change {
+                    // The pairs are found before any change is reported, so
+                    // a change is reported whole or not at all.
+                    if budget.spend(DiffStats::count(&change, &options.markers)) {
+                        result.push(Self::change_entry(
+                            change,
+                            &[index1, index2],
+                            Some(json_diff.score),
+                            options,
+                        ));
+                    }
+                    all_equal = false;
+                } else if change.is_some() || item1.is_object() || item1.is_array() {
+                    result.push(json!([json!(' ')]));
+                } else {
+                    result.push(json!([json!(' '), item1]));
+                }
+                similarity += json_diff.similarity;
+                score += 10.;
+                entries += 1;
+            } else {
+                if budget.spend_on(ChangeKind::Removed, &mut filtered) {
+                    result.push(Self::array_entry('-', item1.clone(), &[index1], options));
+                }
+                all_equal = false;
+                score -= 5.;
+                entries += 1;
+            }
+        }
+
+        for index2 in added {
+            if budget.spend_on(ChangeKind::Added, &mut filtered) {
+                result.push(Self::array_entry(
+                    '+',
+                    array2[index2].clone(),
+                    &[index2],
+                    options,
+                ));
+            }
+            all_equal = false;
+            score -= 5.;
+            entries += 1;
+        }
+
+        let similarity = similarity / entries as f64;
+        if all_equal && !filtered {
+            Ok(Self::identical(100.))
+        } else if Self::has_changed_entries(&result) {
+            Ok(Self {
+                score: score.max(0.),
+                similarity,
+                diff: Some(Value::Array(result)),
+                truncated: false,
+                filtered: false,
+                markers: None,
+            })
+        } else if filtered {
+            Ok(Self::filtered(score.max(0.), similarity))
+        } else {
+            Ok(Self::identical(100.))
+        }
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+
+    use serde_json::Value;
+
+    use crate::diff::JsonDiff;
+    use crate::options::DiffOptions;
+    use crate::pointer::JsonPointer;
+    use crate::visit::DiffVisitor;
+
+    #[test]
+    fn test_array_unordered() {
+        let options = DiffOptions {
+            array_unordered: true,
+            ..DiffOptions::default()
+        };
+        let diff = |json1, json2| JsonDiff::diff_with_options(&json1, &json2, &options).diff;
+
+        assert_eq!(diff(json!([1, 2, 3]), json!([3, 2, 1])), None);
+        assert_eq!(
+            diff(json!([1, 2, 3]), json!([1, 2, 2])),
+            Some(json!([[" ", 1], [" ", 2], ["-", 3], ["+", 2]]))
+        );
+
+        // Objects are paired with their most similar counterpart.
+        assert_eq!(
+            diff(
+                json!([{"id": 1, "foo": 1}, {"id": 2, "foo": 2}, "bar"]),
+                json!(["bar", {"id": 2, "foo": 3}, {"id": 1, "foo": 1}])
+            ),
+            Some(json!([[" "], ["~", {"foo": {"__old": 2, "__new": 3}}], [" ", "bar"]]))
+        );
+
+        // Nested arrays are compared as multisets too.
+        assert_eq!(diff(json!([[1, 2], [3]]), json!([[3], [2, 1]])), None);
+
+        // Arrays are ordered by default.
+        assert!(JsonDiff::diff(&json!([1, 2, 3]), &json!([3, 2, 1]), false)
+            .diff
+            .is_some());
+    }
+
+    #[derive(Default)]
+    struct Paths(Vec<String>);
+
+    impl DiffVisitor for Paths {
+        fn on_added(&mut self, path: &JsonPointer, _value: &Value) {
+            self.0.push(path.to_string());
+        }
+
+        fn on_removed(&mut self, path: &JsonPointer, _value: &Value) {
+            self.0.push(path.to_string());
+        }
+    }
+
+    #[test]
+    fn test_unordered_paths() {
+        let options = DiffOptions {
+            unordered_paths: vec!["/meta/tags".to_owned()],
+            ..DiffOptions::default()
+        };
+        let json1 = json!({"meta": {"tags": ["a", "b", "c"], "changelog": ["v1", "v2"]}});
+        let json2 = json!({"meta": {"tags": ["c", "a", "b"], "changelog": ["v2", "v1"]}});
+
+        // Only the reorder of the ordered sibling is reported.
+        let json_diff = JsonDiff::diff_with_options(&json1, &json2, &options);
+        assert_eq!(
+            json_diff.diff,
+            Some(json!({"meta": {"changelog": [["+", "v2"], [" ", "v1"], ["-", "v2"]]}}))
+        );
+
+        let mut paths = Paths::default();
+        JsonDiff::visit(&json1, &json2, &options, &mut paths).unwrap();
+        assert_eq!(paths.0, ["/meta/changelog/0", "/meta/changelog/1"]);
+
+        // The path must match exactly.
+        let json1 = json!({"tags": [1, 2], "meta": {"tags": [1, 2]}});
+        let json2 = json!({"tags": [2, 1], "meta": {"tags": [2, 1]}});
+        assert_eq!(
+            JsonDiff::diff_with_options(&json1, &json2, &options).diff,
+            Some(json!({"tags": [["+", 2], [" ", 1], ["-", 2]]}))
+        );
+    }
+}
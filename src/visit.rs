@@ -0,0 +1,348 @@
+use serde_json::Value;
+
+use crate::diff::JsonDiff;
+use crate::error::DiffError;
+use crate::flatten::{Change, ChangeKind};
+use crate::options::DiffOptions;
+use crate::pointer::JsonPointer;
+
+/// Receives the operations of a JSON structural difference, in the order
+/// they appear in the difference.
+///
+/// Paths are JSON Pointers to the visited nodes, the empty one being the
+/// root, addressed as [`Change::path`] is.
+///
+/// Every method does nothing by default.
+#[allow(unused_variables)]
+pub trait DiffVisitor {
+    /// Called when `value` is only present in the new JSON file.
+    fn on_added(&mut self, path: &JsonPointer, value: &Value) {}
+
+    /// Called when `value` is only present in the old JSON file.
+    fn on_removed(&mut self, path: &JsonPointer, value: &Value) {}
+
+    /// Called when the scalar `old` has been replaced by `new`, or when
+    /// `old` and `new` differ beyond [`DiffOptions::max_depth`].
+    fn on_changed(&mut self, path: &JsonPointer, old: &Value, new: &Value) {}
+
+    /// Called when an array item has moved from the index `from` to the
+    /// index `to`, with [`DiffOptions::detect_moves`].
+    fn on_moved(&mut self, path: &JsonPointer, from: usize, to: usize) {}
+
+    /// Called when a key has been renamed to `new_key`, with
+    /// [`DiffOptions::detect_renames`]. The changes of its value follow.
+    fn on_renamed(&mut self, path: &JsonPointer, new_key: &str) {}
+}
+
+impl JsonDiff {
+    /// Finds the JSON structural difference of two JSON files using the
+    /// given options, as [`JsonDiff::try_diff_with_options`] does, then
+    /// visits its operations.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DiffError::TooDeep`] if a JSON file is nested deeper than
+    /// [`DiffOptions::max_nesting`].
+    pub fn visit(
+        json1: &Value,
+        json2: &Value,
+        options: &DiffOptions,
+        visitor: &mut impl DiffVisitor,
+    ) -> Result<(), DiffError> {
+        // The indices of the unordered arrays are only exact when the
+        // difference includes them.
+        let options = DiffOptions {
+            include_indices: true,
+            ..options.clone()
+        };
+        Self::try_diff_with_options(json1, json2, &options)?.walk(visitor);
+        Ok(())
+    }
+
+    /// Visits the operations of the JSON structural difference.
+    pub fn walk(&self, visitor: &mut impl DiffVisitor) {
+        let index = |value: Option<&Value>| {
+            value
+                .and_then(Value::as_u64)
+                .and_then(|index| usize::try_from(index).ok())
+        };
+        for Change {
+            path,
+            kind,
+            old,
+            new,
+        } in self.flatten()
+        {
+            match (kind, old, new) {
+                (ChangeKind::Added, _, Some(new)) => visitor.on_added(&path, &new),
+                (ChangeKind::Removed, Some(old), _) => visitor.on_removed(&path, &old),
+                (ChangeKind::Changed, Some(old), Some(new)) => {
+                    visitor.on_changed(&path, &old, &new);
+                }
+                (ChangeKind::Moved, old, new) => {
+                    if let (Some(from), Some(to)) = (index(old.as_ref()), index(new.as_ref())) {
+                        visitor.on_moved(&path, from, to);
+                    }
+                }
+                (ChangeKind::Renamed, _, Some(Value::String(new_key))) => {
+                    visitor.on_renamed(&path, &new_key);
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+
+    use std::fs::File;
+    use std::io::BufReader;
+
+    use serde_json::Value;
+
+    use super::DiffVisitor;
+    use crate::diff::JsonDiff;
+    use crate::filter::DiffFilter;
+    use crate::options::DiffOptions;
+    use crate::pointer::JsonPointer;
+
+    #[derive(Default)]
+    struct Recorder {
+        operations: Vec<String>,
+    }
+
+    impl DiffVisitor for Recorder {
+        fn on_added(&mut self, path: &JsonPointer, value: &Value) {
+            self.operations.push(format!("+ {path} {value}"));
+        }
+
+        fn on_removed(&mut self, path: &JsonPointer, value: &Value) {
+            self.operations.push(format!("- {path} {value}"));
+        }
+
+        fn on_changed(&mut self, path: &JsonPointer, old: &Value, new: &Value) {
+            self.operations.push(format!("~ {path} {old} {new}"));
+        }
+
+        fn on_moved(&mut self, path: &JsonPointer, from: usize, to: usize) {
+            self.operations.push(format!("> {path} {from} {to}"));
+        }
+
+        fn on_renamed(&mut self, path: &JsonPointer, new_key: &str) {
+            self.operations.push(format!("= {path} {new_key}"));
+        }
+    }
+
+    #[derive(Default)]
+    struct Counter {
+        added: usize,
+        removed: usize,
+    }
+
+    impl DiffVisitor for Counter {
+        fn on_added(&mut self, _path: &JsonPointer, _value: &Value) {
+            self.added += 1;
+        }
+
+        fn on_removed(&mut self, _path: &JsonPointer, _value: &Value) {
+            self.removed += 1;
+        }
+    }
+
+    // Counts the added and removed entries of a materialized difference.
+    fn count(diff: &Value, counter: &mut Counter) {
+        match diff {
+            Value::Object(obj) => {
+                for (key, value) in obj {
+                    if key.ends_with("__added") {
+                        counter.added += 1;
+                    } else if key.ends_with("__deleted") {
+                        counter.removed += 1;
+                    } else if key != "__old" && key != "__new" {
+                        count(value, counter);
+                    }
+                }
+            }
+            Value::Array(array) => {
+                for item in array {
+                    match (item[0].as_str(), item.get(1)) {
+                        (Some("+"), _) => counter.added += 1,
+                        (Some("-"), _) => counter.removed += 1,
+                        (Some("~"), Some(change)) => count(change, counter),
+                        _ => {}
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    #[test]
+    fn test_visit() {
+        let json1 = json!({"foo": 42, "bar": [1, 2, {"id": 1, "a": 1}], "baz": "old"});
+        let json2 = json!({"foo": 10, "bar": [2, 3, {"id": 1, "a": 2}], "qux": true});
+
+        let mut recorder = Recorder::default();
+        JsonDiff::visit(&json1, &json2, &DiffOptions::default(), &mut recorder).unwrap();
+        assert_eq!(
+            recorder.operations,
+            [
+                "- /bar/0 1",
+                "+ /bar/1 3",
+                "~ /bar/2/a 1 2",
+                "- /baz \"old\"",
+                "~ /foo 42 10",
+                "+ /qux true",
+            ]
+        );
+
+        let mut recorder = Recorder::default();
+        JsonDiff::visit(&json1, &json2, &DiffOptions::new(true), &mut recorder).unwrap();
+        assert_eq!(
+            recorder.operations,
+            ["- /bar/0 1", "+ /bar/1 3", "- /baz \"old\"", "+ /qux true"]
+        );
+
+        let mut recorder = Recorder::default();
+        let options = DiffOptions {
+            null_equals_missing: true,
+            ..DiffOptions::default()
+        };
+        JsonDiff::visit(
+            &json!({"a": null, "b": 1}),
+            &json!({"c": null}),
+            &options,
+            &mut recorder,
+        )
+        .unwrap();
+        assert_eq!(recorder.operations, ["- /b 1"]);
+
+        let mut recorder = Recorder::default();
+        let options = DiffOptions {
+            values_only: true,
+            ..DiffOptions::default()
+        };
+        JsonDiff::visit(
+            &json!({"a": 1, "b": 2}),
+            &json!({"a": 9, "c": 3}),
+            &options,
+            &mut recorder,
+        )
+        .unwrap();
+        assert_eq!(recorder.operations, ["~ /a 1 9"]);
+
+        // The items of a `replace` opcode beyond the shortest span.
+        let mut recorder = Recorder::default();
+        JsonDiff::visit(
+            &json!([0, 1, 2, 9]),
+            &json!([0, 3, 4, 5, 9]),
+            &DiffOptions::new(true),
+            &mut recorder,
+        )
+        .unwrap();
+        assert_eq!(recorder.operations, ["+ /3 5"]);
+
+        let mut recorder = Recorder::default();
+        let options = DiffOptions {
+            max_depth: Some(1),
+            ..DiffOptions::default()
+        };
+        JsonDiff::visit(&json1, &json2, &options, &mut recorder).unwrap();
+        assert_eq!(
+            recorder.operations,
+            [
+                "~ /bar [1,2,{\"a\":1,\"id\":1}] [2,3,{\"a\":2,\"id\":1}]",
+                "- /baz \"old\"",
+                "~ /foo 42 10",
+                "+ /qux true",
+            ]
+        );
+
+        // The strings containing embedded JSON are compared as JSON.
+        let options = DiffOptions {
+            parse_embedded_json: true,
+            ..DiffOptions::default()
+        };
+        let json1 = json!({"p": "{\"a\":1}"});
+        let mut recorder = Recorder::default();
+        JsonDiff::visit(&json1, &json!({"p": "{\"a\": 1}"}), &options, &mut recorder).unwrap();
+        assert!(recorder.operations.is_empty());
+        let mut recorder = Recorder::default();
+        JsonDiff::visit(&json1, &json!({"p": "{\"a\": 2}"}), &options, &mut recorder).unwrap();
+        assert_eq!(recorder.operations, ["~ /p/a 1 2"]);
+
+        // The options changing the reported operations are followed.
+        let options = DiffOptions {
+            filter: DiffFilter::AddedOnly,
+            ..DiffOptions::default()
+        };
+        let mut recorder = Recorder::default();
+        JsonDiff::visit(
+            &json!({"a": 1, "b": 2}),
+            &json!({"a": 9, "c": 3}),
+            &options,
+            &mut recorder,
+        )
+        .unwrap();
+        assert_eq!(recorder.operations, ["+ /c 3"]);
+    }
+
+    #[test]
+    fn test_visit_moves_and_renames() {
+        let options = DiffOptions {
+            detect_moves: true,
+            detect_renames: true,
+            ..DiffOptions::default()
+        };
+        let mut recorder = Recorder::default();
+        JsonDiff::visit(
+            &json!({"items": [1, 2, 3, 4], "name": {"a": 1, "b": 2, "c": 3}}),
+            &json!({"items": [2, 3, 1, 5], "title": {"a": 1, "b": 2, "c": 4}}),
+            &options,
+            &mut recorder,
+        )
+        .unwrap();
+        assert_eq!(
+            recorder.operations,
+            [
+                "- /items/3 4",
+                "> /items/0 0 2",
+                "+ /items/3 5",
+                "= /name title",
+                "~ /name/c 3 4",
+            ]
+        );
+
+        // The operations of a difference found earlier can be visited too.
+        let json_diff = JsonDiff::diff_with_options(&json!([1, 2]), &json!([2, 1]), &options);
+        let mut recorder = Recorder::default();
+        json_diff.walk(&mut recorder);
+        assert_eq!(recorder.operations, ["> /1 1 0"]);
+    }
+
+    #[test]
+    fn test_visit_fixtures() {
+        let read_json_file = |filename: &str| -> Value {
+            let file = File::open(filename).unwrap();
+            serde_json::from_reader(BufReader::new(file)).unwrap()
+        };
+
+        let json1 = read_json_file("data/a.json");
+        let json2 = read_json_file("data/b.json");
+
+        let mut counter = Counter::default();
+        JsonDiff::visit(&json1, &json2, &DiffOptions::default(), &mut counter).unwrap();
+
+        let mut expected = Counter::default();
+        count(
+            &JsonDiff::diff(&json1, &json2, false).diff.unwrap(),
+            &mut expected,
+        );
+        assert_eq!(
+            (counter.added, counter.removed),
+            (expected.added, expected.removed)
+        );
+        assert!(counter.added + counter.removed > 0);
+    }
+}
@@ -0,0 +1,56 @@
+//! Entry points which only exchange strings, so that they can be exported
+//! to JavaScript, e.g. with `wasm-bindgen`.
+//!
+//! They never panic, since panics abort WebAssembly modules.
+
+use alloc::format;
+use alloc::string::{String, ToString};
+
+use serde_json::Value;
+
+use crate::diff::JsonDiff;
+
+/// Parses two JSON strings and returns their JSON structural difference
+/// serialized as a JSON string.
+///
+/// If `Ok(None)`: the two JSON strings are identical.
+///
+/// # Errors
+///
+/// Returns a message describing the error if an input is not valid JSON,
+/// or if the difference cannot be computed.
+pub fn diff_json_strings(a: &str, b: &str, keys_only: bool) -> Result<Option<String>, String> {
+    let json1: Value =
+        serde_json::from_str(a).map_err(|err| format!("invalid first JSON: {err}"))?;
+    let json2: Value =
+        serde_json::from_str(b).map_err(|err| format!("invalid second JSON: {err}"))?;
+    JsonDiff::try_diff(&json1, &json2, keys_only)
+        .map_err(|err| err.to_string())?
+        .into_diff()
+        .map(|diff| serde_json::to_string(&diff).map_err(|err| err.to_string()))
+        .transpose()
+}
+
+#[cfg(test)]
+mod tests {
+
+    use alloc::string::String;
+
+    use super::diff_json_strings;
+
+    #[test]
+    fn test_diff_json_strings() {
+        assert_eq!(
+            diff_json_strings(r#"{"foo": 1}"#, r#"{"foo": 2}"#, false),
+            Ok(Some(String::from(r#"{"foo":{"__new":2,"__old":1}}"#)))
+        );
+        assert_eq!(
+            diff_json_strings(r#"{"foo": 1}"#, r#"{"foo": 2}"#, true),
+            Ok(None)
+        );
+        assert_eq!(diff_json_strings("[1, 2]", "[1, 2]", false), Ok(None));
+
+        let err = diff_json_strings("[1, 2]", "{", false).unwrap_err();
+        assert!(err.starts_with("invalid second JSON: "));
+    }
+}